@@ -1,6 +1,18 @@
 pub fn main() {
-    state_bc::compiler::compile("states/wl6.st", "src/out.img");
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=states/wl6.st");
     println!("cargo:rerun-if-changed=Cargo.lock");
+    match state_bc::compiler::compile("states/wl6.st", "src/out.img") {
+        Ok(output) => {
+            for source in &output.source_paths {
+                println!("cargo:rerun-if-changed={}", source.display());
+            }
+            for warning in &output.warnings.0 {
+                println!("cargo:warning={}", warning.short());
+            }
+        }
+        Err(diagnostics) => {
+            eprintln!("{diagnostics}");
+            std::process::exit(1);
+        }
+    }
 }
@@ -1,8 +1,240 @@
+#[cfg(feature = "compiler")]
+mod cli {
+    use state_bc::compiler::{self, diagnostic::Diagnostics, Artifact};
+    use std::{path::PathBuf, process::exit, time::SystemTime};
+
+    const USAGE: &str = "usage:
+    state_compiler check <input.st>
+    state_compiler build <input.st> [-o <out.img>] [--emit img,map,enums] [--map <path>] [--enums <path>]
+    state_compiler watch <input.st> [build options]
+    state_compiler fmt [--check] <file.st>...
+    state_compiler disasm <image> [<source.st>]";
+
+    fn usage_error(msg: &str) -> ! {
+        eprintln!("{msg}\n{USAGE}");
+        exit(2)
+    }
+
+    fn report(diagnostics: &Diagnostics) {
+        eprintln!("{diagnostics}");
+    }
+
+    struct BuildOptions {
+        input: String,
+        /// artifact and the path it is written to
+        artifacts: Vec<(Artifact, String)>,
+    }
+
+    impl BuildOptions {
+        fn parse(mut args: impl Iterator<Item = String>) -> BuildOptions {
+            let input = args
+                .next()
+                .unwrap_or_else(|| usage_error("missing input file"));
+            let mut outname = None;
+            let mut emit = Artifact::ALL.to_vec();
+            let mut paths = Vec::new();
+            while let Some(arg) = args.next() {
+                let mut value = || {
+                    args.next()
+                        .unwrap_or_else(|| usage_error(&format!("missing value for {arg}")))
+                };
+                match arg.as_str() {
+                    "-o" | "--out" => outname = Some(value()),
+                    "--emit" => {
+                        emit = value()
+                            .split(',')
+                            .map(|name| {
+                                Artifact::from_name(name).unwrap_or_else(|| {
+                                    usage_error(&format!("unknown artifact {name}"))
+                                })
+                            })
+                            .collect()
+                    }
+                    "--map" => paths.push((Artifact::Map, arg.clone(), value())),
+                    "--enums" => paths.push((Artifact::Enums, arg.clone(), value())),
+                    _ => usage_error(&format!("unknown option {arg}")),
+                }
+            }
+            // a path for an artifact that isn't written is most likely a mistake
+            for (artifact, option, _) in &paths {
+                if !emit.contains(artifact) {
+                    let name = option.trim_start_matches('-');
+                    usage_error(&format!("{option} needs {name} in --emit"));
+                }
+            }
+            let outname = outname.unwrap_or_else(|| {
+                PathBuf::from(&input)
+                    .with_extension("img")
+                    .display()
+                    .to_string()
+            });
+            let artifacts = emit
+                .into_iter()
+                .map(|artifact| {
+                    let path = paths
+                        .iter()
+                        .find(|(a, _, _)| *a == artifact)
+                        .map(|(_, _, path)| path.clone())
+                        .unwrap_or_else(|| artifact.default_path(&outname));
+                    (artifact, path)
+                })
+                .collect();
+            BuildOptions { input, artifacts }
+        }
+
+        /// Compile and write the artifacts. Returns the source files read, if successful.
+        fn build(&self) -> Option<Vec<PathBuf>> {
+            let output = match compiler::compile_file(&self.input) {
+                Ok(output) => output,
+                Err(diagnostics) => {
+                    report(&diagnostics);
+                    return None;
+                }
+            };
+            if !output.warnings.0.is_empty() {
+                report(&output.warnings);
+            }
+            println!(
+                "image {} bytes, {} unoptimized",
+                output.image.len(),
+                output.unoptimized_size
+            );
+            for (artifact, path) in &self.artifacts {
+                if let Err(err) = output.write_artifact(*artifact, path) {
+                    eprintln!("failed to write {path}: {err}");
+                    return None;
+                }
+                println!("wrote {path}");
+            }
+            Some(output.source_paths)
+        }
+    }
+
+    fn check(input: &str) -> bool {
+        match compiler::compile_file(input) {
+            Ok(output) => {
+                if !output.warnings.0.is_empty() {
+                    report(&output.warnings);
+                }
+                println!(
+                    "{input}: ok ({} files, image {} bytes, {} unoptimized)",
+                    output.source_paths.len(),
+                    output.image.len(),
+                    output.unoptimized_size
+                );
+                true
+            }
+            Err(diagnostics) => {
+                report(&diagnostics);
+                false
+            }
+        }
+    }
+
+    /// Reformat `files` in place, or with `check_only` list the ones not formatted. Returns true
+    /// if all files are (now) formatted.
+    fn fmt(files: &[String], check_only: bool) -> bool {
+        let mut ok = true;
+        for file in files {
+            let source = std::fs::read_to_string(file).unwrap_or_else(|err| {
+                eprintln!("failed to read {file}: {err}");
+                exit(1)
+            });
+            let formatted = compiler::format::format_source(&source);
+            if formatted == source {
+                continue;
+            }
+            if check_only {
+                println!("{file}: not formatted");
+                ok = false;
+            } else if let Err(err) = std::fs::write(file, formatted) {
+                eprintln!("failed to write {file}: {err}");
+                ok = false;
+            } else {
+                println!("formatted {file}");
+            }
+        }
+        ok
+    }
+
+    fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    /// Rebuild whenever one of the sources changes. Polls, so it works everywhere without
+    /// platform specific file notifications.
+    fn watch(options: &BuildOptions) -> ! {
+        let mut sources = vec![PathBuf::from(&options.input)];
+        loop {
+            // a failed build keeps watching the files of the last good one
+            if let Some(paths) = options.build() {
+                sources = paths;
+            }
+            println!("watching {} files", sources.len());
+            let stamps = modified(&sources);
+            while modified(&sources) == stamps {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+            }
+        }
+    }
+
+    fn disasm(mut args: impl Iterator<Item = String>) {
+        let imgname = args
+            .next()
+            .unwrap_or_else(|| usage_error("missing image file"));
+        let image = state_bc::ExecImage::load(&imgname).unwrap_or_else(|err| {
+            eprintln!("{err:#}");
+            exit(1)
+        });
+        let id_names = match args.next() {
+            Some(source) => compiler::state_id_names(&source).unwrap_or_else(|diagnostics| {
+                report(&diagnostics);
+                exit(1)
+            }),
+            None => Default::default(),
+        };
+        state_bc::disasm::disassemble(&image, &id_names, &mut std::io::stdout().lock())
+            .expect("disassembly failed");
+    }
+
+    pub fn main() {
+        let mut args = std::env::args().skip(1);
+        let command = args
+            .next()
+            .unwrap_or_else(|| usage_error("missing command"));
+        let ok = match command.as_str() {
+            "check" => check(
+                &args
+                    .next()
+                    .unwrap_or_else(|| usage_error("missing input file")),
+            ),
+            "build" => BuildOptions::parse(args).build().is_some(),
+            "watch" => watch(&BuildOptions::parse(args)),
+            "fmt" => {
+                let mut files: Vec<String> = args.collect();
+                let check_only = files.iter().any(|arg| arg == "--check");
+                files.retain(|arg| arg != "--check");
+                if files.is_empty() {
+                    usage_error("no files to format");
+                }
+                fmt(&files, check_only)
+            }
+            "disasm" => {
+                disasm(args);
+                true
+            }
+            _ => usage_error(&format!("unknown command {command}")),
+        };
+        if !ok {
+            exit(1);
+        }
+    }
+}
+
 fn main() {
     #[cfg(feature = "compiler")]
-    {
-        let filename = std::env::args().nth(1).expect("missing input file");
-        let outname = std::env::args().nth(2).expect("missing output file");
-        state_bc::compiler::compile(&filename, &outname);
-    }
+    cli::main();
 }
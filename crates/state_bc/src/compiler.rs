@@ -1,94 +1,847 @@
 use self::{
-    ast::ToplevelElement,
-    parser::{parse_toplevel, util::Span},
+    ast::{EnemyProperty, Pos, ToplevelElement},
+    diagnostic::{Diagnostic, Diagnostics, Reporter},
+    parser::{parse_toplevel_elements, util::Span},
+};
+use crate::{EnemyInfo, ExecImage, SpawnInfos};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
 };
-use crate::{opcode::Codegen, SpawnInfos};
-use nom::multi::many1;
-use std::{collections::BTreeMap, io::Write};
 
 pub mod ast;
 pub mod codegen;
+pub mod diagnostic;
+pub mod format;
+pub mod lint;
+pub mod lower;
+pub mod optimize;
 pub mod parser;
+pub mod template;
+
+/// Source files of a compile run, in the order they were read
+#[derive(Default)]
+struct SourceFiles {
+    paths: Vec<PathBuf>,
+    /// canonical paths, to detect repeated includes
+    seen: HashSet<PathBuf>,
+}
+
+impl SourceFiles {
+    /// Parse `path` and, recursively, the files it includes. The elements of an included file
+    /// replace the include. Every file is read only once, further includes of it (including
+    /// cycles) are ignored.
+    fn load(
+        &mut self,
+        path: &Path,
+        reporter: &mut Reporter,
+    ) -> std::io::Result<Vec<ToplevelElement>> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(self.parse(path, &source, reporter))
+    }
+
+    /// Like `load`, with the source of `path` already at hand
+    fn parse(
+        &mut self,
+        path: &Path,
+        source: &str,
+        reporter: &mut Reporter,
+    ) -> Vec<ToplevelElement> {
+        self.seen
+            .insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+        self.paths.push(path.to_path_buf());
+        let file = reporter.add_file(&path.display().to_string(), source);
+        let (toplevel_elements, errors) = parse_toplevel_elements(Span::new_extra(source, file));
+        for (pos, message) in errors {
+            reporter.error(pos, message);
+        }
+
+        let mut elements = Vec::new();
+        for tle in toplevel_elements {
+            let ToplevelElement::Include(include) = tle else {
+                elements.push(tle);
+                continue;
+            };
+            let include_path = path.parent().unwrap_or(Path::new("")).join(&include.path);
+            if let Ok(canonical) = std::fs::canonicalize(&include_path) {
+                if self.seen.contains(&canonical) {
+                    continue;
+                }
+            }
+            match self.load(&include_path, reporter) {
+                Ok(included) => elements.extend(included),
+                Err(err) => reporter.error(
+                    include.pos,
+                    format!("failed to read {}: {err}", include_path.display()),
+                ),
+            }
+        }
+        elements
+    }
+}
+
+/// Parse `filename` and all files it includes, with template instances expanded to states
+/// blocks. Also returns the paths of all files read.
+fn parse_file(
+    filename: &str,
+    reporter: &mut Reporter,
+) -> Result<(Vec<ToplevelElement>, Vec<PathBuf>), Diagnostics> {
+    let mut source_files = SourceFiles::default();
+    let toplevel_elements = source_files
+        .load(Path::new(filename), reporter)
+        .map_err(|err| {
+            Diagnostics(vec![Diagnostic::without_pos(
+                filename,
+                format!("failed to read input file: {err}"),
+            )])
+        })?;
+    let toplevel_elements = expand_templates(toplevel_elements, reporter);
+    Ok((toplevel_elements, source_files.paths))
+}
+
+fn expand_templates(
+    toplevel_elements: Vec<ToplevelElement>,
+    reporter: &mut Reporter,
+) -> Vec<ToplevelElement> {
+    let (templates, toplevel_elements) = template::take_templates(toplevel_elements, reporter);
+    toplevel_elements
+        .into_iter()
+        .filter_map(|tle| match tle {
+            ToplevelElement::StatesInstance(instance) => {
+                template::expand(&templates, instance, reporter).map(ToplevelElement::StatesBlock)
+            }
+            tle => Some(tle),
+        })
+        .collect()
+}
+
+/// Map state ids back to the enum names used by the states blocks in `filename`. Different enums
+/// share the same numeric ranges, so this only looks at names actually used as state ids.
+pub fn state_id_names(filename: &str) -> Result<HashMap<i32, String>, Diagnostics> {
+    let mut reporter = Reporter::default();
+    let (toplevel_elements, _) = parse_file(filename, &mut reporter)?;
+    reporter.finish()?;
+    let mut enums = HashMap::new();
+    let mut id_names = HashMap::new();
+    for tle in &toplevel_elements {
+        if let ToplevelElement::EnumDecl(enum_decl) = tle {
+            for (name, value) in &enum_decl.variants {
+                enums.insert(name.clone(), *value as i32);
+            }
+        }
+    }
+    for tle in &toplevel_elements {
+        if let ToplevelElement::StatesBlock(states_block) = tle {
+            for element in &states_block.elements {
+                if let ast::StatesBlockElement::State { id, .. } = element {
+                    if let Some(v) = enums.get(id) {
+                        id_names.insert(*v, id.clone());
+                    }
+                }
+            }
+        }
+    }
+    Ok(id_names)
+}
+
+/// What the checks of the states blocks need to know about a function besides its code
+#[derive(Default)]
+pub struct FunctionInfo {
+    /// host types whose traps the function uses
+    pub trap_hosts: BTreeSet<String>,
+    /// state labels loaded by `loadsl` or `goto`. They are resolved in the states block running
+    /// the function.
+    pub state_labels: Vec<(Pos, String)>,
+    /// native functions called, see `lint`
+    pub calls: BTreeSet<&'static str>,
+}
+
+/// Result of a successful compile run
+#[derive(Debug)]
+pub struct Output {
+    /// the image, see `ExecImage::from_bytes`
+    pub image: Vec<u8>,
+    /// state label pointers, as stored in the image
+    pub labels: BTreeMap<String, i32>,
+    pub enums: BTreeMap<String, usize>,
+    /// names of the named enums and their variants, in declaration order (variant i has value i)
+    pub named_enums: BTreeMap<String, Vec<String>>,
+    /// trap numbers by host type and name, from the `trap <host>.<name> = <number>` declarations
+    pub traps: BTreeMap<(String, String), u8>,
+    /// listing of the bytecode blocks: their ranges in the image and where they come from
+    pub map: String,
+    /// size of the image without the bytecode optimizer, for comparison
+    pub unoptimized_size: usize,
+    /// all source files read, starting with the root
+    pub source_paths: Vec<PathBuf>,
+    /// findings of the lints, see `lint`
+    pub warnings: Diagnostics,
+}
+
+impl Output {
+    pub fn exec_image(&self) -> crate::Result<ExecImage> {
+        ExecImage::from_bytes(&self.image)
+    }
+
+    /// Rust module with all enum values as `ENUM_NAMES`, a Rust enum per named enum, so the
+    /// game refers to sprites and sounds by the names used in the sources, and a `<Host>Trap`
+    /// enum per host type with the declared trap numbers
+    pub fn enums_source(&self) -> String {
+        let mut out = String::from("// generated by the state compiler, do not edit\n\n");
+        out.push_str(&format!(
+            "pub const ENUM_NAMES: [(&str, i32); {}] = [\n",
+            self.enums.len()
+        ));
+        for (name, id) in &self.enums {
+            out.push_str(&format!("    (\"{name}\", {id}),\n"));
+        }
+        out.push_str("];\n");
+        for (enum_name, variants) in &self.named_enums {
+            out.push_str(&rust_enum(enum_name, variants));
+        }
+        let mut hosts: BTreeMap<&str, Vec<(&str, u8)>> = BTreeMap::new();
+        for ((host, name), value) in &self.traps {
+            hosts.entry(host).or_default().push((name, *value));
+        }
+        for (host, traps) in hosts {
+            out.push_str(&trap_enum(host, traps));
+        }
+        out
+    }
+
+    /// Write the image to `outname`, along with `<outname>.rs` and `<outname>.map`. The image
+    /// is replaced atomically, so a running game never sees a partial one.
+    pub fn write(&self, outname: &str) -> std::io::Result<()> {
+        for artifact in Artifact::ALL {
+            self.write_artifact(artifact, &artifact.default_path(outname))?;
+        }
+        Ok(())
+    }
+
+    pub fn write_artifact(&self, artifact: Artifact, path: &str) -> std::io::Result<()> {
+        match artifact {
+            Artifact::Image => {
+                let tmp_path = format!("{path}.tmp");
+                std::fs::write(&tmp_path, &self.image)?;
+                std::fs::rename(tmp_path, path)
+            }
+            Artifact::Map => std::fs::write(path, &self.map),
+            Artifact::Enums => std::fs::write(path, self.enums_source()),
+        }
+    }
+}
+
+/// Files written for an `Output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    Image,
+    Map,
+    Enums,
+}
+
+impl Artifact {
+    pub const ALL: [Artifact; 3] = [Artifact::Image, Artifact::Map, Artifact::Enums];
+
+    pub fn from_name(name: &str) -> Option<Artifact> {
+        match name {
+            "img" => Some(Artifact::Image),
+            "map" => Some(Artifact::Map),
+            "enums" => Some(Artifact::Enums),
+            _ => None,
+        }
+    }
+
+    /// Path of the artifact next to the image `outname`
+    pub fn default_path(self, outname: &str) -> String {
+        match self {
+            Artifact::Image => outname.to_string(),
+            Artifact::Map => format!("{outname}.map"),
+            Artifact::Enums => format!("{outname}.rs"),
+        }
+    }
+}
+
+/// Compile `filename` into the image `outname` (see `Output::write`). All errors found in the
+/// sources are returned at once, nothing is written in this case. On success the output is
+/// returned, e.g. to track its `source_paths` as build dependencies and to show the warnings.
+pub fn compile(filename: &str, outname: &str) -> Result<Output, Diagnostics> {
+    let output = compile_file(filename)?;
+    output.write(outname).map_err(|err| {
+        Diagnostics(vec![Diagnostic::without_pos(
+            filename,
+            format!("failed to write {outname}: {err}"),
+        )])
+    })?;
+    Ok(output)
+}
+
+/// Compile `filename` and the files it includes
+pub fn compile_file(filename: &str) -> Result<Output, Diagnostics> {
+    let mut reporter = Reporter::default();
+    let (toplevel_elements, source_paths) = parse_file(filename, &mut reporter)?;
+    compile_elements(filename, toplevel_elements, source_paths, reporter)
+}
+
+/// Compile `source` without reading it from a file. `name` is used in diagnostics and includes
+/// are resolved relative to it, a source without includes doesn't touch the filesystem.
+pub fn compile_source(name: &str, source: &str) -> Result<Output, Diagnostics> {
+    let mut reporter = Reporter::default();
+    let mut source_files = SourceFiles::default();
+    let toplevel_elements = source_files.parse(Path::new(name), source, &mut reporter);
+    let toplevel_elements = expand_templates(toplevel_elements, &mut reporter);
+    compile_elements(name, toplevel_elements, source_files.paths, reporter)
+}
+
+fn rust_enum(enum_name: &str, variants: &[String]) -> String {
+    let n = variants.len();
+    let mut out = format!(
+        "
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum {enum_name} {{
+"
+    );
+    for (id, variant) in variants.iter().enumerate() {
+        out.push_str(&format!("    {variant} = {id},\n"));
+    }
+    out.push_str(&format!(
+        "}}
+
+impl {enum_name} {{
+    pub const ALL: [{enum_name}; {n}] = [
+"
+    ));
+    for variant in variants {
+        out.push_str(&format!("        {enum_name}::{variant},\n"));
+    }
+    out.push_str(&format!(
+        "    ];\n    pub const NAMES: [&'static str; {n}] = [\n"
+    ));
+    for variant in variants {
+        out.push_str(&format!("        \"{variant}\",\n"));
+    }
+    out.push_str(&format!(
+        "    ];
+
+    pub const fn id(self) -> i32 {{
+        self as i32
+    }}
+    pub fn from_id(id: i32) -> Option<{enum_name}> {{
+        Self::ALL.get(usize::try_from(id).ok()?).copied()
+    }}
+    pub fn name(self) -> &'static str {{
+        Self::NAMES[self as usize]
+    }}
+    pub fn from_name(name: &str) -> Option<{enum_name}> {{
+        let index = Self::NAMES.iter().position(|n| *n == name)?;
+        Some(Self::ALL[index])
+    }}
+}}
+"
+    ));
+    out
+}
+
+/// `play_random_sound` -> `PlayRandomSound`
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
 
-pub fn compile(filename: &str, outname: &str) {
-    let input = std::fs::read_to_string(filename).expect("failed to read input file");
-    let input = Span::new(&input);
+/// Typed trap numbers of `host`, the `TrapHandler::Trap` of its Rust side
+fn trap_enum(host: &str, mut traps: Vec<(&str, u8)>) -> String {
+    traps.sort_by_key(|(_, value)| *value);
+    let enum_name = format!("{}Trap", camel_case(host));
+    let mut out = format!(
+        "
+/// Traps declared with `trap {host}.<name> = <number>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum {enum_name} {{
+"
+    );
+    for (name, value) in &traps {
+        out.push_str(&format!("    {} = {value},\n", camel_case(name)));
+    }
+    out.push_str(&format!(
+        "}}
 
-    let (_input, toplevel_elements) =
-        many1(parse_toplevel)(input).expect("failed to parse toplevel elements");
-    // println!("elements: {toplevel_elements:?}");
+impl TryFrom<u8> for {enum_name} {{
+    type Error = u8;
+
+    fn try_from(value: u8) -> ::std::result::Result<Self, u8> {{
+        match value {{
+"
+    ));
+    for (name, value) in &traps {
+        out.push_str(&format!(
+            "            {value} => Ok({enum_name}::{}),\n",
+            camel_case(name)
+        ));
+    }
+    out.push_str(
+        "            x => Err(x),
+        }
+    }
+}
+",
+    );
+    out
+}
+
+fn compile_elements(
+    filename: &str,
+    toplevel_elements: Vec<ToplevelElement>,
+    source_paths: Vec<PathBuf>,
+    mut reporter: Reporter,
+) -> Result<Output, Diagnostics> {
+    if reporter.has_errors() {
+        // skipped elements would only cause follow-up errors
+        return Err(reporter.finish().unwrap_err());
+    }
 
     let mut enums = BTreeMap::new();
+    let mut named_enums = BTreeMap::new();
     let mut state_blocks = Vec::new();
     let mut spawn_infos = Vec::new();
+    let mut enemy_infos: Vec<EnemyInfo> = Vec::new();
     let mut function_blocks = Vec::new();
+    let mut traps = BTreeMap::new();
+    let mut variadic_traps = BTreeSet::new();
+    let mut drops = Vec::new();
+    let mut entries = Vec::new();
     for tle in toplevel_elements {
         match tle {
-            ToplevelElement::EnumDecl(mut enum_decl) => {
-                for (i, name) in enum_decl.names.drain(..).enumerate() {
-                    enums.insert(name, i);
+            ToplevelElement::EnumDecl(enum_decl) => {
+                for (name, value) in &enum_decl.variants {
+                    enums.insert(name.clone(), *value);
+                }
+                if let Some(name) = enum_decl.name {
+                    if named_enums.contains_key(&name) {
+                        reporter.error(enum_decl.pos, format!("duplicate enum {name}"));
+                    }
+                    // the generated Rust enum looks variants up by value
+                    if enum_decl
+                        .variants
+                        .iter()
+                        .enumerate()
+                        .any(|(i, (_, v))| i != *v)
+                    {
+                        reporter.error(
+                            enum_decl.pos,
+                            format!(
+                                "named enum {name} can't set values, its variants count from 0"
+                            ),
+                        );
+                    }
+                    let variants = enum_decl.variants.into_iter().map(|(name, _)| name);
+                    named_enums.insert(name, variants.collect());
                 }
             }
             ToplevelElement::StatesBlock(state_block) => state_blocks.push(state_block),
             ToplevelElement::SpawnBlock(spawn_block) => {
-                for mut spawn_info in spawn_block.infos {
+                for (pos, property) in &spawn_block.properties {
+                    if let EnemyProperty::Drop(name) = property {
+                        drops.push((*pos, enemy_infos.len(), name.clone()));
+                    }
+                }
+                if enemy_infos.iter().any(|info| info.name == spawn_block.name) {
+                    reporter.error(
+                        spawn_block.pos,
+                        format!("duplicate spawn block {}", spawn_block.name),
+                    );
+                }
+                enemy_infos.push(enemy_info(&spawn_block, &mut reporter));
+                for (pos, mut spawn_info) in spawn_block.infos {
                     spawn_info.state = format!("{}::{}", spawn_block.name, spawn_info.state);
-                    spawn_infos.push(spawn_info);
+                    spawn_info.enemy.clone_from(&spawn_block.name);
+                    spawn_infos.push((pos, spawn_info));
                 }
             }
             ToplevelElement::FunctionBlock(function_block) => function_blocks.push(function_block),
+            ToplevelElement::EntryDecl(entry_decl) => entries.push(entry_decl),
+            ToplevelElement::TemplateDecl(_)
+            | ToplevelElement::StatesInstance(_)
+            | ToplevelElement::Include(_) => {
+                unreachable!("includes and templates are resolved by parse_file")
+            }
+            ToplevelElement::TrapDecl(trap_decl) => {
+                let key = (trap_decl.host, trap_decl.name);
+                let same_number = traps
+                    .iter()
+                    .find(|((host, _), value)| *host == key.0 && **value == trap_decl.value);
+                if let Some(((_, other), _)) = same_number {
+                    reporter.error(
+                        trap_decl.pos,
+                        format!(
+                            "trap {}.{} has the same number {} as {}.{other}",
+                            key.0, key.1, trap_decl.value, key.0
+                        ),
+                    );
+                }
+                if trap_decl.variadic {
+                    variadic_traps.insert(key.clone());
+                }
+                if traps.insert(key.clone(), trap_decl.value).is_some() {
+                    reporter.error(
+                        trap_decl.pos,
+                        format!("duplicate trap declaration {}.{}", key.0, key.1),
+                    );
+                }
+            }
+        }
+    }
+
+    for (pos, index, name) in drops {
+        match enums.get(&name) {
+            Some(v) => enemy_infos[index].drop = Some(*v as i32),
+            None => reporter.error(pos, format!("unknown enum {name} for drop")),
         }
     }
 
     let mut functions = BTreeMap::new();
-    for function_block in function_blocks {
-        let mut codegen = Codegen::default().with_annotation("source", &function_block.name);
-        for element in function_block.elements {
-            codegen = match element {
-                ast::FunctionBlockElement::Label(label) => codegen.label(&label),
-                ast::FunctionBlockElement::LoadiI32 { value } => codegen.loadi_i32(value),
-                ast::FunctionBlockElement::LoadiU8Enum { name } => {
-                    let v = enums
-                        .get(&name)
-                        .unwrap_or_else(|| panic!("could not find enum {name}"));
-                    codegen.loadi_u8(*v as u8)
-                }
-                ast::FunctionBlockElement::LoadiU8 { value } => codegen.loadi_u8(value),
-                ast::FunctionBlockElement::Trap => codegen.trap(),
-                ast::FunctionBlockElement::Add => codegen.add(),
-                ast::FunctionBlockElement::FunctionCall => codegen.call(),
-                ast::FunctionBlockElement::Ceq => codegen.ceq(),
-                ast::FunctionBlockElement::Not => codegen.bin_not(),
-                ast::FunctionBlockElement::Jrc { label } => codegen.jrc_label(&label),
-                ast::FunctionBlockElement::Stop => codegen.stop(),
-                ast::FunctionBlockElement::GoState => codegen.gostate(),
-                ast::FunctionBlockElement::LoadStateLabel { label } => codegen.loadsl(&label),
-            };
+    let mut function_infos = BTreeMap::new();
+    let symbols = lower::Symbols {
+        enums: &enums,
+        traps: &traps,
+        variadic_traps: &variadic_traps,
+    };
+    for function_block in &function_blocks {
+        let (codegen, info) = lower::lower_function(&mut reporter, &symbols, function_block);
+        functions.insert(function_block.name.clone(), codegen);
+        function_infos.insert(function_block.name.clone(), info);
+    }
+    check_state_blocks(&mut reporter, &state_blocks, &enums, &function_infos);
+    let label_names: HashSet<_> = state_blocks
+        .iter()
+        .flat_map(|state_block| {
+            state_block
+                .elements
+                .iter()
+                .filter_map(|element| match element {
+                    ast::StatesBlockElement::Label(label) => {
+                        Some(format!("{}::{}", state_block.name, label))
+                    }
+                    _ => None,
+                })
+        })
+        .collect();
+    for (pos, spawn_info) in &spawn_infos {
+        if !label_names.contains(&spawn_info.state) {
+            reporter.error(*pos, format!("unknown spawn state {}", spawn_info.state));
         }
-        functions.insert(function_block.name.clone(), codegen.stop());
     }
-    {
-        let mut enum_file = std::fs::File::create(format!("{outname}.enums")).unwrap();
-        // write!(enum_file, "{enums:?}").unwrap();
-        let _ = writeln!(
-            enum_file,
-            "const ENUM_NAMES: [(&str, i32); {}] = [",
-            enums.len()
+    if !reporter.has_errors() {
+        lint::lint(
+            &mut reporter,
+            &state_blocks,
+            &function_blocks,
+            &function_infos,
+            &entries,
+            &spawn_infos,
+            &enemy_infos,
         );
-        // let _ = writeln!(enum_file, "[");
-        for (name, id) in enums.iter() {
-            let _ = write!(enum_file, "(\"{name}\", {id}), ");
-        }
-        let _ = write!(enum_file, "\n];");
     }
-    // std::fs::rename(from, to)
-    let tmp_outname = format!("{}.tmp", outname);
-    codegen::codegen(
-        &tmp_outname,
+    let warnings = reporter.finish()?;
+
+    let spawn_infos = spawn_infos.into_iter().map(|(_, info)| info).collect();
+    match codegen::codegen(
         &state_blocks,
         &enums,
         &functions,
-        &SpawnInfos { spawn_infos },
+        &SpawnInfos {
+            spawn_infos,
+            enemy_infos,
+        },
+    ) {
+        Ok(codegen_output) => Ok(Output {
+            image: codegen_output.image,
+            labels: codegen_output.labels,
+            enums,
+            named_enums,
+            traps,
+            map: codegen_output.map,
+            unoptimized_size: codegen_output.unoptimized_size,
+            source_paths,
+            warnings,
+        }),
+        Err(err) => Err(Diagnostics(vec![Diagnostic::without_pos(
+            filename,
+            format!("{err:#}"),
+        )])),
+    }
+}
+
+/// Enemy properties declared by a spawn block, defaults for the missing ones
+fn enemy_info(spawn_block: &ast::SpawnBlock, reporter: &mut Reporter) -> EnemyInfo {
+    let mut info = EnemyInfo::new(&spawn_block.name);
+    let mut declared = HashSet::new();
+    for (pos, property) in &spawn_block.properties {
+        let keyword = property.keyword();
+        if !declared.insert(keyword) {
+            reporter.error(*pos, format!("duplicate property {keyword}"));
+        }
+        let valid = match *property {
+            EnemyProperty::Health(health) => {
+                info.health = health;
+                health.iter().all(|h| *h > 0)
+            }
+            EnemyProperty::CanOpenDoors(can_open_doors) => {
+                info.can_open_doors = can_open_doors;
+                true
+            }
+            // resolved by `compile_elements` once all enums are known
+            EnemyProperty::Drop(_) => true,
+            EnemyProperty::Speed { path, chase } => {
+                info.path_speed = path;
+                info.chase_speed = chase;
+                path > 0 && chase > 0
+            }
+            EnemyProperty::Points(points) => {
+                info.points = points;
+                points >= 0
+            }
+            EnemyProperty::Pain(pain) => {
+                info.pain = pain;
+                true
+            }
+        };
+        if !valid {
+            reporter.error(*pos, format!("{keyword} out of range"));
+        }
+    }
+    info
+}
+
+/// Check the references of all states: state ids, next labels and the think / action functions.
+///
+/// Functions are also checked against the states blocks running them: they may only use traps of
+/// the host type the block is bound to (trap numbers overlap between hosts, so a mismatch would
+/// silently do the wrong thing at runtime) and the state labels they load must exist in the block.
+fn check_state_blocks(
+    reporter: &mut Reporter,
+    state_blocks: &[ast::StatesBlock],
+    enums: &BTreeMap<String, usize>,
+    function_infos: &BTreeMap<String, FunctionInfo>,
+) {
+    for state_block in state_blocks {
+        let labels: HashSet<&str> = state_block
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                ast::StatesBlockElement::Label(label) => Some(label.as_str()),
+                _ => None,
+            })
+            .collect();
+        for element in &state_block.elements {
+            let ast::StatesBlockElement::State {
+                id,
+                think,
+                action,
+                next,
+                pos,
+                ..
+            } = element
+            else {
+                continue;
+            };
+            if !enums.contains_key(id) {
+                reporter.error(*pos, format!("unknown identifier {id}"));
+            }
+            if next != "next" && !labels.contains(next.as_str()) {
+                reporter.error(
+                    *pos,
+                    format!("unknown label {next} in states block {}", state_block.name),
+                );
+            }
+            for function in [think, action] {
+                let Some(info) = function_infos.get(function) else {
+                    reporter.error(*pos, format!("unknown function {function}"));
+                    continue;
+                };
+                for host in &info.trap_hosts {
+                    match &state_block.host {
+                        Some(block_host) if block_host == host => (),
+                        Some(block_host) => reporter.error(
+                            *pos,
+                            format!(
+                                "function {function} uses {host} traps but runs in states block {} of host type {block_host}",
+                                state_block.name
+                            ),
+                        ),
+                        None => reporter.error(
+                            *pos,
+                            format!(
+                                "function {function} uses {host} traps but states block {} has no host type (use 'states {}: {host}')",
+                                state_block.name, state_block.name
+                            ),
+                        ),
+                    }
+                }
+                for (label_pos, label) in &info.state_labels {
+                    if !labels.contains(label.as_str()) {
+                        reporter.error(
+                            *label_pos,
+                            format!(
+                                "unknown state label {label} (function {function} runs in states block {})",
+                                state_block.name
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_include() {
+    let dir = std::env::temp_dir().join(format!("state_bc_include_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(
+        dir.join("main.st"),
+        "include \"sub/a.st\"\ninclude \"sub/b.st\"\nenum { MAIN }\n",
+    )
+    .unwrap();
+    // relative to the including file, b.st is only read once
+    std::fs::write(dir.join("sub/a.st"), "include \"b.st\"\nenum { A }\n").unwrap();
+    std::fs::write(dir.join("sub/b.st"), "enum { B }\nbroken\n").unwrap();
+
+    let mut reporter = Reporter::default();
+    let (elements, paths) =
+        parse_file(dir.join("main.st").to_str().unwrap(), &mut reporter).unwrap();
+    let names: Vec<_> = elements
+        .iter()
+        .filter_map(|tle| match tle {
+            ToplevelElement::EnumDecl(enum_decl) => Some(enum_decl.variants[0].0.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["B", "A", "MAIN"]);
+    assert_eq!(paths.len(), 3);
+    let diagnostics = reporter.finish().unwrap_err();
+    assert_eq!(diagnostics.0.len(), 1);
+    assert!(diagnostics.0[0].file.ends_with("b.st"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_compile_source() {
+    let source = "enum Sprite { SPR_A, SPR_B, }
+states door: door {
+closed:
+    state SPR_B, false, 10, Check, Check, closed
+}
+function Check: door {
+    return
+}
+";
+    let output = compile_source("inline.st", source).unwrap();
+    assert_eq!(output.enums.get("SPR_B"), Some(&1));
+    assert_eq!(output.enums.len(), 2);
+    assert!(output.labels.contains_key("door::closed"));
+    let enums_source = output.enums_source();
+    assert!(enums_source.contains("(\"SPR_A\", 0)"));
+    assert!(enums_source.contains("pub enum Sprite {\n    SPR_A = 0,\n    SPR_B = 1,\n}"));
+    assert!(!enums_source.contains("Trap"));
+    assert_eq!(output.source_paths.len(), 1);
+    let image = std::rc::Rc::new(output.exec_image().unwrap());
+    let ctx = crate::ExecCtx::new("door::closed", image).unwrap();
+    assert_eq!(ctx.state.id, 1);
+
+    let diagnostics = compile_source("inline.st", "states door: door {\n").unwrap_err();
+    assert_eq!(diagnostics.0[0].file, "inline.st");
+}
+
+#[test]
+fn test_trap_enums() {
+    let source = "trap door.get_open = 0
+trap door.play_sound = 4
+trap enemy.play_random_sound = 0
+";
+    let enums_source = compile_source("inline.st", source).unwrap().enums_source();
+    assert!(enums_source.contains("pub enum DoorTrap {\n    GetOpen = 0,\n    PlaySound = 4,\n}"));
+    assert!(enums_source.contains("4 => Ok(DoorTrap::PlaySound),"));
+    assert!(enums_source.contains("pub enum EnemyTrap {\n    PlayRandomSound = 0,\n}"));
+
+    let diagnostics =
+        compile_source("inline.st", "trap door.a = 1\ntrap door.b = 1\n").unwrap_err();
+    assert_eq!(
+        diagnostics.0[0].message,
+        "trap door.b has the same number 1 as door.a"
+    );
+
+    let source = "function Raw: door {\n    loadiu8 0\n    trap\n}\n";
+    let diagnostics = compile_source("inline.st", source).unwrap_err();
+    assert_eq!(
+        diagnostics.0[0].message,
+        "raw trap in function Raw of host type door, use 'trap door.<name>'"
+    );
+}
+
+#[test]
+fn test_enum_values_and_drop() {
+    let source = "enum { SPR_A, MAP_X = 40, MAP_Y, }
+spawn guard {
+    drop MAP_Y
+    undirectional 134, stand
+}
+states guard: enemy {
+stand:
+    state SPR_A, false, 0, None, None, stand
+}
+function None: enemy {
+    return
+}
+";
+    let output = compile_source("inline.st", source).unwrap();
+    assert_eq!(output.enums.get("SPR_A"), Some(&0));
+    assert_eq!(output.enums.get("MAP_Y"), Some(&41));
+    let image = output.exec_image().unwrap();
+    assert_eq!(image.spawn_infos.enemy_infos[0].drop, Some(41));
+
+    let source = source.replace("drop MAP_Y", "drop MAP_Z");
+    let diagnostics = compile_source("inline.st", &source).unwrap_err();
+    assert_eq!(diagnostics.0[0].message, "unknown enum MAP_Z for drop");
+
+    let diagnostics = compile_source("inline.st", "enum Sprite { SPR_A = 1 }\n").unwrap_err();
+    assert_eq!(
+        diagnostics.0[0].message,
+        "named enum Sprite can't set values, its variants count from 0"
     );
-    std::fs::rename(tmp_outname, outname).unwrap();
+}
+
+#[test]
+fn test_image_header() {
+    let source = "enum { SPR_A }
+states door: door {
+closed:
+    state SPR_A, false, 10, Check, Check, closed
+}
+function Check: door {
+    return
+}
+";
+    let output = compile_source("inline.st", source).unwrap();
+    let image = output.exec_image().unwrap();
+    assert_eq!(image.header.version, crate::IMAGE_VERSION);
+    assert_eq!(image.header.enum_hash, crate::enum_hash([("SPR_A", 0)]));
+    assert_ne!(image.header.enum_hash, crate::enum_hash([("SPR_A", 1)]));
+
+    let err = |bytes: &[u8]| ExecImage::from_bytes(bytes).unwrap_err().to_string();
+    let mut bytes = output.image.clone();
+    *bytes.last_mut().unwrap() ^= 1;
+    assert!(err(&bytes).contains("corrupt"));
+    let mut bytes = output.image.clone();
+    bytes[4] += 1;
+    assert!(err(&bytes).contains("version"));
+    assert!(err(b"garbage, not an image").contains("not a state image"));
 }
@@ -1,4 +1,13 @@
-use crate::EnemySpawnInfo;
+use crate::{EnemySpawnInfo, PainBehavior};
+
+/// Location of an element in the source file (1-based)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Pos {
+    /// index of the source file, see `diagnostic::Reporter`
+    pub file: usize,
+    pub line: u32,
+    pub column: usize,
+}
 
 #[derive(Debug)]
 pub enum ToplevelElement {
@@ -6,14 +15,30 @@ pub enum ToplevelElement {
     StatesBlock(StatesBlock),
     SpawnBlock(SpawnBlock),
     FunctionBlock(FunctionBlock),
+    TrapDecl(TrapDecl),
+    EntryDecl(EntryDecl),
+    TemplateDecl(TemplateDecl),
+    StatesInstance(StatesInstance),
+    Include(Include),
 }
 
+/// `include "<path>"`: elements of another file, path relative to the including file
 #[derive(Debug)]
-pub struct EnumDecl {
-    pub names: Vec<String>,
+pub struct Include {
+    pub path: String,
+    pub pos: Pos,
 }
 
 #[derive(Debug)]
+pub struct EnumDecl {
+    /// named enums also become a Rust enum in the generated module (see `Output::enums_source`)
+    pub name: Option<String>,
+    /// names and values, `<name> = <value>` or the previous value + 1
+    pub variants: Vec<(String, usize)>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone)]
 pub enum StatesBlockElement {
     Label(String),
     State {
@@ -23,41 +48,257 @@ pub enum StatesBlockElement {
         think: String,
         action: String,
         next: String,
+        pos: Pos,
     },
 }
 
+/// `trap <host>.<name>[(..)] = <value>`: names a trap number of a host type
+#[derive(Debug)]
+pub struct TrapDecl {
+    pub host: String,
+    pub name: String,
+    pub value: u8,
+    /// `(..)`: takes any number of arguments, calls push the argument count as u8 after them
+    pub variadic: bool,
+    pub pos: Pos,
+}
+
+/// `entry <host>.<label> [if <condition>, ..]`: a state label the game jumps to in the states
+/// blocks of a host type. Without conditions every block of the host needs it, otherwise only
+/// the ones meeting any of them. See `lint`.
+#[derive(Debug)]
+pub struct EntryDecl {
+    pub host: String,
+    pub label: String,
+    pub conditions: Vec<EntryCondition>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryCondition {
+    /// a state of the block runs a function calling this native function, e.g. `ThinkChase`
+    /// jumps to `shoot`
+    Calls(String),
+    /// the spawn block of the same name has this pain behavior
+    Pain(PainBehavior),
+}
+
 #[derive(Debug)]
 pub struct StatesBlock {
     pub name: String,
+    /// host type the states run on (`states <name>: <host>`), used to check trap usage
+    pub host: Option<String>,
+    pub pos: Pos,
     pub elements: Vec<StatesBlockElement>,
 }
 
+/// `template <name>(<params>): <host> { .. }`: states block with parameters. `{param}` in state
+/// ids and function names is replaced by the argument of the instance.
 #[derive(Debug)]
-pub struct SpawnBlock {
+pub struct TemplateDecl {
     pub name: String,
-    pub infos: Vec<EnemySpawnInfo>,
+    pub params: Vec<String>,
+    pub host: Option<String>,
+    pub pos: Pos,
+    pub elements: Vec<StatesBlockElement>,
+}
+
+/// `states <name> = <template>(<args>) { .. }`: expands to a states block. The optional elements
+/// are appended to the ones of the template.
+#[derive(Debug)]
+pub struct StatesInstance {
+    pub name: String,
+    pub template: String,
+    pub args: Vec<String>,
+    pub pos: Pos,
+    pub elements: Vec<StatesBlockElement>,
 }
 
 #[derive(Debug)]
+pub struct SpawnBlock {
+    pub name: String,
+    pub pos: Pos,
+    pub infos: Vec<(Pos, EnemySpawnInfo)>,
+    pub properties: Vec<(Pos, EnemyProperty)>,
+}
+
+/// Property line of a spawn block, see `crate::EnemyInfo`
+#[derive(Debug, Clone)]
+pub enum EnemyProperty {
+    Health([i32; 3]),
+    CanOpenDoors(bool),
+    /// enum name of the map id, resolved once all enums are known
+    Drop(String),
+    Speed {
+        path: i32,
+        chase: i32,
+    },
+    Points(i32),
+    Pain(PainBehavior),
+}
+
+impl EnemyProperty {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            EnemyProperty::Health(_) => "health",
+            EnemyProperty::CanOpenDoors(_) => "can_open_doors",
+            EnemyProperty::Drop(_) => "drop",
+            EnemyProperty::Speed { .. } => "speed",
+            EnemyProperty::Points(_) => "points",
+            EnemyProperty::Pain(_) => "pain",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum FunctionBlockElement {
     Label(String),
-    // LoadI32 { addr: u8 },
-    LoadiI32 { value: i32 },
-    LoadiU8Enum { name: String },
-    LoadiU8 { value: u8 },
-    LoadStateLabel { label: String },
-    // StoreI32 { addr: u8 },
+    LoadI32 {
+        addr: u8,
+    },
+    LoadiI32 {
+        value: i32,
+    },
+    LoadiU8Enum {
+        name: String,
+    },
+    LoadiU8 {
+        value: u8,
+    },
+    LoadStateLabel {
+        label: String,
+    },
+    StoreI32 {
+        addr: u8,
+    },
     Trap,
+    NamedTrap {
+        host: String,
+        name: String,
+    },
     Add,
     Ceq,
     Not,
-    Jrc { label: String },
+    Jrc {
+        label: String,
+    },
+    Jmp {
+        label: String,
+    },
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    Clt,
+    Cgt,
+    Cle,
+    Cge,
+    And,
+    Or,
+    Pop,
+    Swap,
+    Dup,
     FunctionCall,
+    /// `call <Name>`: call of a native function, resolved by name
+    NamedFunctionCall {
+        name: String,
+    },
     GoState,
     Stop,
+    /// `if <cond> { .. } else { .. }`
+    If {
+        cond: Expr,
+        then_branch: Vec<(Pos, FunctionBlockElement)>,
+        else_branch: Vec<(Pos, FunctionBlockElement)>,
+    },
+    /// `goto <label>`: switch to a state label of the states block running the function
+    Goto {
+        label: String,
+    },
+    /// `goto <expr>`: switch to a state pointer computed at runtime
+    GotoExpr(Expr),
+    /// `return`
+    Return,
+    /// `wait <expr>`: suspend the block, it continues after the given number of ticks
+    Wait(Expr),
+    /// call used as a statement. Results are left on the stack, traps may push any number.
+    Call(Expr),
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Gt => ">",
+            BinaryOp::Le => "<=",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Callee {
+    /// `name(..)`: trap of the host type of the function, or a native function
+    Name(String),
+    /// `trap(name, ..)` or `host.name(..)`
+    Trap { host: Option<String>, name: String },
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    /// plain integer literal, i32
+    Int(i32),
+    /// integer literal with `u8` suffix
+    U8(u8),
+    /// enum value, u8
+    Name {
+        name: String,
+        pos: Pos,
+    },
+    Call {
+        callee: Callee,
+        args: Vec<Expr>,
+        pos: Pos,
+    },
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
 #[derive(Debug)]
 pub struct FunctionBlock {
     pub name: String,
-    pub elements: Vec<FunctionBlockElement>,
+    /// host type the function runs on (`function <name>: <host>`), resolves unqualified traps
+    pub host: Option<String>,
+    pub pos: Pos,
+    pub elements: Vec<(Pos, FunctionBlockElement)>,
 }
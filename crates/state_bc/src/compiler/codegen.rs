@@ -1,29 +1,53 @@
 use crate::{
     ms::{endian::WriteExt, Writable},
-    opcode::Codegen,
-    SpawnInfos, StateBc,
+    opcode::{Codegen, Instruction},
+    ImageHeader, SpawnInfos, StateBc, IMAGE_VERSION,
 };
 use std::{
-    collections::{BTreeMap, HashMap},
-    io::{Cursor, Seek, Write},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    io::Cursor,
 };
 
-use super::ast::{StatesBlock, StatesBlockElement};
+use super::{
+    ast::{StatesBlock, StatesBlockElement},
+    optimize,
+};
+
+/// A block in the output whose tail later blocks can share
+struct PlacedBlock {
+    /// absolute positions of its instructions
+    boundaries: HashSet<usize>,
+    end: usize,
+}
+
 #[derive(Default)]
 struct BytecodeOutput {
     code: Vec<u8>,
     start_ptr: i32,
+    /// run the peephole optimizer and share tails of blocks
+    optimize: bool,
+    blocks: Vec<PlacedBlock>,
 }
 
+/// size of the JMP replacing a shared tail
+const JMP_SIZE: usize = 5;
+
 impl BytecodeOutput {
-    pub fn new(start_ptr: i32) -> Self {
+    pub fn new(start_ptr: i32, optimize: bool) -> Self {
         Self {
             start_ptr,
             code: vec![0u8; start_ptr as usize],
+            optimize,
+            blocks: Vec::new(),
         }
     }
-    pub fn append_codegen(&mut self, codegen: Codegen) -> i32 {
+    /// Append the code of `codegen`, unless it is already there. Returns its position and size.
+    pub fn append_codegen(&mut self, codegen: Codegen) -> (i32, usize) {
         let mut code = codegen.finalize();
+        if self.optimize {
+            code = optimize::optimize(code);
+        }
         if self.code.len() >= code.len() {
             // compression / size optimization:
             // search for subsequence match in previously generated code
@@ -32,12 +56,72 @@ impl BytecodeOutput {
                 .windows(code.len())
                 .position(|window| window == code)
             {
-                return pos as i32;
+                return (pos as i32, code.len());
+            }
+        }
+        if self.optimize {
+            if let Some(shared) = self.share_tail(&code) {
+                code = shared;
+            }
+            if let Some(layout) = optimize::layout(&code) {
+                let base = self.code.len();
+                self.blocks.push(PlacedBlock {
+                    boundaries: layout.boundaries.iter().map(|pos| base + pos).collect(),
+                    end: base + code.len(),
+                });
             }
         }
         let pos = self.code.len() as i32;
+        let len = code.len();
         self.code.append(&mut code);
-        pos
+        (pos, len)
+    }
+
+    /// Replace the longest tail of `code` that a placed block ends with by a jump to that block's
+    /// tail. The tail must be self-contained: no jump from before it lands inside it (jumping to
+    /// its start is fine) and no jump in it leaves it. Blocks with a WAIT are left alone, saved
+    /// suspensions resume by an offset into the block and only hash the block itself.
+    fn share_tail(&self, code: &[u8]) -> Option<Vec<u8>> {
+        let layout = optimize::layout(code)?;
+        let has_wait = layout.boundaries.iter().any(|&pos| {
+            matches!(
+                Instruction::decode(&mut Cursor::new(&code[pos..])),
+                Ok(Instruction::Wait)
+            )
+        });
+        if has_wait {
+            return None;
+        }
+        for start in layout.boundaries {
+            let tail = &code[start..];
+            if tail.len() <= JMP_SIZE {
+                break;
+            }
+            let self_contained = layout.jumps.iter().all(|&(pos, target)| {
+                if pos < start {
+                    target <= start
+                } else {
+                    target >= start
+                }
+            });
+            if !self_contained {
+                continue;
+            }
+            for block in &self.blocks {
+                let Some(tail_start) = block.end.checked_sub(tail.len()) else {
+                    continue;
+                };
+                if block.boundaries.contains(&tail_start)
+                    && self.code[tail_start..block.end] == *tail
+                {
+                    let mut shared = code[..start].to_vec();
+                    let jmp_end = self.code.len() + start + JMP_SIZE;
+                    Instruction::Jmp(tail_start as i32 - jmp_end as i32).encode(&mut shared);
+                    return Some(shared);
+                }
+            }
+        }
+        None
     }
 
     fn write_states(&mut self, states: &[StateBc]) {
@@ -50,15 +134,37 @@ impl BytecodeOutput {
     }
 }
 
+/// Image generated by `codegen`
+pub struct CodegenOutput {
+    pub image: Vec<u8>,
+    pub labels: BTreeMap<String, i32>,
+    /// one line per bytecode block: its range in the image and where it comes from
+    pub map: String,
+    /// size the image would have without the optimizer
+    pub unoptimized_size: usize,
+}
+
+/// Place the bytecode blocks after the state table of size `states_size`. Returns the output and
+/// the position and size of each block.
+fn place_blocks(
+    codegens: &[(String, Codegen)],
+    states_size: i32,
+    optimize: bool,
+) -> (BytecodeOutput, Vec<(i32, usize)>) {
+    let mut bytecode_output = BytecodeOutput::new(states_size, optimize);
+    let placed = codegens
+        .iter()
+        .map(|(_, codegen)| bytecode_output.append_codegen(codegen.clone()))
+        .collect();
+    (bytecode_output, placed)
+}
+
 pub fn codegen(
-    outname: &str,
     state_blocks: &[StatesBlock],
     enums: &BTreeMap<String, usize>,
     functions: &BTreeMap<String, Codegen>,
     spawn_infos: &SpawnInfos,
-) {
-    let _ = std::fs::rename(outname, format!("{outname}.bak")); // don't care if it does not work
-
+) -> crate::Result<CodegenOutput> {
     let mut states = Vec::new();
     let mut label_ptrs = BTreeMap::new(); // keep them sorted in the output file
     let mut ps_label_ptrs = Vec::new();
@@ -73,14 +179,7 @@ pub fn codegen(
                     label_ptrs.insert(format!("{}::{}", state_block.name, name), ip);
                     label_ptrs2.insert(name.clone(), ip);
                 }
-                StatesBlockElement::State {
-                    id: _,
-                    directional: _,
-                    ticks: _,
-                    think: _,
-                    action: _,
-                    next: _,
-                } => ip += crate::STATE_BC_SIZE,
+                StatesBlockElement::State { .. } => ip += crate::STATE_BC_SIZE,
             }
         }
         ps_label_ptrs.push(label_ptrs2);
@@ -104,6 +203,7 @@ pub fn codegen(
                 think,
                 action,
                 next,
+                pos: _,
             } = element
             {
                 let id = *enums
@@ -141,35 +241,35 @@ pub fn codegen(
         }
     }
 
-    let mut f = std::fs::File::create(outname).expect("failed to open img file");
+    // the header is filled in once the content hash is known
+    let mut image = vec![0u8; ImageHeader::SIZE];
 
     // write labels and spawn info
-    f.writei32(label_ptrs.len() as i32).unwrap();
+    image.writei32(label_ptrs.len() as i32)?;
     for (name, ptr) in &label_ptrs {
         let b = name.as_bytes();
-        f.writeu8(b.len() as u8).unwrap();
-        let _ = f.write(b).unwrap();
-        f.writei32(*ptr).unwrap();
+        image.writeu8(b.len() as u8)?;
+        image.extend_from_slice(b);
+        image.writei32(*ptr)?;
     }
-    spawn_infos.write(&mut f).unwrap();
+    spawn_infos.write(&mut image)?;
 
     // luxury feature: sort bytecode blocks by descending size. This way the bytecode
     // compression should be approximately ideal (there might be better ordering to also
     // leverage matches across different blocks, but yeah well...)
     codegens.sort_unstable_by_key(|(_, codegen)| -(codegen.len() as i64));
-    let mut bytecode_output = BytecodeOutput::new(ip);
+    let (unoptimized_output, _) = place_blocks(&codegens, ip, false);
+    let (mut bytecode_output, placed) = place_blocks(&codegens, ip, true);
     let mut bc_pos = HashMap::new();
-    let mut map_f =
-        std::fs::File::create(format!("{outname}.map")).expect("failed to open map file");
-    let img_code_offs = f.stream_position().unwrap() as i32;
-    for (name, codegen) in codegens {
-        let pos = bytecode_output.append_codegen(codegen.clone());
-        bc_pos.insert(name, pos);
+    let mut map = String::new();
+    let img_code_offs = image.len() as i32;
+    for ((name, codegen), (pos, len)) in codegens.iter().zip(placed) {
+        bc_pos.insert(name.clone(), pos);
         writeln!(
-            map_f,
+            map,
             "{:04x} - {:04x} {}",
             pos + img_code_offs,
-            pos + codegen.len() as i32 + img_code_offs,
+            pos + len as i32 + img_code_offs,
             codegen
                 .get_annotation("source")
                 .expect("missing annotation 'source'"),
@@ -188,7 +288,22 @@ pub fn codegen(
             .expect("missing bc offset for {action_name}");
     }
     bytecode_output.write_states(&states);
-    let _ = f.write(&bytecode_output.code).unwrap();
+    crate::verify::verify_code(&bytecode_output.code, &label_ptrs)
+        .map_err(|err| err.context("generated image is invalid"))?;
+    image.extend_from_slice(&bytecode_output.code);
+    let header = ImageHeader {
+        version: IMAGE_VERSION,
+        enum_hash: crate::enum_hash(enums.iter().map(|(name, id)| (name.as_str(), *id as i32))),
+        image_hash: crate::content_hash(&image[ImageHeader::SIZE..]),
+    };
+    header.write(&mut Cursor::new(&mut image[..ImageHeader::SIZE]))?;
+    let unoptimized_size = image.len() - bytecode_output.code.len() + unoptimized_output.code.len();
+    Ok(CodegenOutput {
+        image,
+        labels: label_ptrs,
+        map,
+        unoptimized_size,
+    })
 }
 
 fn codegen_for_function_name(
@@ -202,3 +317,51 @@ fn codegen_for_function_name(
         .clone()
         .with_state_label_ptrs(label_ptrs)
 }
+
+#[test]
+fn test_share_tail() {
+    // counts r1 up to 10, then leaves the state
+    let with_tail = |codegen: Codegen| {
+        codegen
+            .load_i32(1)
+            .loadi_i32(1)
+            .add()
+            .store_i32(1)
+            .load_i32(1)
+            .loadi_i32(10)
+            .clt()
+            .jrc_label("end")
+            .loadi_i32(0)
+            .gostate()
+            .label("end")
+            .stop()
+    };
+    let a = with_tail(Codegen::default().loadi_i32(5).store_i32(0));
+    let b = with_tail(Codegen::default().load_i32(2).store_i32(0));
+    let mut output = BytecodeOutput::new(0, true);
+    let (pos_a, len_a) = output.append_codegen(a.clone());
+    let (pos_b, len_b) = output.append_codegen(b.clone());
+    assert_eq!(len_a, a.len());
+    // loadi32 2, then a jump to `storei32 0` and the rest of a
+    assert_eq!(len_b, 2 + JMP_SIZE);
+    assert_eq!(output.code.len(), len_a + len_b);
+
+    for r1 in [0, 9, 10] {
+        let regs = [0, r1, 7, 0, 0, 0, 0, 0];
+        for (codegen, pos) in [(a.clone(), pos_a), (b.clone(), pos_b)] {
+            let (events, env) = optimize::run(&codegen.finalize(), 0, regs);
+            let (shared_events, shared_env) = optimize::run(&output.code, pos as usize, regs);
+            assert_eq!(events, shared_events);
+            assert_eq!(env.regs, shared_env.regs);
+        }
+    }
+    crate::verify::verify_block(&output.code, 0, pos_b as usize).unwrap();
+
+    // a suspended block resumes inside its own code, don't share the tail
+    let a = with_tail(Codegen::default().loadi_i32(2).wait());
+    let b = with_tail(Codegen::default().loadi_i32(3).wait());
+    let mut output = BytecodeOutput::new(0, true);
+    output.append_codegen(a);
+    let (_, len_b) = output.append_codegen(b.clone());
+    assert_eq!(len_b, b.len());
+}
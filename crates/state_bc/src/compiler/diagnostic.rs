@@ -0,0 +1,183 @@
+use std::fmt;
+
+use super::ast::Pos;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// reported by the lints, does not fail the compile run
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A compile error or warning, located in the source file if possible
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    pub pos: Option<Pos>,
+    pub message: String,
+    /// source line at `pos`
+    pub snippet: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(file: &str, source: &str, pos: Pos, message: String) -> Self {
+        let snippet = source
+            .lines()
+            .nth((pos.line as usize).saturating_sub(1))
+            .map(|line| line.to_string());
+        Diagnostic {
+            severity: Severity::Error,
+            file: file.to_string(),
+            pos: Some(pos),
+            message,
+            snippet,
+        }
+    }
+
+    /// Diagnostic that does not refer to a specific source location
+    pub fn without_pos(file: &str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            file: file.to_string(),
+            pos: None,
+            message,
+            snippet: None,
+        }
+    }
+
+    /// One line `<file>:<line>:<column>: <severity>: <message>`, e.g. for `cargo:warning`
+    pub fn short(&self) -> String {
+        match self.pos {
+            Some(pos) => format!(
+                "{}:{}:{}: {}: {}",
+                self.file, pos.line, pos.column, self.severity, self.message
+            ),
+            None => format!("{}: {}: {}", self.file, self.severity, self.message),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}", self.severity, self.message)?;
+        let Some(pos) = self.pos else {
+            return write!(f, "  --> {}", self.file);
+        };
+        let line = pos.line.to_string();
+        let gutter = " ".repeat(line.len());
+        write!(f, "{gutter}--> {}:{}:{}", self.file, pos.line, pos.column)?;
+        if let Some(snippet) = &self.snippet {
+            writeln!(f)?;
+            writeln!(f, "{gutter} |")?;
+            writeln!(f, "{line} | {snippet}")?;
+            write!(
+                f,
+                "{gutter} | {}^",
+                " ".repeat(pos.column.saturating_sub(1))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// All errors and warnings of a compile run
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.0.iter().filter(|d| d.severity == severity).count()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.0 {
+            writeln!(f, "{diagnostic}\n")?;
+        }
+        write!(f, "{} error(s)", self.count(Severity::Error))?;
+        match self.count(Severity::Warning) {
+            0 => Ok(()),
+            warnings => write!(f, ", {warnings} warning(s)"),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// Collects the diagnostics of a compile run. Every source file is registered with `add_file`,
+/// the returned index is part of all positions in it.
+#[derive(Default)]
+pub struct Reporter {
+    files: Vec<(String, String)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Reporter {
+    pub fn add_file(&mut self, name: &str, source: &str) -> usize {
+        self.files.push((name.to_string(), source.to_string()));
+        self.files.len() - 1
+    }
+
+    pub fn error(&mut self, pos: Pos, message: String) {
+        let (file, source) = &self.files[pos.file];
+        self.diagnostics
+            .push(Diagnostic::new(file, source, pos, message));
+    }
+
+    pub fn error_without_pos(&mut self, file: &str, message: String) {
+        self.diagnostics
+            .push(Diagnostic::without_pos(file, message));
+    }
+
+    pub fn warning(&mut self, pos: Pos, message: String) {
+        let (file, source) = &self.files[pos.file];
+        let mut diagnostic = Diagnostic::new(file, source, pos, message);
+        diagnostic.severity = Severity::Warning;
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Err with all collected diagnostics if there are errors, otherwise Ok with the warnings
+    pub fn finish(self) -> Result<Diagnostics, Diagnostics> {
+        if self.has_errors() {
+            Err(Diagnostics(self.diagnostics))
+        } else {
+            Ok(Diagnostics(self.diagnostics))
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+#[test]
+fn test_display() {
+    let source = "function Foo {\n    loadiu8 300\n}\n";
+    let diagnostic = Diagnostic::new(
+        "test.st",
+        source,
+        Pos {
+            file: 0,
+            line: 2,
+            column: 13,
+        },
+        "u8 out of range 300".to_string(),
+    );
+    assert_eq!(
+        diagnostic.to_string(),
+        "error: u8 out of range 300\n --> test.st:2:13\n  |\n2 |     loadiu8 300\n  |             ^"
+    );
+}
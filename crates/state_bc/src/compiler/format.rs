@@ -0,0 +1,148 @@
+//! Canonical layout of .st sources. Only whitespace is changed: lines are indented by 4 spaces
+//! per open `{` or `(`, labels start at column 0, commas are followed by exactly one space,
+//! trailing whitespace and runs of blank lines are removed. Comments and strings are kept as is.
+
+/// Code part of `line` and its trailing `//` comment, if any. `//` inside strings doesn't count.
+fn split_comment(line: &str) -> (&str, &str) {
+    let mut in_string = false;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '/' if prev == '/' && !in_string => return (&line[..i - 1], &line[i - 1..]),
+            _ => (),
+        }
+        prev = c;
+    }
+    (line, "")
+}
+
+/// `name:` alone on a line, a state label or a jump target in a function block
+fn is_label(code: &str) -> bool {
+    code.strip_suffix(':').is_some_and(|name| {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+fn normalize_commas(code: &str) -> String {
+    let mut out = String::new();
+    let mut in_string = false;
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                while out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push(',');
+                while chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+                    chars.next();
+                }
+                if chars.peek().is_some() {
+                    out.push(' ');
+                }
+                continue;
+            }
+            _ => (),
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Net change of the nesting depth by `code`, and whether it starts by closing a level
+fn depth_change(code: &str) -> (i32, bool) {
+    let mut delta = 0;
+    let mut in_string = false;
+    for c in code.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' | '(' if !in_string => delta += 1,
+            '}' | ')' if !in_string => delta -= 1,
+            _ => (),
+        }
+    }
+    (delta, code.starts_with(['}', ')']))
+}
+
+pub fn format_source(source: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0i32;
+    let mut blank = false;
+    for line in source.lines() {
+        let (code, comment) = split_comment(line.trim());
+        let code = normalize_commas(code.trim_end());
+        if code.is_empty() && comment.is_empty() {
+            // at most one blank line, none at the start
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        let (delta, closes_first) = depth_change(&code);
+        let indent = if is_label(&code) {
+            0
+        } else if closes_first {
+            depth - 1
+        } else {
+            depth
+        };
+        for _ in 0..indent.max(0) {
+            out.push_str("    ");
+        }
+        out.push_str(&code);
+        if !comment.is_empty() {
+            if !code.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(comment);
+        }
+        out.push('\n');
+        depth = (depth + delta).max(0);
+    }
+    out
+}
+
+#[test]
+fn test_format_source() {
+    let source = "
+states door: door {
+  closed:
+        state DEMO , false,0, Check , None, closed   // wait
+}
+
+
+function Check: door {
+if trigger() {
+play_sound(
+SND_DOOR_OPEN)
+} else {
+    return
+}
+// done
+}
+include \"a, b.st\"
+";
+    let expected = "states door: door {
+closed:
+    state DEMO, false, 0, Check, None, closed // wait
+}
+
+function Check: door {
+    if trigger() {
+        play_sound(
+            SND_DOOR_OPEN)
+    } else {
+        return
+    }
+    // done
+}
+include \"a, b.st\"
+";
+    let formatted = format_source(source);
+    assert_eq!(formatted, expected);
+    assert_eq!(format_source(&formatted), formatted);
+}
@@ -0,0 +1,266 @@
+//! Warnings about dead code in the sources: states no entry point can reach and functions no
+//! state runs.
+//!
+//! The entry points of a states block are the states its spawn block starts enemies in and the
+//! labels the game jumps to, declared with `entry <host>.<label>` (e.g. `Enemy::hit` jumps to
+//! `die`). From there the `next` labels of the states and the labels loaded by their functions
+//! (`goto`, `loadsl`) are followed. Computed gotos like `goto ready_state()` can't be followed,
+//! their targets have to be declared as entries.
+
+use super::{
+    ast::{EntryCondition, EntryDecl, FunctionBlock, Pos, StatesBlock, StatesBlockElement},
+    diagnostic::Reporter,
+    FunctionInfo,
+};
+use crate::{EnemyInfo, EnemySpawnInfo, Function};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+struct State<'a> {
+    id: &'a str,
+    think: &'a str,
+    action: &'a str,
+    next: &'a str,
+    pos: Pos,
+    /// nearest label before the state and the distance to it
+    label: Option<(&'a str, usize)>,
+}
+
+impl State<'_> {
+    /// Infos of the think and action functions
+    fn functions<'f>(
+        &self,
+        function_infos: &'f BTreeMap<String, FunctionInfo>,
+    ) -> impl Iterator<Item = &'f FunctionInfo> {
+        [
+            function_infos.get(self.think),
+            function_infos.get(self.action),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// The states of a block in order, and the index of the state each label points to
+fn states_and_labels(state_block: &StatesBlock) -> (Vec<State<'_>>, HashMap<&str, usize>) {
+    let mut states = Vec::new();
+    let mut labels = HashMap::new();
+    let mut last_label = None;
+    for element in &state_block.elements {
+        match element {
+            StatesBlockElement::Label(label) => {
+                labels.insert(label.as_str(), states.len());
+                last_label = Some((label.as_str(), 0));
+            }
+            StatesBlockElement::State {
+                id,
+                think,
+                action,
+                next,
+                pos,
+                ..
+            } => {
+                states.push(State {
+                    id,
+                    think,
+                    action,
+                    next,
+                    pos: *pos,
+                    label: last_label,
+                });
+                if let Some((_, offset)) = &mut last_label {
+                    *offset += 1;
+                }
+            }
+        }
+    }
+    (states, labels)
+}
+
+fn entry_applies(
+    entry: &EntryDecl,
+    state_block: &StatesBlock,
+    calls: &BTreeSet<&str>,
+    enemy_info: Option<&EnemyInfo>,
+) -> bool {
+    if state_block.host.as_ref() != Some(&entry.host) {
+        return false;
+    }
+    entry.conditions.is_empty()
+        || entry.conditions.iter().any(|condition| match condition {
+            EntryCondition::Calls(function) => calls.contains(function.as_str()),
+            EntryCondition::Pain(pain) => enemy_info.is_some_and(|info| info.pain == *pain),
+        })
+}
+
+/// Warn about unreachable states, entry labels missing in a states block and unused functions.
+/// Expects sources without errors, unknown labels and functions are skipped.
+pub fn lint(
+    reporter: &mut Reporter,
+    state_blocks: &[StatesBlock],
+    function_blocks: &[FunctionBlock],
+    function_infos: &BTreeMap<String, FunctionInfo>,
+    entries: &[EntryDecl],
+    spawn_infos: &[(Pos, EnemySpawnInfo)],
+    enemy_infos: &[EnemyInfo],
+) {
+    for entry in entries {
+        for condition in &entry.conditions {
+            if let EntryCondition::Calls(function) = condition {
+                if Function::try_from_identifier(function).is_none() {
+                    reporter.error(entry.pos, format!("unknown native function {function}"));
+                }
+            }
+        }
+    }
+
+    let mut used_functions = BTreeSet::new();
+    for state_block in state_blocks {
+        let (states, labels) = states_and_labels(state_block);
+        let calls: BTreeSet<&str> = states
+            .iter()
+            .flat_map(|state| state.functions(function_infos))
+            .flat_map(|info| info.calls.iter().copied())
+            .collect();
+        used_functions.extend(states.iter().flat_map(|state| [state.think, state.action]));
+
+        let enemy_info = enemy_infos
+            .iter()
+            .find(|info| info.name == state_block.name);
+        let mut roots = Vec::new();
+        for entry in entries {
+            if !entry_applies(entry, state_block, &calls, enemy_info) {
+                continue;
+            }
+            match labels.get(entry.label.as_str()) {
+                Some(index) => roots.push(*index),
+                None => reporter.warning(
+                    state_block.pos,
+                    format!(
+                        "states block {} has no label {}, the game jumps to it (entry {}.{})",
+                        state_block.name, entry.label, entry.host, entry.label
+                    ),
+                ),
+            }
+        }
+        let prefix = format!("{}::", state_block.name);
+        roots.extend(
+            spawn_infos
+                .iter()
+                .filter_map(|(_, info)| info.state.strip_prefix(&prefix))
+                .filter_map(|label| labels.get(label).copied()),
+        );
+
+        let mut reachable = vec![false; states.len()];
+        while let Some(index) = roots.pop() {
+            if index >= states.len() || reachable[index] {
+                continue;
+            }
+            reachable[index] = true;
+            let state = &states[index];
+            if state.next == "next" {
+                roots.push(index + 1);
+            } else {
+                roots.extend(labels.get(state.next));
+            }
+            for info in state.functions(function_infos) {
+                roots.extend(
+                    info.state_labels
+                        .iter()
+                        .filter_map(|(_, label)| labels.get(label.as_str())),
+                );
+            }
+        }
+
+        let mut index = 0;
+        while index < states.len() {
+            if reachable[index] {
+                index += 1;
+                continue;
+            }
+            let first = &states[index];
+            let count = reachable[index..].iter().take_while(|r| !**r).count();
+            let location = match first.label {
+                Some((label, offset)) => format!("{}::{label}+{offset}", state_block.name),
+                None => format!("{}+{index}", state_block.name),
+            };
+            let message = match count {
+                1 => format!("unreachable state {} at {location}", first.id),
+                _ => format!(
+                    "{count} unreachable states starting with {} at {location}",
+                    first.id
+                ),
+            };
+            reporter.warning(first.pos, message);
+            index += count;
+        }
+    }
+
+    for function_block in function_blocks {
+        if !used_functions.contains(function_block.name.as_str()) {
+            reporter.warning(
+                function_block.pos,
+                format!("function {} is never used", function_block.name),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_lint() {
+    let source = "enum { A }
+entry enemy.die
+entry enemy.pain1 if pain alternate
+entry enemy.shoot if ThinkChase
+entry door.closed
+spawn guard {
+    pain never
+    undirectional 1, stand
+}
+states guard: enemy {
+stand:
+    state A, false, 0, Chase, None, next
+    state A, false, 0, None, Jump, stand
+dead:
+    state A, false, 0, None, None, dead
+    state A, false, 0, None, None, dead
+shoot:
+    state A, false, 0, None, None, stand
+}
+function Chase: enemy {
+    ThinkChase()
+}
+function Jump: enemy {
+    goto shoot
+}
+function None {
+}
+function Unused {
+}
+";
+    let output = super::compile_source("inline.st", source).unwrap();
+    let messages: Vec<_> = output
+        .warnings
+        .0
+        .iter()
+        .map(|warning| warning.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "states block guard has no label die, the game jumps to it (entry enemy.die)",
+            "2 unreachable states starting with A at guard::dead+0",
+            "function Unused is never used",
+        ]
+    );
+    assert!(output
+        .warnings
+        .to_string()
+        .ends_with("0 error(s), 3 warning(s)"));
+
+    let diagnostics =
+        super::compile_source("inline.st", "entry enemy.die if NoSuchFunction\n").unwrap_err();
+    assert_eq!(
+        diagnostics.0[0].message,
+        "unknown native function NoSuchFunction"
+    );
+}
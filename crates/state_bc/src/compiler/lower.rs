@@ -0,0 +1,677 @@
+//! Lowering of function blocks to bytecode. Assembly directives map 1:1 to instructions,
+//! statements and expressions expand to the same instruction sequences one would write by hand.
+
+use super::{
+    ast::{BinaryOp, Callee, Expr, FunctionBlock, FunctionBlockElement, Pos, UnaryOp},
+    diagnostic::Reporter,
+    FunctionInfo,
+};
+use crate::{opcode::Codegen, Function};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Names a function block can refer to
+pub struct Symbols<'a> {
+    pub enums: &'a BTreeMap<String, usize>,
+    pub traps: &'a BTreeMap<(String, String), u8>,
+    /// traps declared with `(..)`, calls push their argument count
+    pub variadic_traps: &'a BTreeSet<(String, String)>,
+}
+
+/// Type of the value an expression pushes. Traps and native functions don't declare what they
+/// push, their results are `Unknown` and accepted everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    I32,
+    U8,
+    Bool,
+    Unknown,
+}
+
+impl Type {
+    fn name(self) -> &'static str {
+        match self {
+            Type::I32 => "i32",
+            Type::U8 => "u8",
+            Type::Bool => "bool",
+            Type::Unknown => "unknown",
+        }
+    }
+
+    fn is(self, ty: Type) -> bool {
+        self == ty || self == Type::Unknown
+    }
+}
+
+struct FunctionLowering<'a> {
+    reporter: &'a mut Reporter,
+    symbols: &'a Symbols<'a>,
+    name: &'a str,
+    host: Option<&'a str>,
+    labels: HashSet<String>,
+    info: FunctionInfo,
+    next_label: usize,
+}
+
+/// Generate the code of a function block, terminated by STOP. Errors are reported and the
+/// offending element is skipped.
+pub fn lower_function(
+    reporter: &mut Reporter,
+    symbols: &Symbols,
+    function_block: &FunctionBlock,
+) -> (Codegen, FunctionInfo) {
+    let mut lowering = FunctionLowering {
+        reporter,
+        symbols,
+        name: &function_block.name,
+        host: function_block.host.as_deref(),
+        labels: HashSet::new(),
+        info: FunctionInfo::default(),
+        next_label: 0,
+    };
+    lowering.collect_labels(&function_block.elements);
+    let codegen = Codegen::default().with_annotation("source", &function_block.name);
+    let codegen = lowering.elements(codegen, &function_block.elements);
+    (codegen.stop(), lowering.info)
+}
+
+/// True if control never falls through the end of `elements`
+fn diverges(elements: &[(Pos, FunctionBlockElement)]) -> bool {
+    matches!(
+        elements.last(),
+        Some((
+            _,
+            FunctionBlockElement::Goto { .. }
+                | FunctionBlockElement::GotoExpr(_)
+                | FunctionBlockElement::Return
+                | FunctionBlockElement::Stop
+                | FunctionBlockElement::GoState
+                | FunctionBlockElement::Jmp { .. }
+        ))
+    )
+}
+
+/// Value of `expr` if it only consists of integer literals and arithmetic
+fn const_i32(expr: &Expr) -> Option<i32> {
+    match expr {
+        Expr::Int(v) => Some(*v),
+        Expr::Unary(UnaryOp::Neg, inner) => const_i32(inner)?.checked_neg(),
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (const_i32(lhs)?, const_i32(rhs)?);
+            match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+impl FunctionLowering<'_> {
+    fn collect_labels(&mut self, elements: &[(Pos, FunctionBlockElement)]) {
+        for (pos, element) in elements {
+            match element {
+                FunctionBlockElement::Label(label) => {
+                    let is_new = self.labels.insert(label.clone());
+                    if !is_new {
+                        self.reporter
+                            .error(*pos, format!("duplicate label {label}"));
+                    }
+                }
+                FunctionBlockElement::If {
+                    then_branch,
+                    else_branch,
+                    ..
+                } => {
+                    self.collect_labels(then_branch);
+                    self.collect_labels(else_branch);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Label for generated jumps. Contains a '.', so it can't clash with labels in the source.
+    fn new_label(&mut self, kind: &str) -> String {
+        self.next_label += 1;
+        format!("{kind}.{}", self.next_label)
+    }
+
+    fn elements(
+        &mut self,
+        mut codegen: Codegen,
+        elements: &[(Pos, FunctionBlockElement)],
+    ) -> Codegen {
+        for (pos, element) in elements {
+            codegen = self.element(codegen, *pos, element);
+        }
+        codegen
+    }
+
+    fn element(&mut self, codegen: Codegen, pos: Pos, element: &FunctionBlockElement) -> Codegen {
+        match element {
+            FunctionBlockElement::Label(label) => codegen.label(label),
+            FunctionBlockElement::LoadI32 { addr } => codegen.load_i32(*addr),
+            FunctionBlockElement::StoreI32 { addr } => codegen.store_i32(*addr),
+            FunctionBlockElement::LoadiI32 { value } => codegen.loadi_i32(*value),
+            FunctionBlockElement::LoadiU8Enum { name } => match self.enum_value(pos, name) {
+                Some(v) => codegen.loadi_u8(v),
+                None => codegen,
+            },
+            FunctionBlockElement::LoadiU8 { value } => codegen.loadi_u8(*value),
+            FunctionBlockElement::Trap => match self.host {
+                // the trap number comes from the stack, it can't be checked against the host
+                Some(host) => {
+                    self.reporter.error(
+                        pos,
+                        format!(
+                            "raw trap in function {} of host type {host}, use 'trap {host}.<name>'",
+                            self.name
+                        ),
+                    );
+                    codegen
+                }
+                None => codegen.trap(),
+            },
+            FunctionBlockElement::NamedTrap { host, name } => {
+                self.trap(codegen, pos, host, name, None)
+            }
+            FunctionBlockElement::Add => codegen.add(),
+            FunctionBlockElement::FunctionCall => codegen.call(),
+            FunctionBlockElement::NamedFunctionCall { name } => {
+                match Function::try_from_identifier(name) {
+                    Some(function) => self.function_call(codegen, function),
+                    None => {
+                        self.reporter
+                            .error(pos, format!("unknown native function {name}"));
+                        codegen
+                    }
+                }
+            }
+            FunctionBlockElement::Ceq => codegen.ceq(),
+            FunctionBlockElement::Not => codegen.bin_not(),
+            FunctionBlockElement::Jrc { label } | FunctionBlockElement::Jmp { label }
+                if !self.labels.contains(label) =>
+            {
+                self.reporter.error(
+                    pos,
+                    format!("unknown label {label} in function {}", self.name),
+                );
+                codegen
+            }
+            FunctionBlockElement::Jrc { label } => codegen.jrc_label(label),
+            FunctionBlockElement::Jmp { label } => codegen.jmp_label(label),
+            FunctionBlockElement::Sub => codegen.sub(),
+            FunctionBlockElement::Mul => codegen.mul(),
+            FunctionBlockElement::Div => codegen.div(),
+            FunctionBlockElement::Neg => codegen.bin_neg(),
+            FunctionBlockElement::Clt => codegen.clt(),
+            FunctionBlockElement::Cgt => codegen.cgt(),
+            FunctionBlockElement::Cle => codegen.cle(),
+            FunctionBlockElement::Cge => codegen.cge(),
+            FunctionBlockElement::And => codegen.bin_and(),
+            FunctionBlockElement::Or => codegen.bin_or(),
+            FunctionBlockElement::Pop => codegen.pop(),
+            FunctionBlockElement::Swap => codegen.swap(),
+            FunctionBlockElement::Dup => codegen.dup(),
+            FunctionBlockElement::Stop => codegen.stop(),
+            FunctionBlockElement::GoState => codegen.gostate(),
+            FunctionBlockElement::LoadStateLabel { label } => {
+                self.info.state_labels.push((pos, label.clone()));
+                codegen.loadsl(label)
+            }
+            FunctionBlockElement::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let else_label = self.new_label("else");
+                let codegen = self.jump_if_false(codegen, pos, cond, &else_label);
+                let mut codegen = self.elements(codegen, then_branch);
+                if else_branch.is_empty() {
+                    return codegen.label(&else_label);
+                }
+                let end_label = self.new_label("endif");
+                if !diverges(then_branch) {
+                    codegen = codegen.jmp_label(&end_label);
+                }
+                let codegen = self.elements(codegen.label(&else_label), else_branch);
+                codegen.label(&end_label)
+            }
+            FunctionBlockElement::Goto { label } => {
+                self.info.state_labels.push((pos, label.clone()));
+                codegen.loadsl(label).gostate()
+            }
+            FunctionBlockElement::GotoExpr(expr) => {
+                let (codegen, _) = self.expr(codegen, pos, expr);
+                codegen.gostate()
+            }
+            FunctionBlockElement::Return => codegen.stop(),
+            FunctionBlockElement::Wait(expr) => {
+                let (codegen, ty) = self.expr(codegen, pos, expr);
+                self.expect_type(pos, ty, Type::I32, "wait");
+                if let Some(ticks @ ..=0) = const_i32(expr) {
+                    self.reporter.error(
+                        pos,
+                        format!("wait needs a positive number of ticks, found {ticks}"),
+                    );
+                }
+                codegen.wait()
+            }
+            FunctionBlockElement::Call(expr) => self.expr(codegen, pos, expr).0,
+        }
+    }
+
+    /// Jump to `label` if `cond` is false. Saves the NOT for negated conditions.
+    fn jump_if_false(&mut self, codegen: Codegen, pos: Pos, cond: &Expr, label: &str) -> Codegen {
+        let (codegen, ty) = match cond {
+            Expr::Unary(UnaryOp::Not, inner) => self.expr(codegen, pos, inner),
+            _ => {
+                let (codegen, ty) = self.expr(codegen, pos, cond);
+                (codegen.bin_not(), ty)
+            }
+        };
+        self.expect_type(pos, ty, Type::Bool, "if");
+        codegen.jrc_label(label)
+    }
+
+    fn expect_type(&mut self, pos: Pos, ty: Type, expected: Type, context: &str) {
+        if !ty.is(expected) {
+            self.reporter.error(
+                pos,
+                format!(
+                    "{context} needs a value of type {}, found {}",
+                    expected.name(),
+                    ty.name()
+                ),
+            );
+        }
+    }
+
+    /// Push the value of `expr` and return its type. Operand types are checked here, the
+    /// instructions would fail at runtime otherwise. `pos` is the position of the statement, the
+    /// closest one for operators. Both operands of `&&` and `||` are always evaluated.
+    fn expr(&mut self, codegen: Codegen, pos: Pos, expr: &Expr) -> (Codegen, Type) {
+        match expr {
+            Expr::Int(v) => (codegen.loadi_i32(*v), Type::I32),
+            Expr::U8(v) => (codegen.loadi_u8(*v), Type::U8),
+            Expr::Name { name, pos } => match self.enum_value(*pos, name) {
+                Some(v) => (codegen.loadi_u8(v), Type::U8),
+                None => (codegen, Type::Unknown),
+            },
+            Expr::Unary(UnaryOp::Neg, inner) => match **inner {
+                Expr::Int(v) => (codegen.loadi_i32(-v), Type::I32),
+                _ => {
+                    let (codegen, ty) = self.expr(codegen, pos, inner);
+                    self.expect_type(pos, ty, Type::I32, "operator -");
+                    (codegen.bin_neg(), Type::I32)
+                }
+            },
+            Expr::Unary(UnaryOp::Not, inner) => {
+                let (codegen, ty) = self.expr(codegen, pos, inner);
+                self.expect_type(pos, ty, Type::Bool, "operator !");
+                (codegen.bin_not(), Type::Bool)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (codegen, lhs_ty) = self.expr(codegen, pos, lhs);
+                let (codegen, rhs_ty) = self.expr(codegen, pos, rhs);
+                let ty = self.binary_type(pos, *op, lhs_ty, rhs_ty);
+                let codegen = match op {
+                    BinaryOp::Add => codegen.add(),
+                    BinaryOp::Sub => codegen.sub(),
+                    BinaryOp::Mul => codegen.mul(),
+                    BinaryOp::Div => codegen.div(),
+                    BinaryOp::Eq => codegen.ceq(),
+                    BinaryOp::Ne => codegen.ceq().bin_not(),
+                    BinaryOp::Lt => codegen.clt(),
+                    BinaryOp::Gt => codegen.cgt(),
+                    BinaryOp::Le => codegen.cle(),
+                    BinaryOp::Ge => codegen.cge(),
+                    BinaryOp::And => codegen.bin_and(),
+                    BinaryOp::Or => codegen.bin_or(),
+                };
+                (codegen, ty)
+            }
+            Expr::Call { callee, args, pos } => {
+                let mut codegen = codegen;
+                for arg in args {
+                    codegen = self.expr(codegen, *pos, arg).0;
+                }
+                (self.call(codegen, *pos, callee, args.len()), Type::Unknown)
+            }
+        }
+    }
+
+    /// Check the operand types of `op` and return the type of its result
+    fn binary_type(&mut self, pos: Pos, op: BinaryOp, lhs: Type, rhs: Type) -> Type {
+        let (operands, result) = match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                (&[Type::I32][..], Type::I32)
+            }
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Gt
+            | BinaryOp::Le
+            | BinaryOp::Ge => (&[Type::I32, Type::U8][..], Type::Bool),
+            BinaryOp::And | BinaryOp::Or => (&[Type::Bool][..], Type::Bool),
+        };
+        let symbol = op.symbol();
+        for ty in [lhs, rhs] {
+            if ty != Type::Unknown && !operands.contains(&ty) {
+                let names: Vec<_> = operands.iter().map(|ty| ty.name()).collect();
+                self.reporter.error(
+                    pos,
+                    format!(
+                        "operator {symbol} needs {} operands, found {}",
+                        names.join(" or "),
+                        ty.name()
+                    ),
+                );
+                return result;
+            }
+        }
+        if lhs != rhs && lhs != Type::Unknown && rhs != Type::Unknown {
+            self.reporter.error(
+                pos,
+                format!(
+                    "mismatched operand types {} and {} for operator {symbol}",
+                    lhs.name(),
+                    rhs.name()
+                ),
+            );
+        }
+        result
+    }
+
+    /// Call `callee` with `num_args` arguments already pushed
+    fn call(&mut self, codegen: Codegen, pos: Pos, callee: &Callee, num_args: usize) -> Codegen {
+        match callee {
+            Callee::Trap {
+                host: Some(host),
+                name,
+            } => self.trap(codegen, pos, host, name, Some(num_args)),
+            Callee::Trap { host: None, name } => match self.host {
+                Some(host) => self.trap(codegen, pos, host, name, Some(num_args)),
+                None => {
+                    self.reporter.error(
+                        pos,
+                        format!(
+                            "trap {name} needs a host type, use 'function {}: <host>' or '<host>.{name}(..)'",
+                            self.name
+                        ),
+                    );
+                    codegen
+                }
+            },
+            Callee::Name(name) => {
+                if let Some(host) = self.host {
+                    let key = (host.to_string(), name.clone());
+                    if self.symbols.traps.contains_key(&key) {
+                        return self.trap(codegen, pos, host, name, Some(num_args));
+                    }
+                }
+                match Function::try_from_identifier(name) {
+                    Some(function) => self.function_call(codegen, function),
+                    None => {
+                        self.reporter
+                            .error(pos, format!("unknown function or trap {name}"));
+                        codegen
+                    }
+                }
+            }
+        }
+    }
+
+    fn function_call(&mut self, codegen: Codegen, function: Function) -> Codegen {
+        self.info.calls.insert(function.name());
+        codegen.function_call(function)
+    }
+
+    /// Call trap `host.name`. `num_args` is the argument count of a call expression, pushed for
+    /// variadic traps; `None` for the `trap` directive, where the code pushes it by hand.
+    fn trap(
+        &mut self,
+        codegen: Codegen,
+        pos: Pos,
+        host: &str,
+        name: &str,
+        num_args: Option<usize>,
+    ) -> Codegen {
+        if let Some(function_host) = self.host {
+            if function_host != host {
+                self.reporter.error(
+                    pos,
+                    format!(
+                        "function {} runs on host type {function_host}, it can't use trap {host}.{name}",
+                        self.name
+                    ),
+                );
+                return codegen;
+            }
+        }
+        let key = (host.to_string(), name.to_string());
+        match self.symbols.traps.get(&key) {
+            Some(v) => {
+                let codegen = match num_args {
+                    Some(n) if self.symbols.variadic_traps.contains(&key) => {
+                        match u8::try_from(n) {
+                            Ok(n) => codegen.loadi_u8(n),
+                            Err(_) => {
+                                self.reporter.error(
+                                    pos,
+                                    format!("too many arguments for trap {host}.{name} ({n})"),
+                                );
+                                codegen
+                            }
+                        }
+                    }
+                    _ => codegen,
+                };
+                self.info.trap_hosts.insert(key.0);
+                codegen.loadi_u8(*v).trap()
+            }
+            None => {
+                self.reporter
+                    .error(pos, format!("undeclared trap {host}.{name}"));
+                codegen
+            }
+        }
+    }
+
+    fn enum_value(&mut self, pos: Pos, name: &str) -> Option<u8> {
+        match self.symbols.enums.get(name) {
+            Some(v) if *v <= u8::MAX as usize => Some(*v as u8),
+            Some(v) => {
+                self.reporter
+                    .error(pos, format!("enum {name} = {v} does not fit into u8"));
+                None
+            }
+            None => {
+                self.reporter.error(pos, format!("unknown enum {name}"));
+                None
+            }
+        }
+    }
+}
+
+/// Lower the function block in `source` with the enums SND, SND_B and a few weapon traps.
+/// Returns the error messages on failure.
+#[cfg(test)]
+fn lower_source(source: &str) -> Result<(Codegen, FunctionInfo), Vec<String>> {
+    use super::parser::{parse_function_block, util::Span};
+    let (_, super::ast::ToplevelElement::FunctionBlock(function_block)) =
+        parse_function_block(Span::new_extra(source, 0)).unwrap()
+    else {
+        panic!("not a function block");
+    };
+    let enums = BTreeMap::from([("SND".to_string(), 3), ("SND_B".to_string(), 5)]);
+    let trap = |name: &str| ("weapon".to_string(), name.to_string());
+    let traps = BTreeMap::from([
+        (trap("fire"), 0),
+        (trap("play_random_sound"), 1),
+        (trap("play_sound"), 4),
+    ]);
+    let variadic_traps = BTreeSet::from([trap("play_random_sound")]);
+    let symbols = Symbols {
+        enums: &enums,
+        traps: &traps,
+        variadic_traps: &variadic_traps,
+    };
+    let mut reporter = Reporter::default();
+    reporter.add_file("test.st", source);
+    let (codegen, info) = lower_function(&mut reporter, &symbols, &function_block);
+    match reporter.finish() {
+        Ok(_) => Ok((codegen, info)),
+        Err(diagnostics) => Err(diagnostics.0.into_iter().map(|d| d.message).collect()),
+    }
+}
+
+#[cfg(test)]
+fn assert_lowers_to(body: &str, expected: Codegen) {
+    let source = format!("function Check: weapon {{\n{body}\n}}");
+    let (codegen, _) = lower_source(&source).unwrap();
+    let no_labels = std::collections::HashMap::new();
+    assert_eq!(
+        codegen.with_state_label_ptrs(&no_labels).finalize(),
+        expected.stop().with_state_label_ptrs(&no_labels).finalize(),
+        "{body}"
+    );
+}
+
+#[cfg(test)]
+fn lower_errors(body: &str) -> Vec<String> {
+    match lower_source(&format!("function Check: weapon {{\n{body}\n}}")) {
+        Ok(_) => panic!("{body} lowered without errors"),
+        Err(messages) => messages,
+    }
+}
+
+#[test]
+fn test_lower_if() {
+    let source = "function Check: weapon {\n    if !fire() && 1 < 2 {\n        play_sound(SND)\n    } else {\n        goto attack\n    }\n}";
+    let (codegen, info) = lower_source(source).unwrap();
+    assert_eq!(info.state_labels.len(), 1);
+    let label_ptrs = std::collections::HashMap::from([("attack".to_string(), 0)]);
+    let expected = Codegen::default()
+        .loadi_u8(0)
+        .trap()
+        .bin_not()
+        .loadi_i32(1)
+        .loadi_i32(2)
+        .clt()
+        .bin_and()
+        .bin_not()
+        .jrc_label("else")
+        .loadi_u8(3)
+        .loadi_u8(4)
+        .trap()
+        .jmp_label("end")
+        .label("else")
+        .loadsl("attack")
+        .gostate()
+        .label("end")
+        .stop();
+    assert_eq!(
+        codegen.with_state_label_ptrs(&label_ptrs).finalize(),
+        expected.with_state_label_ptrs(&label_ptrs).finalize()
+    );
+}
+
+#[test]
+fn test_lower_expressions() {
+    assert_lowers_to(
+        "wait 2 * -3 + 10 / 5 + 6",
+        Codegen::default()
+            .loadi_i32(2)
+            .loadi_i32(-3)
+            .mul()
+            .loadi_i32(10)
+            .loadi_i32(5)
+            .div()
+            .add()
+            .loadi_i32(6)
+            .add()
+            .wait(),
+    );
+    assert_lowers_to(
+        "if SND != SND_B || 1 >= 2 {\n    return\n}",
+        Codegen::default()
+            .loadi_u8(3)
+            .loadi_u8(5)
+            .ceq()
+            .bin_not()
+            .loadi_i32(1)
+            .loadi_i32(2)
+            .cge()
+            .bin_or()
+            .bin_not()
+            .jrc_label("else")
+            .stop()
+            .label("else"),
+    );
+    // results of traps are not typed
+    assert_lowers_to(
+        "if fire() == 1 {\n    return\n}",
+        Codegen::default()
+            .loadi_u8(0)
+            .trap()
+            .loadi_i32(1)
+            .ceq()
+            .bin_not()
+            .jrc_label("else")
+            .stop()
+            .label("else"),
+    );
+    // variadic traps get the argument count pushed
+    assert_lowers_to(
+        "play_random_sound(SND, SND_B)",
+        Codegen::default()
+            .loadi_u8(3)
+            .loadi_u8(5)
+            .loadi_u8(2)
+            .loadi_u8(1)
+            .trap(),
+    );
+}
+
+#[test]
+fn test_lower_type_errors() {
+    let cases = [
+        (
+            "if SND == 1 {\n    return\n}",
+            "mismatched operand types u8 and i32 for operator ==",
+        ),
+        ("wait SND + 2", "operator + needs i32 operands, found u8"),
+        (
+            "if (1 < 2) < 3 {\n    return\n}",
+            "operator < needs i32 or u8 operands, found bool",
+        ),
+        (
+            "if 1 && fire() {\n    return\n}",
+            "operator && needs bool operands, found i32",
+        ),
+        (
+            "if fire() && !SND {\n    return\n}",
+            "operator ! needs a value of type bool, found u8",
+        ),
+        (
+            "wait -SND",
+            "operator - needs a value of type i32, found u8",
+        ),
+        (
+            "if 1 + 2 {\n    return\n}",
+            "if needs a value of type bool, found i32",
+        ),
+        ("wait SND", "wait needs a value of type i32, found u8"),
+        (
+            "wait 1 - 1",
+            "wait needs a positive number of ticks, found 0",
+        ),
+    ];
+    for (body, message) in cases {
+        assert_eq!(lower_errors(body), [message], "{body}");
+    }
+}
@@ -0,0 +1,380 @@
+//! Peephole optimizer for the bytecode of a single block. The passes run until nothing changes:
+//!
+//! - constant folding of arithmetic on `loadii32` operands
+//! - `not; not` after a comparison or logic operator is removed, `not; jrc` becomes `jrnc` (and
+//!   `not; jrnc` becomes `jrc`)
+//! - code after `stop`, `gostate` and `jmp` that no jump reaches is removed
+//! - jumps to the next instruction are removed
+//!
+//! Sharing code between blocks (identical blocks and tails) is done when the blocks are placed in
+//! the image, see `codegen::BytecodeOutput`.
+
+use crate::opcode::Instruction;
+
+#[derive(Debug, Clone, Copy)]
+struct Op {
+    instruction: Instruction,
+    /// jump target as index into the ops, `ops.len()` is the end of the block
+    target: Option<usize>,
+}
+
+/// Decode a finalized block. None if it isn't a self-contained sequence of instructions, e.g.
+/// because of a jump out of the block; such code is left alone.
+fn decode(code: &[u8]) -> Option<Vec<Op>> {
+    let mut cursor = std::io::Cursor::new(code);
+    let mut positions = Vec::new();
+    let mut instructions = Vec::new();
+    while (cursor.position() as usize) < code.len() {
+        positions.push(cursor.position() as usize);
+        instructions.push(Instruction::decode(&mut cursor).ok()?);
+    }
+    positions.push(code.len());
+    instructions
+        .iter()
+        .zip(&positions)
+        .map(|(instruction, pos)| {
+            let target = match instruction.jump_target(*pos) {
+                Some(target) => Some(positions.binary_search(&target).ok()?),
+                None => None,
+            };
+            Some(Op {
+                instruction: *instruction,
+                target,
+            })
+        })
+        .collect()
+}
+
+fn encode(ops: &[Op]) -> Vec<u8> {
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let mut pos = 0;
+    for op in ops {
+        positions.push(pos);
+        pos += op.instruction.size();
+    }
+    positions.push(pos);
+    let mut code = Vec::with_capacity(pos);
+    for (i, op) in ops.iter().enumerate() {
+        let mut instruction = op.instruction;
+        if let Some(target) = op.target {
+            let offs = positions[target] as i32 - positions[i + 1] as i32;
+            instruction = match instruction {
+                Instruction::Jrc(_) => Instruction::Jrc(offs),
+                Instruction::Jrnc(_) => Instruction::Jrnc(offs),
+                Instruction::Jmp(_) => Instruction::Jmp(offs),
+                other => other,
+            };
+        }
+        instruction.encode(&mut code);
+    }
+    code
+}
+
+fn fold(a: i32, b: i32, instruction: Instruction) -> Option<i32> {
+    match instruction {
+        Instruction::Add => a.checked_add(b),
+        Instruction::Sub => a.checked_sub(b),
+        Instruction::Mul => a.checked_mul(b),
+        // division by zero is left to fail at runtime
+        Instruction::Div => a.checked_div(b),
+        _ => None,
+    }
+}
+
+/// True if `instruction` always pushes a Bool. NOT fails on anything else, so `not; not` can
+/// only be dropped after one of these.
+fn pushes_bool(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Ceq
+            | Instruction::Clt
+            | Instruction::Cgt
+            | Instruction::Cle
+            | Instruction::Cge
+            | Instruction::Not
+            | Instruction::And
+            | Instruction::Or
+    )
+}
+
+/// One round of all peephole rules. Returns false if nothing changed.
+fn peephole(ops: &mut Vec<Op>) -> bool {
+    let mut is_target = vec![false; ops.len() + 1];
+    for op in ops.iter() {
+        if let Some(target) = op.target {
+            is_target[target] = true;
+        }
+    }
+    // an instruction can be merged into its predecessor if no jump lands between them
+    let joinable = |i: usize| !is_target[i];
+
+    let mut removed = vec![false; ops.len()];
+    let mut i = 0;
+    while i < ops.len() {
+        let rest = &ops[i..];
+        match rest {
+            [Op {
+                instruction: Instruction::LoadiI32(a),
+                ..
+            }, Op {
+                instruction: Instruction::LoadiI32(b),
+                ..
+            }, op, ..]
+                if joinable(i + 1) && joinable(i + 2) =>
+            {
+                if let Some(v) = fold(*a, *b, op.instruction) {
+                    ops[i].instruction = Instruction::LoadiI32(v);
+                    removed[i + 1] = true;
+                    removed[i + 2] = true;
+                    i += 3;
+                    continue;
+                }
+            }
+            [Op {
+                instruction: Instruction::LoadiI32(a),
+                ..
+            }, Op {
+                instruction: Instruction::Neg,
+                ..
+            }, ..]
+                if joinable(i + 1) =>
+            {
+                if let Some(v) = a.checked_neg() {
+                    ops[i].instruction = Instruction::LoadiI32(v);
+                    removed[i + 1] = true;
+                    i += 2;
+                    continue;
+                }
+            }
+            [Op {
+                instruction: Instruction::Not,
+                ..
+            }, next, ..]
+                if joinable(i + 1) =>
+            {
+                let inverted = match next.instruction {
+                    Instruction::Not
+                        if i > 0
+                            && joinable(i)
+                            && !removed[i - 1]
+                            && pushes_bool(ops[i - 1].instruction) =>
+                    {
+                        None
+                    }
+                    Instruction::Jrc(offs) => Some(Instruction::Jrnc(offs)),
+                    Instruction::Jrnc(offs) => Some(Instruction::Jrc(offs)),
+                    _ => {
+                        i += 1;
+                        continue;
+                    }
+                };
+                match inverted {
+                    // `not; not` on a Bool: nothing jumps to the first one
+                    None => removed[i] = true,
+                    Some(instruction) => {
+                        ops[i] = Op {
+                            instruction,
+                            target: next.target,
+                        }
+                    }
+                }
+                removed[i + 1] = true;
+                i += 2;
+                continue;
+            }
+            [op, ..] if op.instruction.is_terminator() => {
+                let mut j = i + 1;
+                while j < ops.len() && !is_target[j] {
+                    removed[j] = true;
+                    j += 1;
+                }
+                if let (Instruction::Jmp(_), Some(target)) = (op.instruction, op.target) {
+                    // jump to the next live instruction
+                    if target == j {
+                        removed[i] = true;
+                    }
+                }
+                i = j;
+                continue;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+
+    if !removed.contains(&true) {
+        return false;
+    }
+    // jumps to a removed instruction continue at the next one that is kept
+    let mut new_index = Vec::with_capacity(ops.len() + 1);
+    let mut kept = 0;
+    for r in &removed {
+        new_index.push(kept);
+        if !r {
+            kept += 1;
+        }
+    }
+    new_index.push(kept);
+    let old_ops = std::mem::take(ops);
+    for (op, r) in old_ops.into_iter().zip(removed) {
+        if !r {
+            ops.push(Op {
+                instruction: op.instruction,
+                target: op.target.map(|target| new_index[target]),
+            });
+        }
+    }
+    true
+}
+
+/// Optimize a finalized block, see the module documentation
+pub fn optimize(code: Vec<u8>) -> Vec<u8> {
+    let Some(mut ops) = decode(&code) else {
+        return code;
+    };
+    let mut changed = false;
+    while peephole(&mut ops) {
+        changed = true;
+    }
+    if changed {
+        encode(&ops)
+    } else {
+        code
+    }
+}
+
+/// Instruction positions of a block and its jumps, used to find tails that can be shared
+pub struct Layout {
+    pub boundaries: Vec<usize>,
+    /// position and target of each jump
+    pub jumps: Vec<(usize, usize)>,
+}
+
+pub fn layout(code: &[u8]) -> Option<Layout> {
+    let mut cursor = std::io::Cursor::new(code);
+    let mut layout = Layout {
+        boundaries: Vec::new(),
+        jumps: Vec::new(),
+    };
+    while (cursor.position() as usize) < code.len() {
+        let pos = cursor.position() as usize;
+        layout.boundaries.push(pos);
+        let instruction = Instruction::decode(&mut cursor).ok()?;
+        if let Some(target) = instruction.jump_target(pos) {
+            layout.jumps.push((pos, target));
+        }
+    }
+    Some(layout)
+}
+
+/// Run `code` from `offs` with `regs` until it stops, returning the events and the final
+/// environment
+#[cfg(test)]
+pub(super) fn run(
+    code: &[u8],
+    offs: usize,
+    regs: [i32; crate::opcode::NUM_REGS],
+) -> (Vec<String>, crate::opcode::Env) {
+    use crate::opcode::{exec, Env, Event};
+
+    let mut env = Env::with_regs(regs);
+    let mut cursor = std::io::Cursor::new(code);
+    cursor.set_position(offs as u64);
+    let mut events = Vec::new();
+    loop {
+        match exec(&mut cursor, &mut env) {
+            Ok(Event::Stop) => return (events, env),
+            Ok(Event::GoState) => {
+                events.push(format!("gostate {:?}", env.stack.pop()));
+                return (events, env);
+            }
+            Ok(event) => events.push(format!("{event:?}")),
+            Err(err) => {
+                events.push(format!("error {err}"));
+                return (events, env);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_optimize() {
+    use crate::opcode::Codegen;
+
+    let code = Codegen::default()
+        .loadi_i32(2)
+        .loadi_i32(3)
+        .add()
+        .loadi_i32(4)
+        .mul()
+        .store_i32(1)
+        .load_i32(0)
+        .loadi_i32(0)
+        .cgt()
+        .bin_not()
+        .jrc_label("else")
+        .loadi_i32(7)
+        .bin_neg()
+        .store_i32(2)
+        .jmp_label("end")
+        .loadi_u8(0) // dead
+        .trap()
+        .label("else")
+        .load_i32(0)
+        .loadi_i32(0)
+        .ceq()
+        .bin_not()
+        .bin_not()
+        .jrc_label("end")
+        .loadi_i32(100)
+        .gostate()
+        .label("end")
+        .stop()
+        .stop() // dead
+        .finalize();
+    let optimized = optimize(code.clone());
+    assert!(optimized.len() < code.len());
+    let instructions: Vec<_> = crate::opcode::decode_block(&optimized, 0)
+        .unwrap()
+        .into_iter()
+        .map(|(_, instruction)| instruction)
+        .collect();
+    assert_eq!(instructions[0], Instruction::LoadiI32(20));
+    assert!(instructions.contains(&Instruction::LoadiI32(-7)));
+    assert!(matches!(instructions[5], Instruction::Jrnc(_)));
+    assert!(!instructions.contains(&Instruction::Not));
+    assert!(!instructions.contains(&Instruction::Trap));
+    assert_eq!(instructions.last(), Some(&Instruction::Stop));
+
+    for r0 in [-1, 0, 1] {
+        let mut regs = [0; crate::opcode::NUM_REGS];
+        regs[0] = r0;
+        let (events, env) = run(&code, 0, regs);
+        let (optimized_events, optimized_env) = run(&optimized, 0, regs);
+        assert_eq!(events, optimized_events);
+        assert_eq!(env.regs, optimized_env.regs);
+        assert_eq!(env.stack, optimized_env.stack);
+    }
+    let (events, env) = run(&optimized, 0, [0; crate::opcode::NUM_REGS]);
+    assert!(events.is_empty());
+    assert_eq!(env.regs[1], 20);
+    assert!(env.stack.is_empty());
+
+    // nothing to do: the code is returned as is
+    let code = Codegen::default()
+        .load_i32(0)
+        .store_i32(1)
+        .stop()
+        .finalize();
+    assert_eq!(optimize(code.clone()), code);
+
+    // `not; not` fails on anything but a Bool, keep it unless the operand is one
+    let code = Codegen::default()
+        .load_i32(0)
+        .bin_not()
+        .bin_not()
+        .store_i32(1)
+        .stop()
+        .finalize();
+    assert_eq!(optimize(code.clone()), code);
+}
@@ -1,35 +1,68 @@
-use self::util::{handle_unexpected, is_identifier, ws, MyError, Res, Span};
+use self::util::{handle_unexpected, is_identifier, token_pos, ws, MyError, Res, Span};
 use super::ast::{
-    EnumDecl, FunctionBlock, FunctionBlockElement, SpawnBlock, StatesBlock, StatesBlockElement,
-    ToplevelElement,
+    EnemyProperty, EntryCondition, EntryDecl, EnumDecl, FunctionBlock, FunctionBlockElement,
+    Include, Pos, SpawnBlock, StatesBlock, StatesBlockElement, StatesInstance, TemplateDecl,
+    ToplevelElement, TrapDecl,
 };
-use crate::{Direction, EnemySpawnInfo};
+use crate::{opcode::NUM_REGS, Direction, EnemySpawnInfo, PainBehavior};
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, take_till1, take_while},
-    character::complete::{alpha1, alphanumeric1, char, one_of},
-    combinator::{recognize, value},
-    multi::{many0, many0_count, many1, separated_list0},
-    sequence::{delimited, pair, terminated},
+    bytes::complete::{is_not, tag, take_till1, take_while, take_while1},
+    character::complete::{alpha1, alphanumeric1, char, multispace0, one_of, space1},
+    combinator::{cut, map, opt, recognize, value},
+    multi::{many0, many0_count, many1, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
 };
 
+pub mod statement;
 pub mod util;
 
 fn parse_enum_name(input: Span) -> Res<'_, Span> {
-    ws(take_while(is_identifier))(input)
+    ws(take_while1(is_identifier))(input)
 }
 
-fn parse_enum_body(input: Span) -> Res<'_, Vec<Span>> {
-    separated_list0(tag(","), parse_enum_name)(input)
+/// `<name>` or `<name> = <value>`
+fn parse_enum_variant(input: Span<'_>) -> Res<'_, (Span<'_>, Option<i32>)> {
+    pair(
+        parse_enum_name,
+        opt(preceded(ws(char('=')), cut(ws(decimal)))),
+    )(input)
+}
+
+/// Comma separated variants, a trailing comma is allowed
+fn parse_enum_body(input: Span) -> Res<'_, Vec<(Span, Option<i32>)>> {
+    terminated(
+        separated_list0(tag(","), parse_enum_variant),
+        opt(ws(tag(","))),
+    )(input)
 }
 
 fn parse_enum_decl(input: Span) -> Res<'_, ToplevelElement> {
+    let (input, pos) = token_pos(input)?;
     let (input, _) = ws(tag("enum"))(input)?;
-    let (input, enum_names) = delimited(char('{'), parse_enum_body, char('}'))(input)?;
+    let (input, name) = opt(ws(identifier))(input)?;
+    let (input, variants) = cut(delimited(char('{'), parse_enum_body, char('}')))(input)?;
+    // like in C, a variant without a value follows the previous one
+    let mut next = 0;
+    let mut values = Vec::new();
+    for (variant, value) in variants {
+        if let Some(value) = value {
+            next = usize::try_from(value).map_err(|_| {
+                nom::Err::Failure(MyError::Custom(
+                    variant,
+                    format!("negative enum value {value}"),
+                ))
+            })?;
+        }
+        values.push((variant.to_string(), next));
+        next += 1;
+    }
     Ok((
         input,
         ToplevelElement::EnumDecl(EnumDecl {
-            names: enum_names.iter().map(|v| v.to_string()).collect(),
+            name,
+            variants: values,
+            pos,
         }),
     ))
 }
@@ -48,8 +81,26 @@ pub fn identifier(input: Span) -> Res<'_, String> {
 }
 
 fn decimal(input: Span) -> Res<'_, i32> {
-    recognize(many1(terminated(one_of("-0123456789"), many0(char('_')))))(input)
-        .map(|(i, s)| (i, s.parse::<i32>().expect("failed to parse integer {s}")))
+    let (rest, s) = recognize(many1(terminated(one_of("-0123456789"), many0(char('_')))))(input)?;
+    match s.parse::<i32>() {
+        Ok(v) => Ok((rest, v)),
+        Err(_) => Err(nom::Err::Failure(MyError::Custom(
+            s,
+            format!("invalid integer `{s}`"),
+        ))),
+    }
+}
+
+fn line_comment(input: Span<'_>) -> Res<'_, ()> {
+    value(
+        (), // Output is thrown away.
+        pair(tag("//"), is_not("\n\r")),
+    )(input)
+}
+
+/// Closing brace of a block, optionally preceded by comments
+fn block_end(input: Span<'_>) -> Res<'_, char> {
+    preceded(many0(ws(line_comment)), ws(char('}')))(input)
 }
 
 fn boolean(input: Span) -> Res<'_, bool> {
@@ -57,8 +108,9 @@ fn boolean(input: Span) -> Res<'_, bool> {
 }
 
 fn parse_state(input: Span) -> Res<'_, StatesBlockElement> {
+    let (input, pos) = token_pos(input)?;
     let (input, _) = ws(tag("state"))(input)?;
-    let (input, (id, directional, ticks, think, action, next)) = parse_state_body(input)?;
+    let (input, (id, directional, ticks, think, action, next)) = cut(parse_state_body)(input)?;
 
     Ok((
         input,
@@ -69,20 +121,30 @@ fn parse_state(input: Span) -> Res<'_, StatesBlockElement> {
             think,
             action,
             next,
+            pos,
         },
     ))
 }
 
+/// Identifier that may contain `{param}` placeholders, e.g. `{prefix}_W1_1`
+fn templated_identifier(input: Span<'_>) -> Res<'_, String> {
+    recognize(many1(alt((
+        take_while1(is_identifier),
+        recognize(delimited(char('{'), identifier, char('}'))),
+    ))))(input)
+    .map(|(i, s)| (i, s.to_string()))
+}
+
 fn parse_state_body(input: Span) -> Res<'_, (String, bool, i32, String, String, String)> {
-    let (input, id) = ws(identifier)(input)?;
+    let (input, id) = ws(templated_identifier)(input)?;
     let (input, _) = char(',')(input)?;
     let (input, directional) = ws(boolean)(input)?;
     let (input, _) = char(',')(input)?;
     let (input, ticks) = ws(decimal)(input)?;
     let (input, _) = char(',')(input)?;
-    let (input, think) = ws(identifier)(input)?;
+    let (input, think) = ws(templated_identifier)(input)?;
     let (input, _) = char(',')(input)?;
-    let (input, action) = ws(identifier)(input)?;
+    let (input, action) = ws(templated_identifier)(input)?;
     let (input, _) = char(',')(input)?;
     let (input, next) = ws(identifier)(input)?;
 
@@ -95,35 +157,178 @@ fn states_block_element(input: Span) -> Res<'_, StatesBlockElement> {
 
 fn parse_states_block(input: Span) -> Res<'_, ToplevelElement> {
     let (input, _) = ws(tag("states"))(input)?;
+    let (input, pos) = token_pos(input)?;
     let (input, name) = ws(take_while(is_identifier))(input)?;
-    let (input, elements) =
-        delimited(ws(char('{')), many0(states_block_element), ws(char('}')))(input)?;
+    let (input, host) = opt(preceded(char(':'), ws(identifier)))(input)?;
+    let (input, elements) = cut(delimited(
+        ws(char('{')),
+        many0(states_block_element),
+        block_end,
+    ))(input)?;
 
     Ok((
         input,
         ToplevelElement::StatesBlock(StatesBlock {
             name: name.to_string(),
+            host,
+            pos,
             elements,
         }),
     ))
 }
 
-fn spawn_on_death(name: &str) -> Option<i32> {
-    match name {
-        "ammo" => Some(49),
-        "silver_key" => Some(43),
-        "grofaz" => Some(224), // FIXME: abuse blinky
-        _ => None,
+fn parse_include(input: Span<'_>) -> Res<'_, ToplevelElement> {
+    let (input, _) = ws(tag("include"))(input)?;
+    let (input, pos) = token_pos(input)?;
+    let (input, path) = cut(ws(delimited(char('"'), is_not("\"\n"), char('"'))))(input)?;
+    Ok((
+        input,
+        ToplevelElement::Include(Include {
+            path: path.to_string(),
+            pos,
+        }),
+    ))
+}
+
+fn parse_template_decl(input: Span<'_>) -> Res<'_, ToplevelElement> {
+    let (input, _) = ws(tag("template"))(input)?;
+    let (input, pos) = token_pos(input)?;
+    let (input, name) = cut(ws(identifier))(input)?;
+    let (input, params) = cut(delimited(
+        char('('),
+        separated_list0(char(','), ws(identifier)),
+        char(')'),
+    ))(input)?;
+    let (input, host) = opt(preceded(ws(char(':')), ws(identifier)))(input)?;
+    let (input, elements) = cut(delimited(
+        ws(char('{')),
+        many0(states_block_element),
+        block_end,
+    ))(input)?;
+    Ok((
+        input,
+        ToplevelElement::TemplateDecl(TemplateDecl {
+            name,
+            params,
+            host,
+            pos,
+            elements,
+        }),
+    ))
+}
+
+fn parse_states_instance(input: Span<'_>) -> Res<'_, ToplevelElement> {
+    let (input, _) = ws(tag("states"))(input)?;
+    let (input, pos) = token_pos(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    let (input, _) = char('=')(input)?;
+    let (input, template) = cut(ws(identifier))(input)?;
+    let (input, args) = cut(delimited(
+        char('('),
+        separated_list0(char(','), ws(take_while1(is_identifier))),
+        char(')'),
+    ))(input)?;
+    let (input, elements) = opt(delimited(
+        ws(char('{')),
+        many0(states_block_element),
+        cut(block_end),
+    ))(input)?;
+    Ok((
+        input,
+        ToplevelElement::StatesInstance(StatesInstance {
+            name,
+            template,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            pos,
+            elements: elements.unwrap_or_default(),
+        }),
+    ))
+}
+
+/// `<host>.<name>`
+fn parse_trap_name(input: Span<'_>) -> Res<'_, (String, String)> {
+    ws(separated_pair(identifier, char('.'), identifier))(input)
+}
+
+fn parse_trap_decl(input: Span<'_>) -> Res<'_, ToplevelElement> {
+    let (input, _) = ws(tag("trap"))(input)?;
+    let (input, pos) = token_pos(input)?;
+    let (input, (host, name)) = cut(parse_trap_name)(input)?;
+    let (input, variadic) = opt(ws(tag("(..)")))(input)?;
+    let (input, _) = cut(char('='))(input)?;
+    let (input, _) = multispace0(input)?;
+    let value_input = input;
+    let (input, value) = cut(ws(decimal))(input)?;
+    if value > u8::MAX as i32 || value < 0 {
+        return Err(nom::Err::Failure(MyError::Custom(
+            value_input,
+            format!("trap number out of range {value} (0..=255)"),
+        )));
     }
+    Ok((
+        input,
+        ToplevelElement::TrapDecl(TrapDecl {
+            host,
+            name,
+            value: value as u8,
+            variadic: variadic.is_some(),
+            pos,
+        }),
+    ))
 }
 
-fn spawn_block_directional_element(input: Span) -> Res<'_, Vec<EnemySpawnInfo>> {
-    let (input, _) = ws(tag("directional"))(input)?;
-    let (input, start_id) = ws(decimal)(input)?;
+/// `pain <behavior>` or the name of a native function
+fn entry_condition(input: Span<'_>) -> Res<'_, EntryCondition> {
+    alt((
+        preceded(
+            ws(tag("pain")),
+            cut(map(pain_behavior, EntryCondition::Pain)),
+        ),
+        map(ws(identifier), EntryCondition::Calls),
+    ))(input)
+}
+
+/// `entry <host>.<label> [if <condition>, ..]`
+fn parse_entry_decl(input: Span<'_>) -> Res<'_, ToplevelElement> {
+    let (input, _) = ws(tag("entry"))(input)?;
+    let (input, pos) = token_pos(input)?;
+    let (input, (host, label)) = cut(parse_trap_name)(input)?;
+    let (input, conditions) = opt(preceded(
+        ws(tag("if")),
+        cut(separated_list1(char(','), entry_condition)),
+    ))(input)?;
+    Ok((
+        input,
+        ToplevelElement::EntryDecl(EntryDecl {
+            host,
+            label,
+            conditions: conditions.unwrap_or_default(),
+            pos,
+        }),
+    ))
+}
+
+/// `<id>, <state label>[, easy|medium|hard]`. The skill level is the lowest one the enemy
+/// appears on, all of them if omitted.
+fn spawn_element_body(input: Span<'_>) -> Res<'_, (i32, String, u8)> {
+    let (input, id) = ws(decimal)(input)?;
     let (input, _) = char(',')(input)?;
     let (input, state) = ws(identifier)(input)?;
-    let (input, _) = char(',')(input)?;
-    let (input, bonus_item_name) = ws(identifier)(input)?;
+    let (input, skill) = opt(preceded(
+        char(','),
+        cut(ws(alt((
+            value(0, tag("easy")),
+            value(1, tag("medium")),
+            value(2, tag("hard")),
+        )))),
+    ))(input)?;
+    Ok((input, (id, state, skill.unwrap_or(0))))
+}
+
+fn spawn_block_directional_element(input: Span) -> Res<'_, Vec<(Pos, EnemySpawnInfo)>> {
+    let (input, pos) = token_pos(input)?;
+    let (input, _) = ws(tag("directional"))(input)?;
+    let (input, (start_id, state, skill)) = cut(spawn_element_body)(input)?;
 
     let mut infos = Vec::new();
 
@@ -136,66 +341,148 @@ fn spawn_block_directional_element(input: Span) -> Res<'_, Vec<EnemySpawnInfo>>
     .iter()
     .enumerate()
     {
-        infos.push(EnemySpawnInfo {
-            id: start_id + i as i32,
-            direction: *direction,
-            state: state.clone(),
-            spawn_on_death: spawn_on_death(&bonus_item_name),
-        })
+        infos.push((
+            pos,
+            EnemySpawnInfo {
+                id: start_id + i as i32,
+                direction: *direction,
+                state: state.clone(),
+                enemy: String::new(),
+                skill,
+            },
+        ))
     }
     Ok((input, infos))
 }
-fn spawn_block_undirectional_element(input: Span) -> Res<'_, Vec<EnemySpawnInfo>> {
+fn spawn_block_undirectional_element(input: Span) -> Res<'_, Vec<(Pos, EnemySpawnInfo)>> {
+    let (input, pos) = token_pos(input)?;
     let (input, _) = ws(tag("undirectional"))(input)?;
-    let (input, id) = ws(decimal)(input)?;
-    let (input, _) = char(',')(input)?;
-    let (input, state) = ws(identifier)(input)?;
-    let (input, _) = char(',')(input)?;
-    let (input, bonus_item_name) = ws(identifier)(input)?;
-
-    let infos = vec![EnemySpawnInfo {
-        id,
-        direction: Direction::South, // FIXME: not really undirectional
-        state,
-        spawn_on_death: spawn_on_death(&bonus_item_name),
-    }];
+    let (input, (id, state, skill)) = cut(spawn_element_body)(input)?;
+
+    let infos = vec![(
+        pos,
+        EnemySpawnInfo {
+            id,
+            direction: Direction::South, // FIXME: not really undirectional
+            state,
+            enemy: String::new(),
+            skill,
+        },
+    )];
     Ok((input, infos))
 }
 
+/// `health <easy>, <medium>, <hard>`, `can_open_doors <bool>`, `drop <map id enum>`,
+/// `speed <path>, <chase>`, `points <n>` or `pain alternate|never`
+fn spawn_block_property(input: Span<'_>) -> Res<'_, (Pos, EnemyProperty)> {
+    let (input, pos) = token_pos(input)?;
+    let comma = || ws(char(','));
+    let (input, property) = alt((
+        preceded(
+            ws(tag("health")),
+            cut(map(
+                tuple((ws(decimal), comma(), ws(decimal), comma(), ws(decimal))),
+                |(easy, _, medium, _, hard)| EnemyProperty::Health([easy, medium, hard]),
+            )),
+        ),
+        preceded(
+            ws(tag("can_open_doors")),
+            cut(map(ws(boolean), EnemyProperty::CanOpenDoors)),
+        ),
+        preceded(
+            ws(tag("drop")),
+            cut(map(ws(identifier), EnemyProperty::Drop)),
+        ),
+        preceded(
+            ws(tag("speed")),
+            cut(map(
+                separated_pair(ws(decimal), comma(), ws(decimal)),
+                |(path, chase)| EnemyProperty::Speed { path, chase },
+            )),
+        ),
+        preceded(
+            ws(tag("points")),
+            cut(map(ws(decimal), EnemyProperty::Points)),
+        ),
+        preceded(
+            ws(tag("pain")),
+            cut(map(pain_behavior, EnemyProperty::Pain)),
+        ),
+    ))(input)?;
+    Ok((input, (pos, property)))
+}
+
+fn pain_behavior(input: Span<'_>) -> Res<'_, PainBehavior> {
+    ws(alt((
+        value(PainBehavior::Alternate, tag("alternate")),
+        value(PainBehavior::Never, tag("never")),
+    )))(input)
+}
+
+enum SpawnBlockElement {
+    Spawn(Vec<(Pos, EnemySpawnInfo)>),
+    Property((Pos, EnemyProperty)),
+}
+
 fn parse_spawn_block(input: Span) -> Res<'_, ToplevelElement> {
     let (input, _) = ws(tag("spawn"))(input)?;
+    let (input, pos) = token_pos(input)?;
     let (input, name) = ws(take_while(is_identifier))(input)?;
-    let (input, mut elements) = delimited(
+    let (input, elements) = cut(delimited(
         ws(char('{')),
-        many0(alt((
-            spawn_block_directional_element,
-            spawn_block_undirectional_element,
-        ))),
-        ws(char('}')),
-    )(input)?;
+        many0(preceded(
+            many0(ws(line_comment)),
+            alt((
+                map(spawn_block_directional_element, SpawnBlockElement::Spawn),
+                map(spawn_block_undirectional_element, SpawnBlockElement::Spawn),
+                map(spawn_block_property, SpawnBlockElement::Property),
+            )),
+        )),
+        block_end,
+    ))(input)?;
 
-    let infos = elements.drain(..).flatten().collect();
+    let mut infos = Vec::new();
+    let mut properties = Vec::new();
+    for element in elements {
+        match element {
+            SpawnBlockElement::Spawn(spawn_infos) => infos.extend(spawn_infos),
+            SpawnBlockElement::Property(property) => properties.push(property),
+        }
+    }
 
     Ok((
         input,
         ToplevelElement::SpawnBlock(SpawnBlock {
             name: name.to_string(),
+            pos,
             infos,
+            properties,
         }),
     ))
 }
 
-pub fn parse_bytecode_directive(input: Span) -> Res<'_, FunctionBlockElement> {
-    // pub fn parse_load_i32(input: Span) -> Res<'_, FunctionBlockElement> {
-    //     let (input, _) = ws(tag("loadi32"))(input)?;
-    //     let (input, addr) = ws(decimal)(input)?;
-    //     Ok((input, FunctionBlockElement::LoadI32 { addr: addr as u8 }))
-    // }
-    // pub fn parse_store_i32(input: Span) -> Res<'_, FunctionBlockElement> {
-    //     let (input, _) = ws(tag("storei32"))(input)?;
-    //     let (input, addr) = ws(decimal)(input)?;
-    //     Ok((input, FunctionBlockElement::StoreI32 { addr: addr as u8 }))
-    // }
+pub fn parse_bytecode_directive(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+    pub fn parse_register_addr(input: Span<'_>) -> Res<'_, u8> {
+        let (input, _) = multispace0(input)?;
+        let (rest, addr) = ws(decimal)(input)?;
+        if !(0..NUM_REGS as i32).contains(&addr) {
+            return Err(nom::Err::Failure(MyError::Custom(
+                input,
+                format!("register address out of range {addr} (0..{NUM_REGS})"),
+            )));
+        }
+        Ok((rest, addr as u8))
+    }
+    pub fn parse_load_i32(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+        let (input, _) = ws(tag("loadi32"))(input)?;
+        let (input, addr) = parse_register_addr(input)?;
+        Ok((input, FunctionBlockElement::LoadI32 { addr }))
+    }
+    pub fn parse_store_i32(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+        let (input, _) = ws(tag("storei32"))(input)?;
+        let (input, addr) = parse_register_addr(input)?;
+        Ok((input, FunctionBlockElement::StoreI32 { addr }))
+    }
     pub fn parse_loadi_i32(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, _) = ws(tag("loadii32"))(input)?;
         let (input, value) = ws(decimal)(input)?;
@@ -213,9 +500,13 @@ pub fn parse_bytecode_directive(input: Span) -> Res<'_, FunctionBlockElement> {
     }
     pub fn parse_loadi_u8(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, _) = ws(tag("loadiu8"))(input)?;
+        let value_input = input;
         let (input, value) = ws(decimal)(input)?;
-        if value > u8::MAX as i32 {
-            panic!("u8 out of range {value}"); // FIXME: emit proper parse error
+        if !(0..=u8::MAX as i32).contains(&value) {
+            return Err(nom::Err::Failure(MyError::Custom(
+                value_input,
+                format!("u8 out of range {value}"),
+            )));
         }
         Ok((input, FunctionBlockElement::LoadiU8 { value: value as u8 }))
     }
@@ -223,6 +514,11 @@ pub fn parse_bytecode_directive(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, _) = ws(tag("add"))(input)?;
         Ok((input, FunctionBlockElement::Add))
     }
+    pub fn parse_named_trap(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+        let (input, _) = ws(tag("trap"))(input)?;
+        let (input, (host, name)) = parse_trap_name(input)?;
+        Ok((input, FunctionBlockElement::NamedTrap { host, name }))
+    }
     pub fn parse_trap(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, _) = ws(tag("trap"))(input)?;
         Ok((input, FunctionBlockElement::Trap))
@@ -249,6 +545,16 @@ pub fn parse_bytecode_directive(input: Span) -> Res<'_, FunctionBlockElement> {
             },
         ))
     }
+    pub fn parse_jmp(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+        let (input, _) = ws(tag("jmp"))(input)?;
+        let (input, label) = ws(take_while(is_identifier))(input)?;
+        Ok((
+            input,
+            FunctionBlockElement::Jmp {
+                label: label.to_string(),
+            },
+        ))
+    }
     pub fn parse_load_state_label(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, _) = ws(tag("loadsl"))(input)?;
         let (input, label) = ws(take_while(is_identifier))(input)?;
@@ -267,50 +573,85 @@ pub fn parse_bytecode_directive(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, label) = ws(terminated(take_while(is_identifier), char(':')))(input)?;
         Ok((input, FunctionBlockElement::Label(label.to_string())))
     }
+    /// operators without operand
+    pub fn parse_simple_op(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+        ws(alt((
+            value(FunctionBlockElement::Sub, tag("sub")),
+            value(FunctionBlockElement::Mul, tag("mul")),
+            value(FunctionBlockElement::Div, tag("div")),
+            value(FunctionBlockElement::Neg, tag("neg")),
+            value(FunctionBlockElement::Clt, tag("clt")),
+            value(FunctionBlockElement::Cgt, tag("cgt")),
+            value(FunctionBlockElement::Cle, tag("cle")),
+            value(FunctionBlockElement::Cge, tag("cge")),
+            value(FunctionBlockElement::And, tag("and")),
+            value(FunctionBlockElement::Or, tag("or")),
+            value(FunctionBlockElement::Pop, tag("pop")),
+            value(FunctionBlockElement::Swap, tag("swap")),
+            value(FunctionBlockElement::Dup, tag("dup")),
+        )))(input)
+    }
+    pub fn parse_named_call(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+        // the function name must be on the same line, a bare 'call' takes the id from the stack
+        let (input, _) = ws(terminated(tag("call"), space1))(input)?;
+        let (input, name) = ws(identifier)(input)?;
+        Ok((input, FunctionBlockElement::NamedFunctionCall { name }))
+    }
     pub fn parse_call(input: Span) -> Res<'_, FunctionBlockElement> {
         let (input, _) = ws(tag("call"))(input)?;
         Ok((input, FunctionBlockElement::FunctionCall))
     }
-    pub fn parse_line_comment(input: Span) -> Res<'_, ()> {
-        value(
-            (), // Output is thrown away.
-            pair(tag("//"), is_not("\n\r")),
-        )(input)
-    }
-    let (input, _) = many0(parse_line_comment)(input)?;
-    let (input, e) = ws(alt((
-        // parse_load_i32,
-        // parse_store_i32,
+    ws(alt((
+        // labels first: they are the only elements ending in ':', so a label that happens to
+        // start like a directive (e.g. 'order:') is not mistaken for one
+        parse_label,
+        parse_load_i32,
+        parse_store_i32,
+        parse_named_trap,
         parse_trap,
         parse_loadi_i32,
         parse_loadi_u8, // FIXME: identifier parsing is crap (accepts also digit in fist place). need to match this rule first.
         parse_loadi_u8_enum,
         parse_jrc,
+        parse_jmp,
         parse_load_state_label,
         parse_gostate,
         parse_add,
         parse_ceq,
         parse_not,
+        parse_named_call,
         parse_call,
-        parse_label,
         parse_stop,
-    )))(input)?;
-    let (input, _) = many0(parse_line_comment)(input)?;
-    Ok((input, e))
+        parse_simple_op,
+    )))(input)
 }
+
+/// Statement or assembly directive, with surrounding comments
+pub fn parse_function_element(input: Span<'_>) -> Res<'_, (Pos, FunctionBlockElement)> {
+    let (input, _) = many0(ws(line_comment))(input)?;
+    let (input, pos) = token_pos(input)?;
+    let (input, e) = alt((statement::parse_statement, parse_bytecode_directive))(input)?;
+    let (input, _) = many0(ws(line_comment))(input)?;
+    Ok((input, (pos, e)))
+}
+
 pub fn parse_function_block(input: Span) -> Res<'_, ToplevelElement> {
     let (input, _) = ws(tag("function"))(input)?;
+    let (input, pos) = token_pos(input)?;
     let (input, name) = ws(take_while(is_identifier))(input)?;
-    let (input, elements) = delimited(
+    let (input, host) = opt(preceded(char(':'), ws(identifier)))(input)?;
+    let (input, elements) = cut(delimited(
         ws(char('{')),
-        many0(alt((parse_bytecode_directive,))),
-        ws(char('}')),
-    )(input)?;
+        many0(parse_function_element),
+        block_end,
+    ))(input)?;
 
     Ok((
         input,
         ToplevelElement::FunctionBlock(FunctionBlock {
             name: name.to_string(),
+            host,
+            pos,
             elements,
         }),
     ))
@@ -319,11 +660,111 @@ pub fn parse_function_block(input: Span) -> Res<'_, ToplevelElement> {
 pub fn parse_toplevel(input: Span) -> Res<'_, ToplevelElement> {
     alt((
         parse_enum_decl,
+        parse_states_instance,
         parse_states_block,
         parse_spawn_block,
         parse_function_block,
+        parse_trap_decl,
+        parse_entry_decl,
+        parse_template_decl,
+        parse_include,
         handle_unexpected(take_till1(|c: char| c.is_whitespace()), |txt| {
-            MyError::Custom(format!("unexpected at toplevel: {txt:?}"))
+            MyError::Custom(txt, format!("unexpected at toplevel: {txt:?}"))
         }),
     ))(input)
 }
+
+const TOPLEVEL_KEYWORDS: [&str; 8] = [
+    "enum", "states", "spawn", "function", "trap", "entry", "template", "include",
+];
+
+/// Parse a whole file. After a syntax error parsing continues at the next line starting with a
+/// toplevel keyword, so all errors of a file are reported in one run.
+pub fn parse_toplevel_elements(input: Span<'_>) -> (Vec<ToplevelElement>, Vec<(Pos, String)>) {
+    let mut elements = Vec::new();
+    let mut errors = Vec::new();
+    let mut input = input;
+    loop {
+        if let Ok((rest, _)) = many0(ws(line_comment))(input) {
+            input = rest;
+        }
+        let (rest, _) = multispace0::<_, MyError<Span>>(input).expect("multispace0 cannot fail");
+        input = rest;
+        if input.fragment().is_empty() {
+            break;
+        }
+        match parse_toplevel(input) {
+            Ok((rest, element)) => {
+                elements.push(element);
+                input = rest;
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                let (pos, message) = e.describe();
+                errors.push((pos, message));
+                input = skip_to_toplevel_keyword(input, pos);
+            }
+            Err(nom::Err::Incomplete(_)) => {
+                errors.push((util::pos(&input), "incomplete input".to_string()));
+                break;
+            }
+        }
+    }
+    (elements, errors)
+}
+
+/// Skip to the start of the first line after `pos` that begins with a toplevel keyword
+fn skip_to_toplevel_keyword(input: Span<'_>, pos: Pos) -> Span<'_> {
+    use nom::Slice;
+    let fragment = *input.fragment();
+    let mut line = input.location_line();
+    let mut offset = 0;
+    while let Some(newline) = fragment[offset..].find('\n') {
+        offset += newline + 1;
+        line += 1;
+        let rest = &fragment[offset..];
+        let starts_keyword = TOPLEVEL_KEYWORDS.iter().any(|keyword| {
+            rest.strip_prefix(keyword)
+                .is_some_and(|after| !after.starts_with(is_identifier))
+        });
+        if line > pos.line && starts_keyword {
+            return input.slice(offset..);
+        }
+    }
+    input.slice(fragment.len()..)
+}
+
+#[test]
+fn test_error_recovery() {
+    let source = "enum { A, B }\n\
+                  function Foo {\n    loadiu8 300\n}\n\
+                  // comment\n\
+                  states bar {\n    garbage\n}\n\
+                  function Baz {\n    stop\n}\n";
+    let (elements, errors) = parse_toplevel_elements(Span::new_extra(source, 0));
+    assert_eq!(elements.len(), 2);
+    let lines: Vec<_> = errors.iter().map(|(pos, _)| pos.line).collect();
+    assert_eq!(lines, [3, 7]);
+}
+
+#[test]
+fn test_spawn_block() {
+    let source = "spawn dog {\n    health 1, 2, 3\n    pain never // no pain states\n    directional 134, stand\n    undirectional 138, path, hard\n}";
+    let (rest, element) = parse_spawn_block(Span::new_extra(source, 0)).unwrap();
+    assert!(rest.is_empty());
+    let ToplevelElement::SpawnBlock(spawn_block) = element else {
+        panic!("not a spawn block: {element:?}");
+    };
+    let skills: Vec<_> = spawn_block
+        .infos
+        .iter()
+        .map(|(_, info)| info.skill)
+        .collect();
+    assert_eq!(skills, [0, 0, 0, 0, 2]);
+    assert!(matches!(
+        spawn_block.properties[..],
+        [
+            (_, EnemyProperty::Health([1, 2, 3])),
+            (_, EnemyProperty::Pain(PainBehavior::Never))
+        ]
+    ));
+}
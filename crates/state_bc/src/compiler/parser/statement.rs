@@ -0,0 +1,271 @@
+//! Structured statements and expressions in function blocks. They can be mixed freely with the
+//! assembly directives and are lowered to the same bytecode (see `compiler::lower`).
+
+use super::{
+    block_end, identifier, parse_function_element,
+    util::{is_identifier, token_pos, ws, MyError, Res, Span},
+};
+use crate::compiler::ast::{BinaryOp, Callee, Expr, FunctionBlockElement, Pos, UnaryOp};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, one_of, satisfy},
+    combinator::{cut, map, not, opt, recognize, value},
+    multi::{many0, many1, separated_list0},
+    sequence::{delimited, pair, preceded, separated_pair, terminated},
+};
+
+/// `kw` not followed by further identifier characters, so `iffy` is not the keyword `if`
+fn keyword<'a>(kw: &'static str) -> impl FnMut(Span<'a>) -> Res<'a, Span<'a>> {
+    ws(terminated(tag(kw), not(satisfy(is_identifier))))
+}
+
+fn block(input: Span<'_>) -> Res<'_, Vec<(Pos, FunctionBlockElement)>> {
+    preceded(
+        ws(char('{')),
+        cut(terminated(many0(parse_function_element), block_end)),
+    )(input)
+}
+
+fn parse_if(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+    let (input, _) = keyword("if")(input)?;
+    let (input, cond) = cut(expr)(input)?;
+    let (input, then_branch) = cut(block)(input)?;
+    let else_if = map(pair(token_pos, parse_if), |(pos, element)| {
+        vec![(pos, element)]
+    });
+    let (input, else_branch) = opt(preceded(keyword("else"), cut(alt((else_if, block)))))(input)?;
+    Ok((
+        input,
+        FunctionBlockElement::If {
+            cond,
+            then_branch,
+            else_branch: else_branch.unwrap_or_default(),
+        },
+    ))
+}
+
+fn parse_goto(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+    let (input, _) = keyword("goto")(input)?;
+    cut(alt((
+        // a plain identifier is a state label, anything else computes the state pointer
+        map(terminated(ws(identifier), not(one_of("(."))), |label| {
+            FunctionBlockElement::Goto { label }
+        }),
+        map(expr, FunctionBlockElement::GotoExpr),
+    )))(input)
+}
+
+pub fn parse_statement(input: Span<'_>) -> Res<'_, FunctionBlockElement> {
+    alt((
+        parse_if,
+        parse_goto,
+        value(FunctionBlockElement::Return, keyword("return")),
+        map(
+            preceded(keyword("wait"), cut(expr)),
+            FunctionBlockElement::Wait,
+        ),
+        map(call, FunctionBlockElement::Call),
+    ))(input)
+}
+
+fn args(input: Span<'_>) -> Res<'_, Vec<Expr>> {
+    delimited(
+        ws(char('(')),
+        cut(separated_list0(ws(char(',')), expr)),
+        cut(ws(char(')'))),
+    )(input)
+}
+
+/// `trap(name, args..)`, `trap(host.name, args..)`, `host.name(args..)` or `name(args..)`
+fn call(input: Span<'_>) -> Res<'_, Expr> {
+    let (input, pos) = token_pos(input)?;
+    let trap_name = alt((
+        map(
+            separated_pair(identifier, char('.'), identifier),
+            |(host, name)| (Some(host), name),
+        ),
+        map(identifier, |name| (None, name)),
+    ));
+    let trap = preceded(
+        pair(keyword("trap"), ws(char('('))),
+        cut(terminated(
+            pair(ws(trap_name), many0(preceded(ws(char(',')), expr))),
+            ws(char(')')),
+        )),
+    );
+    let (input, (callee, args)) = alt((
+        map(trap, |((host, name), args)| {
+            (Callee::Trap { host, name }, args)
+        }),
+        map(
+            pair(separated_pair(identifier, char('.'), identifier), args),
+            |((host, name), args)| {
+                (
+                    Callee::Trap {
+                        host: Some(host),
+                        name,
+                    },
+                    args,
+                )
+            },
+        ),
+        map(pair(identifier, args), |(name, args)| {
+            (Callee::Name(name), args)
+        }),
+    ))(input)?;
+    Ok((input, Expr::Call { callee, args, pos }))
+}
+
+fn integer(input: Span<'_>) -> Res<'_, Expr> {
+    let (rest, digits) =
+        recognize(many1(terminated(one_of("0123456789"), many0(char('_')))))(input)?;
+    let (rest, is_u8) = opt(tag("u8"))(rest)?;
+    let v: i64 = digits.replace('_', "").parse().unwrap_or(i64::MAX);
+    let expr = if is_u8.is_some() {
+        u8::try_from(v).map(Expr::U8).ok()
+    } else {
+        i32::try_from(v).map(Expr::Int).ok()
+    };
+    match expr {
+        Some(expr) => Ok((rest, expr)),
+        None => Err(nom::Err::Failure(MyError::Custom(
+            digits,
+            format!("integer out of range {digits}"),
+        ))),
+    }
+}
+
+fn name(input: Span<'_>) -> Res<'_, Expr> {
+    let (input, pos) = token_pos(input)?;
+    let (input, name) = identifier(input)?;
+    Ok((input, Expr::Name { name, pos }))
+}
+
+fn primary(input: Span<'_>) -> Res<'_, Expr> {
+    ws(alt((
+        delimited(char('('), cut(expr), cut(ws(char(')')))),
+        integer,
+        call,
+        name,
+    )))(input)
+}
+
+fn unary(input: Span<'_>) -> Res<'_, Expr> {
+    let op = ws(alt((
+        value(UnaryOp::Not, char('!')),
+        value(UnaryOp::Neg, char('-')),
+    )));
+    alt((
+        map(pair(op, cut(unary)), |(op, e)| Expr::Unary(op, Box::new(e))),
+        primary,
+    ))(input)
+}
+
+/// Left associative chain of `operand (op operand)*`
+fn binary_chain<'a>(
+    input: Span<'a>,
+    operand: fn(Span<'a>) -> Res<'a, Expr>,
+    mut op: impl FnMut(Span<'a>) -> Res<'a, BinaryOp>,
+) -> Res<'a, Expr> {
+    let (mut input, mut lhs) = operand(input)?;
+    while let Ok((rest, op)) = op(input) {
+        let (rest, rhs) = cut(operand)(rest)?;
+        lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        input = rest;
+    }
+    Ok((input, lhs))
+}
+
+fn product(input: Span<'_>) -> Res<'_, Expr> {
+    binary_chain(
+        input,
+        unary,
+        ws(alt((
+            value(BinaryOp::Mul, char('*')),
+            // not the start of a line comment
+            value(BinaryOp::Div, terminated(char('/'), not(char('/')))),
+        ))),
+    )
+}
+
+fn sum(input: Span<'_>) -> Res<'_, Expr> {
+    binary_chain(
+        input,
+        product,
+        ws(alt((
+            value(BinaryOp::Add, char('+')),
+            value(BinaryOp::Sub, char('-')),
+        ))),
+    )
+}
+
+/// Comparisons don't chain: `a < b < c` is a syntax error
+fn comparison(input: Span<'_>) -> Res<'_, Expr> {
+    let (input, lhs) = sum(input)?;
+    let op = ws(alt((
+        value(BinaryOp::Le, tag("<=")),
+        value(BinaryOp::Ge, tag(">=")),
+        value(BinaryOp::Eq, tag("==")),
+        value(BinaryOp::Ne, tag("!=")),
+        value(BinaryOp::Lt, tag("<")),
+        value(BinaryOp::Gt, tag(">")),
+    )));
+    let (input, rhs) = opt(pair(op, cut(sum)))(input)?;
+    Ok(match rhs {
+        Some((op, rhs)) => (input, Expr::Binary(op, Box::new(lhs), Box::new(rhs))),
+        None => (input, lhs),
+    })
+}
+
+fn conjunction(input: Span<'_>) -> Res<'_, Expr> {
+    binary_chain(input, comparison, value(BinaryOp::And, ws(tag("&&"))))
+}
+
+pub fn expr(input: Span<'_>) -> Res<'_, Expr> {
+    binary_chain(input, conjunction, value(BinaryOp::Or, ws(tag("||"))))
+}
+
+#[test]
+fn test_parse_statement() {
+    let source = "if !fire() && ammo >= 2 * 3 { goto attack } else if x { return } else { weapon.play_sound(SND_A) }";
+    let (rest, statement) = parse_statement(Span::new_extra(source, 0)).unwrap();
+    assert!(rest.is_empty());
+    let FunctionBlockElement::If {
+        cond,
+        then_branch,
+        else_branch,
+    } = statement
+    else {
+        panic!("not an if: {statement:?}");
+    };
+    let Expr::Binary(BinaryOp::And, lhs, rhs) = cond else {
+        panic!("unexpected condition {cond:?}");
+    };
+    assert!(matches!(*lhs, Expr::Unary(UnaryOp::Not, _)));
+    assert!(
+        matches!(*rhs, Expr::Binary(BinaryOp::Ge, _, ref product) if matches!(**product, Expr::Binary(BinaryOp::Mul, _, _)))
+    );
+    assert!(
+        matches!(&then_branch[..], [(_, FunctionBlockElement::Goto { label })] if label == "attack")
+    );
+    let [(_, FunctionBlockElement::If { else_branch, .. })] = &else_branch[..] else {
+        panic!("expected else if: {else_branch:?}");
+    };
+    assert!(matches!(
+        &else_branch[..],
+        [(_, FunctionBlockElement::Call(Expr::Call { callee: Callee::Trap { host: Some(host), .. }, args, .. }))]
+            if host == "weapon" && args.len() == 1
+    ));
+}
+
+#[test]
+fn test_parse_wait() {
+    let (rest, statement) = parse_statement(Span::new_extra("wait delay() * 2", 0)).unwrap();
+    assert!(rest.is_empty());
+    assert!(matches!(
+        statement,
+        FunctionBlockElement::Wait(Expr::Binary(BinaryOp::Mul, _, _))
+    ));
+    assert!(parse_statement(Span::new_extra("wait }", 0)).is_err());
+}
@@ -6,7 +6,10 @@ use nom::{
 };
 use nom_locate::LocatedSpan;
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use crate::compiler::ast::Pos;
+
+/// Input of the parsers. `extra` is the index of the source file, see `diagnostic::Reporter`.
+pub type Span<'a> = LocatedSpan<&'a str, usize>;
 pub type Res<'a, Output> = IResult<Span<'a>, Output, MyError<Span<'a>>>;
 
 // WIP: error handling inspired by https://www.reddit.com/r/rust/comments/mtmufz/comment/gv18j0a/?utm_source=share&utm_medium=web2x&context=3
@@ -15,7 +18,39 @@ pub type Res<'a, Output> = IResult<Span<'a>, Output, MyError<Span<'a>>>;
 #[derive(Debug)]
 pub enum MyError<I> {
     Nom(nom::error::Error<I>),
-    Custom(String),
+    /// error with a message, located at the given input
+    Custom(I, String),
+}
+
+impl<'a> MyError<Span<'a>> {
+    /// Position and human readable message
+    pub fn describe(&self) -> (Pos, String) {
+        match self {
+            MyError::Nom(e) => {
+                let token = e.input.fragment().split_whitespace().next();
+                let message = match token {
+                    Some(token) => format!("syntax error at `{token}`"),
+                    None => "unexpected end of input".to_string(),
+                };
+                (pos(&e.input), message)
+            }
+            MyError::Custom(input, message) => (pos(input), message.clone()),
+        }
+    }
+}
+
+/// Skip whitespace and return the position of the following token
+pub fn token_pos(input: Span<'_>) -> Res<'_, Pos> {
+    let (input, _) = multispace0(input)?;
+    Ok((input, pos(&input)))
+}
+
+pub fn pos(span: &Span) -> Pos {
+    Pos {
+        file: span.extra,
+        line: span.location_line(),
+        column: span.get_utf8_column(),
+    }
 }
 
 impl<I> ParseError<I> for MyError<I> {
@@ -0,0 +1,223 @@
+//! Expansion of states templates into plain states blocks
+
+use super::{
+    ast::{StatesBlock, StatesBlockElement, StatesInstance, TemplateDecl, ToplevelElement},
+    diagnostic::Reporter,
+};
+use std::collections::BTreeMap;
+
+/// Split the template declarations off the other toplevel elements. Templates can be used before
+/// they are declared.
+pub fn take_templates(
+    toplevel_elements: Vec<ToplevelElement>,
+    reporter: &mut Reporter,
+) -> (BTreeMap<String, TemplateDecl>, Vec<ToplevelElement>) {
+    let mut templates = BTreeMap::new();
+    let mut rest = Vec::new();
+    for tle in toplevel_elements {
+        match tle {
+            ToplevelElement::TemplateDecl(template) => {
+                check_placeholders(&template, reporter);
+                let pos = template.pos;
+                let name = template.name.clone();
+                if templates.insert(name.clone(), template).is_some() {
+                    reporter.error(pos, format!("duplicate template {name}"));
+                }
+            }
+            tle => rest.push(tle),
+        }
+    }
+    (templates, rest)
+}
+
+fn check_placeholders(template: &TemplateDecl, reporter: &mut Reporter) {
+    let params: BTreeMap<&str, &str> = template
+        .params
+        .iter()
+        .map(|param| (param.as_str(), param.as_str()))
+        .collect();
+    for element in &template.elements {
+        if let StatesBlockElement::State {
+            id,
+            think,
+            action,
+            pos,
+            ..
+        } = element
+        {
+            for text in [id, think, action] {
+                if let Err(name) = substitute(text, &params) {
+                    reporter.error(
+                        *pos,
+                        format!("unknown parameter {{{name}}} in template {}", template.name),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Replace the `{param}` placeholders in `text`. Err with the name of an unknown placeholder.
+fn substitute(text: &str, params: &BTreeMap<&str, &str>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        // the parser only accepts complete placeholders
+        let end = start + rest[start..].find('}').expect("unterminated placeholder");
+        let name = &rest[start + 1..end];
+        out.push_str(params.get(name).ok_or_else(|| name.to_string())?);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// States block for `instance`: the template states with the arguments substituted, followed by
+/// the states of the instance itself. Expanded template states are located at the instance, so
+/// errors caused by a bad argument point there.
+pub fn expand(
+    templates: &BTreeMap<String, TemplateDecl>,
+    instance: StatesInstance,
+    reporter: &mut Reporter,
+) -> Option<StatesBlock> {
+    let Some(template) = templates.get(&instance.template) else {
+        reporter.error(
+            instance.pos,
+            format!("unknown template {}", instance.template),
+        );
+        return None;
+    };
+    if template.params.len() != instance.args.len() {
+        reporter.error(
+            instance.pos,
+            format!(
+                "template {} takes {} argument(s), {} given",
+                template.name,
+                template.params.len(),
+                instance.args.len()
+            ),
+        );
+        return None;
+    }
+    let params: BTreeMap<&str, &str> = template
+        .params
+        .iter()
+        .map(String::as_str)
+        .zip(instance.args.iter().map(String::as_str))
+        .collect();
+
+    let mut elements = Vec::new();
+    let mut ok = true;
+    for element in &template.elements {
+        let StatesBlockElement::State {
+            id,
+            directional,
+            ticks,
+            think,
+            action,
+            next,
+            pos: _,
+        } = element
+        else {
+            elements.push(element.clone());
+            continue;
+        };
+        // unknown placeholders were already reported with the template
+        let [Ok(id), Ok(think), Ok(action)] =
+            [id, think, action].map(|text| substitute(text, &params))
+        else {
+            ok = false;
+            continue;
+        };
+        elements.push(StatesBlockElement::State {
+            id,
+            directional: *directional,
+            ticks: *ticks,
+            think,
+            action,
+            next: next.clone(),
+            pos: instance.pos,
+        });
+    }
+    elements.extend(instance.elements);
+    ok.then(|| StatesBlock {
+        name: instance.name,
+        host: template.host.clone(),
+        pos: instance.pos,
+        elements,
+    })
+}
+
+#[test]
+fn test_substitute() {
+    let params = BTreeMap::from([("prefix", "BROWN"), ("sight", "SightBrown")]);
+    assert_eq!(
+        substitute("{prefix}_W1_1", &params).as_deref(),
+        Ok("BROWN_W1_1")
+    );
+    assert_eq!(substitute("{sight}", &params).as_deref(), Ok("SightBrown"));
+    assert_eq!(substitute("None", &params).as_deref(), Ok("None"));
+    assert_eq!(substitute("{death}", &params), Err("death".to_string()));
+}
+
+#[cfg(test)]
+const TEMPLATE_SOURCE: &str = "enum { A_1, A_2, B_1 }
+template walker(prefix, think): enemy {
+stand:
+    state {prefix}_1, false, 10, {think}, None, next
+    state {prefix}_2, false, 10, None, None, stand
+}
+function None: enemy {
+    return
+}
+function ThinkA: enemy {
+    return
+}
+";
+
+#[cfg(test)]
+fn template_errors(instance: &str) -> Vec<String> {
+    let source = format!("{TEMPLATE_SOURCE}{instance}");
+    match super::compile_source("inline.st", &source) {
+        Ok(_) => Vec::new(),
+        Err(diagnostics) => diagnostics.0.into_iter().map(|d| d.message).collect(),
+    }
+}
+
+#[test]
+fn test_expand() {
+    // the instance states follow the template states
+    let source = format!(
+        "{TEMPLATE_SOURCE}states a = walker(A, ThinkA) {{\ndead:\n    state B_1, false, 0, None, None, dead\n}}\n"
+    );
+    let output = super::compile_source("inline.st", &source).unwrap();
+    assert_eq!(output.labels["a::stand"], 0);
+    assert_eq!(output.labels["a::dead"], 2 * crate::STATE_BC_SIZE);
+    let image = output.exec_image().unwrap();
+    let ids: Vec<_> = image.read_states().unwrap().iter().map(|s| s.id).collect();
+    assert_eq!(ids, [0, 1, 2]);
+}
+
+#[test]
+fn test_expand_errors() {
+    assert_eq!(
+        template_errors("states a = runner(A, ThinkA) {\n}\n"),
+        ["unknown template runner"]
+    );
+    assert_eq!(
+        template_errors("states a = walker(A) {\n}\n"),
+        ["template walker takes 2 argument(s), 1 given"]
+    );
+    let source = "template broken(prefix): enemy {
+stand:
+    state {prefix}_1, false, 10, {think}, None, stand
+}
+states a = broken(A) {
+}
+";
+    assert_eq!(
+        template_errors(source),
+        ["unknown parameter {think} in template broken"]
+    );
+}
@@ -0,0 +1,161 @@
+use crate::{
+    opcode::{decode_block, Instruction},
+    ExecImage, Function, Result, STATE_BC_SIZE,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Write,
+};
+
+/// Human readable listing of an ExecImage: label table, state table and every bytecode block
+/// referenced by a state (deduplicated blocks are listed once with all their users).
+///
+/// `id_names` maps state ids to the enum names used in the source. Unknown ids are printed as
+/// numbers.
+pub fn disassemble(
+    image: &ExecImage,
+    id_names: &HashMap<i32, String>,
+    w: &mut dyn Write,
+) -> Result<()> {
+    let states = image.read_states()?;
+
+    writeln!(
+        w,
+        "image version {}, enum hash {:016x}, image hash {:016x}",
+        image.header.version, image.header.enum_hash, image.header.image_hash
+    )?;
+    writeln!(w, "labels:")?;
+    let sorted_labels = image
+        .labels
+        .iter()
+        .map(|(name, ptr)| (*ptr, name.as_str()))
+        .collect::<BTreeSet<_>>();
+    for (ptr, name) in &sorted_labels {
+        writeln!(w, "  {ptr:04x} {name}")?;
+    }
+
+    writeln!(w)?;
+    writeln!(w, "states:")?;
+    let mut block_users = BTreeMap::<i32, Vec<String>>::new();
+    for (i, state) in states.iter().enumerate() {
+        let ptr = i as i32 * STATE_BC_SIZE;
+        let name = state_name(image, ptr);
+        let id = match id_names.get(&state.id) {
+            Some(id_name) => id_name.clone(),
+            None => state.id.to_string(),
+        };
+        writeln!(
+            w,
+            "  {ptr:04x} {name:<28} {id:<20} ticks={:<4} {} think=@{:04x} action=@{:04x} next={}",
+            state.ticks,
+            if state.directional { "dir  " } else { "undir" },
+            state.think_offs,
+            state.action_offs,
+            state_name(image, state.next),
+        )?;
+        block_users
+            .entry(state.think_offs)
+            .or_default()
+            .push(format!("think {name}"));
+        block_users
+            .entry(state.action_offs)
+            .or_default()
+            .push(format!("action {name}"));
+    }
+
+    writeln!(w)?;
+    writeln!(w, "code:")?;
+    for (offs, users) in &block_users {
+        writeln!(w, "@{offs:04x}:")?;
+        for user in users {
+            writeln!(w, "  ; {user}")?;
+        }
+        let mut prev = None;
+        for (pos, instruction) in decode_block(&image.code, *offs as usize)? {
+            let operand = match instruction {
+                Instruction::PushU8(v) | Instruction::LoadI32(v) | Instruction::StoreI32(v) => {
+                    v.to_string()
+                }
+                Instruction::LoadiI32(v) => v.to_string(),
+                Instruction::Jrc(_) | Instruction::Jmp(_) | Instruction::Jrnc(_) => {
+                    format!("@{:04x}", instruction.jump_target(pos).unwrap())
+                }
+                _ => String::new(),
+            };
+            let comment = match (prev, instruction) {
+                (Some(Instruction::PushU8(f)), Instruction::Call) => match Function::try_from(f) {
+                    Ok(function) => format!("; {function:?}"),
+                    Err(_) => "; <invalid function>".to_string(),
+                },
+                (Some(Instruction::LoadiI32(ptr)), Instruction::GoState) => {
+                    format!("; -> {}", state_name(image, ptr))
+                }
+                _ => String::new(),
+            };
+            writeln!(
+                w,
+                "  {pos:04x}  {:<9} {operand:<6} {comment}",
+                instruction.mnemonic()
+            )?;
+            prev = Some(instruction);
+        }
+    }
+    Ok(())
+}
+
+/// Name a state pointer by its closest label, e.g. `brown::path+2`
+fn state_name(image: &ExecImage, ptr: i32) -> String {
+    match image.find_label_for_ptr(ptr) {
+        Some((label, 0)) => label.to_string(),
+        Some((label, index)) => format!("{label}+{index}"),
+        None => format!("{ptr:04x}"),
+    }
+}
+
+#[cfg(feature = "compiler")]
+#[test]
+fn test_disassemble() {
+    let source = "enum { SPR_A, SPR_B }
+states door: door {
+closed:
+    state SPR_A, false, 10, Check, None, next
+    state SPR_B, true, 5, None, None, open
+open:
+    state SPR_B, false, 1, None, None, closed
+}
+function Check: door {
+    if 1 < 2 {
+        goto open
+    }
+    return
+}
+function None: door {
+    return
+}
+";
+    let output = crate::compiler::compile_source("inline.st", source).unwrap();
+    let image = output.exec_image().unwrap();
+    let id_names = HashMap::from([(0, "SPR_A".to_string())]);
+    let mut listing = Vec::new();
+    disassemble(&image, &id_names, &mut listing).unwrap();
+    let listing = String::from_utf8(listing).unwrap();
+    let lines: Vec<_> = listing.lines().map(str::trim_end).collect();
+
+    assert!(listing.contains("labels:\n  0000 door::closed\n  002a door::open\n"));
+    // state ids by name where known, next pointers by label
+    let states: Vec<_> = lines
+        .iter()
+        .filter(|line| line.contains(" ticks="))
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .collect();
+    assert_eq!(states.len(), 3);
+    assert_eq!(states[0][..3], ["0000", "door::closed", "SPR_A"]);
+    assert_eq!(states[0].last(), Some(&"next=door::closed+1"));
+    assert_eq!(states[1][..3], ["0015", "door::closed+1", "1"]);
+    assert_eq!(states[1].last(), Some(&"next=door::open"));
+    assert_eq!(states[2].last(), Some(&"next=door::closed"));
+    // mnemonics with jump targets and goto labels resolved
+    assert!(lines.contains(&"  ; think door::closed"));
+    assert!(lines.contains(&"  004a  jrnc      @0055"));
+    assert!(lines.contains(&"  0054  gostate          ; -> door::open"));
+}
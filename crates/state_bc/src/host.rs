@@ -0,0 +1,457 @@
+//! Interface between bytecode and the game objects running it.
+
+use std::io::Cursor;
+
+use anyhow::anyhow;
+
+use crate::{
+    opcode::{self, BudgetExhausted, Env, Event, Value},
+    trace::{self, TraceEvent},
+    ExecCtx, ExecImage, Function, Result, Suspended,
+};
+
+/// Host side of the trap ABI. Each host type (enemy, door, weapon, ...) has its own set of trap
+/// numbers, declared in the .st files as `trap <host>.<name> = <number>`. `Trap` is the typed
+/// form of these numbers on the Rust side, the compiler generates it as `<Host>Trap` (see
+/// `compiler::Output::enums_source`).
+pub trait TrapHandler {
+    type Trap: TryFrom<u8> + std::fmt::Debug;
+
+    /// Handle `trap`. The trap number is already popped, any further operands are still on the
+    /// stack and results are pushed onto it.
+    fn trap(&mut self, trap: Self::Trap, env: &mut Env) -> Result<()>;
+}
+
+/// Pop the trap number from the stack, map it to the handler's trap type and dispatch it.
+pub fn handle_trap<H: TrapHandler>(handler: &mut H, env: &mut Env) -> Result<()> {
+    let trap = match env.stack.pop() {
+        Some(Value::U8(num)) => H::Trap::try_from(num)
+            .map_err(|_| anyhow!("trap number {num} not handled by this host"))?,
+        Some(x) => return Err(anyhow!("expected trap number, got {x:?}")),
+        None => return Err(anyhow!("stack underflow")),
+    };
+    handler.trap(trap, env)
+}
+
+/// A game object running bytecode. Implementations usually borrow the object together with
+/// whatever per-update context its functions and traps need.
+pub trait Host: TrapHandler {
+    fn exec_ctx(&mut self) -> &mut ExecCtx;
+
+    /// Handle CALL
+    fn dispatch_call(&mut self, function: Function) -> Result<()>;
+
+    /// Handle GOSTATE. The default switches the ExecCtx to the state at `ptr`.
+    fn go_state(&mut self, ptr: i32) -> Result<()> {
+        self.exec_ctx().jump(ptr)
+    }
+
+    /// Number of opcodes a single `run_block` may execute
+    fn budget(&self) -> u32 {
+        opcode::DEFAULT_BUDGET
+    }
+
+    /// Label of the state to continue in when a block runs out of budget. Hosts pick it by the
+    /// actor's current state, e.g. a dying actor should end up dead rather than standing, and
+    /// may bring their own state in line as the jump follows right away. With None the
+    /// `BudgetExhausted` error is returned to the caller.
+    fn safe_label(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Run the bytecode block at `offs` on `host` until STOP, GOSTATE or WAIT. Registers are loaded
+/// from and stored back to the host's ExecCtx, also if execution fails. A block that exceeds the
+/// host's budget is aborted and the ExecCtx moved to the host's safe state, if it has one.
+///
+/// WAIT stores the stack and the position after it in `ExecCtx::suspended`. Each further run of
+/// the same block counts down one tick, the last one continues after the WAIT. WAIT fails for
+/// less than one tick.
+pub fn run_block<H: Host>(image: &ExecImage, offs: i32, host: &mut H) -> Result<()> {
+    let mut env = Env::with_regs(host.exec_ctx().regs);
+    env.budget = host.budget();
+    let exec_ctx = host.exec_ctx();
+    let mut start = offs;
+    match exec_ctx.suspended.take() {
+        Some(mut suspended) if suspended.block == offs => {
+            suspended.ticks -= 1;
+            if suspended.ticks > 0 {
+                exec_ctx.suspended = Some(suspended);
+                return Ok(());
+            }
+            start = suspended.resume;
+            env.stack = suspended.stack;
+        }
+        other => exec_ctx.suspended = other,
+    }
+    // the state running the block, GOSTATE may leave it
+    let label = trace::is_enabled()
+        .then(|| image.find_label_for_ptr(host.exec_ctx().ptr))
+        .flatten()
+        .map(|(label, _)| label);
+    let res = exec_block(image, offs, start, host, &mut env);
+    host.exec_ctx().regs = env.regs;
+    if let Some(label) = label {
+        let ops = env.executed;
+        trace::count_ops(label, ops);
+        trace::record(host.exec_ctx().actor, TraceEvent::Block { offs, ops });
+    }
+    match res {
+        Err(err) if err.is::<BudgetExhausted>() => recover(host, err),
+        res => res,
+    }
+}
+
+/// Log the actor and state that ran out of budget and jump to the host's safe state
+fn recover<H: Host>(host: &mut H, err: anyhow::Error) -> Result<()> {
+    let Some(safe_label) = host.safe_label() else {
+        return Err(err);
+    };
+    let exec_ctx = host.exec_ctx();
+    let state = match exec_ctx.image.find_label_for_ptr(exec_ctx.ptr) {
+        Some((label, index)) => format!("{label}+{index}"),
+        None => format!("0x{:x}", exec_ctx.ptr),
+    };
+    let actor = match exec_ctx.actor {
+        Some(actor) => format!("actor {actor}"),
+        None => "actor".to_string(),
+    };
+    eprintln!("{actor} in state {state}: {err}, moving to {safe_label}");
+    exec_ctx
+        .jump_label(&safe_label)
+        .map_err(|jump_err| err.context(format!("failed to recover: {jump_err}")))
+}
+
+/// Name of the trap on top of the stack, for traces
+fn trap_name<H: TrapHandler>(env: &Env) -> String {
+    match env.stack.last() {
+        Some(Value::U8(num)) => H::Trap::try_from(*num)
+            .map(|trap| format!("{trap:?}"))
+            .unwrap_or_else(|_| num.to_string()),
+        x => format!("{x:?}"),
+    }
+}
+
+/// Run the block at `offs`, starting at `start` (`offs` unless resumed after WAIT)
+fn exec_block<H: Host>(
+    image: &ExecImage,
+    offs: i32,
+    start: i32,
+    host: &mut H,
+    env: &mut Env,
+) -> Result<()> {
+    let code = usize::try_from(start)
+        .ok()
+        .and_then(|start| image.code.get(start..))
+        .ok_or_else(|| anyhow!("block offset {start} out of range"))?;
+    let mut cursor = Cursor::new(code);
+    loop {
+        match opcode::exec(&mut cursor, env)? {
+            Event::Stop => return Ok(()),
+            Event::Call(function) => {
+                if trace::is_enabled() {
+                    trace::record(host.exec_ctx().actor, TraceEvent::Call(function));
+                }
+                host.dispatch_call(function)?
+            }
+            Event::Trap => {
+                if trace::is_enabled() {
+                    trace::record(host.exec_ctx().actor, TraceEvent::Trap(trap_name::<H>(env)));
+                }
+                handle_trap(host, env)?
+            }
+            Event::GoState => {
+                return match env.stack.pop() {
+                    Some(Value::I32(ptr)) => host.go_state(ptr),
+                    Some(x) => Err(anyhow!("unhandled GOSTATE operand {x:?}")),
+                    None => Err(anyhow!("stack underflow")),
+                };
+            }
+            Event::Wait => {
+                let ticks = match env.stack.pop() {
+                    Some(Value::I32(ticks)) if ticks > 0 => ticks,
+                    Some(Value::I32(ticks)) => {
+                        return Err(anyhow!(
+                            "WAIT needs a positive number of ticks, found {ticks}"
+                        ))
+                    }
+                    Some(x) => return Err(anyhow!("unhandled WAIT operand {x:?}")),
+                    None => return Err(anyhow!("stack underflow")),
+                };
+                if trace::is_enabled() {
+                    trace::record(host.exec_ctx().actor, TraceEvent::Wait(ticks));
+                }
+                host.exec_ctx().suspended = Some(Suspended {
+                    block: offs,
+                    resume: start + cursor.position() as i32,
+                    ticks,
+                    stack: std::mem::take(&mut env.stack),
+                });
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+enum TestTrap {
+    Push,
+}
+
+#[cfg(test)]
+impl TryFrom<u8> for TestTrap {
+    type Error = ();
+
+    fn try_from(value: u8) -> std::result::Result<Self, ()> {
+        match value {
+            7 => Ok(TestTrap::Push),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+struct TestHost(i32);
+
+#[cfg(test)]
+impl TrapHandler for TestHost {
+    type Trap = TestTrap;
+
+    fn trap(&mut self, trap: TestTrap, env: &mut Env) -> Result<()> {
+        match trap {
+            TestTrap::Push => env.stack.push(Value::I32(self.0)),
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_handle_trap() {
+    let mut env = Env::default();
+    env.stack.push(Value::U8(7));
+    handle_trap(&mut TestHost(4711), &mut env).unwrap();
+    assert_eq!(env.stack, [Value::I32(4711)]);
+
+    env.stack.push(Value::U8(0));
+    assert!(handle_trap(&mut TestHost(4711), &mut env).is_err());
+}
+
+#[cfg(test)]
+struct TestStateHost {
+    exec_ctx: ExecCtx,
+    calls: Vec<Function>,
+    /// picks the safe label by the current state
+    safe_label: Option<fn(&ExecCtx) -> &'static str>,
+}
+
+#[cfg(test)]
+impl TrapHandler for TestStateHost {
+    type Trap = TestTrap;
+
+    fn trap(&mut self, trap: TestTrap, env: &mut Env) -> Result<()> {
+        match trap {
+            TestTrap::Push => env.stack.push(Value::I32(self.calls.len() as i32)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Host for TestStateHost {
+    fn exec_ctx(&mut self) -> &mut ExecCtx {
+        &mut self.exec_ctx
+    }
+
+    fn dispatch_call(&mut self, function: Function) -> Result<()> {
+        self.calls.push(function);
+        Ok(())
+    }
+
+    fn budget(&self) -> u32 {
+        100
+    }
+
+    fn safe_label(&mut self) -> Option<String> {
+        self.safe_label
+            .map(|safe_label| safe_label(&self.exec_ctx).to_string())
+    }
+}
+
+#[test]
+fn test_run_block() {
+    use crate::{ms::Writable, StateBc, STATE_BC_SIZE};
+
+    // two states, the block of the first one calls a function, stores the trap result in r0
+    // and switches to the second state
+    let block = opcode::Codegen::default()
+        .function_call(Function::ActionDie)
+        .loadi_u8(7)
+        .trap()
+        .store_i32(0)
+        .loadi_i32(STATE_BC_SIZE)
+        .gostate()
+        .finalize();
+    let mut code = Vec::new();
+    for (id, next) in [(1, STATE_BC_SIZE), (2, 0)] {
+        StateBc {
+            id,
+            ticks: 1,
+            directional: false,
+            think_offs: 2 * STATE_BC_SIZE,
+            action_offs: 2 * STATE_BC_SIZE,
+            next,
+        }
+        .write(&mut code)
+        .unwrap();
+    }
+    code.extend(block);
+    let image = std::rc::Rc::new(ExecImage {
+        header: crate::test_header(),
+        code,
+        num_states: 2,
+        labels: [("test::start".to_string(), 0)].into(),
+        spawn_infos: Default::default(),
+    });
+
+    let mut host = TestStateHost {
+        exec_ctx: ExecCtx::new("test::start", image.clone()).unwrap(),
+        calls: Vec::new(),
+        safe_label: None,
+    };
+    run_block(&image, 2 * STATE_BC_SIZE, &mut host).unwrap();
+    assert_eq!(host.calls, [Function::ActionDie]);
+    assert_eq!(host.exec_ctx.regs[0], 1);
+    assert_eq!(host.exec_ctx.state.id, 2);
+
+    assert!(run_block(&image, 4711, &mut host).is_err());
+}
+
+#[test]
+fn test_budget() {
+    use crate::{ms::Writable, StateBc, STATE_BC_SIZE};
+
+    // every state runs a block that loops forever
+    let block = opcode::Codegen::default()
+        .label("loop")
+        .jmp_label("loop")
+        .finalize();
+    let mut code = Vec::new();
+    for id in [1, 2, 3, 4] {
+        StateBc {
+            id,
+            ticks: 1,
+            directional: false,
+            think_offs: 4 * STATE_BC_SIZE,
+            action_offs: 4 * STATE_BC_SIZE,
+            next: 0,
+        }
+        .write(&mut code)
+        .unwrap();
+    }
+    code.extend(block);
+    let image = std::rc::Rc::new(ExecImage {
+        header: crate::test_header(),
+        code,
+        num_states: 4,
+        labels: [
+            ("test::safe".to_string(), 0),
+            ("test::loop".to_string(), STATE_BC_SIZE),
+            ("test::die".to_string(), 2 * STATE_BC_SIZE),
+            ("test::dead".to_string(), 3 * STATE_BC_SIZE),
+        ]
+        .into(),
+        spawn_infos: Default::default(),
+    });
+
+    let mut host = TestStateHost {
+        exec_ctx: ExecCtx::new("test::loop", image.clone()).unwrap(),
+        calls: Vec::new(),
+        safe_label: None,
+    };
+    let err = run_block(&image, 4 * STATE_BC_SIZE, &mut host).unwrap_err();
+    assert_eq!(err.downcast_ref::<BudgetExhausted>().unwrap().budget, 100);
+    assert_eq!(host.exec_ctx.state.id, 2);
+
+    host.safe_label = Some(|_| "test::safe");
+    run_block(&image, 4 * STATE_BC_SIZE, &mut host).unwrap();
+    assert_eq!(host.exec_ctx.state.id, 1);
+
+    // a dying actor is moved on to its end state instead of back to the idle one
+    host.safe_label = Some(
+        |exec_ctx| match exec_ctx.image.find_label_for_ptr(exec_ctx.ptr) {
+            Some(("test::die", _)) => "test::dead",
+            _ => "test::safe",
+        },
+    );
+    host.exec_ctx.jump_label("test::die").unwrap();
+    run_block(&image, 4 * STATE_BC_SIZE, &mut host).unwrap();
+    assert_eq!(host.exec_ctx.state.id, 4);
+}
+
+#[test]
+fn test_wait() {
+    use crate::{ms::Writable, StateBc, STATE_BC_SIZE};
+
+    // keeps a value on the stack across the wait and stores it afterwards
+    let block = opcode::Codegen::default()
+        .loadi_i32(4711)
+        .loadi_i32(2)
+        .wait()
+        .store_i32(0)
+        .finalize();
+    let other_block = 2 * STATE_BC_SIZE + block.len() as i32;
+    let mut code = Vec::new();
+    for id in [1, 2] {
+        StateBc {
+            id,
+            ticks: 1,
+            directional: false,
+            think_offs: 2 * STATE_BC_SIZE,
+            action_offs: 2 * STATE_BC_SIZE,
+            next: 0,
+        }
+        .write(&mut code)
+        .unwrap();
+    }
+    code.extend(block);
+    code.extend(opcode::Codegen::default().stop().finalize());
+    let image = std::rc::Rc::new(ExecImage {
+        header: crate::test_header(),
+        code,
+        num_states: 2,
+        labels: [
+            ("test::a".to_string(), 0),
+            ("test::b".to_string(), STATE_BC_SIZE),
+        ]
+        .into(),
+        spawn_infos: Default::default(),
+    });
+
+    let mut host = TestStateHost {
+        exec_ctx: ExecCtx::new("test::a", image.clone()).unwrap(),
+        calls: Vec::new(),
+        safe_label: None,
+    };
+    run_block(&image, 2 * STATE_BC_SIZE, &mut host).unwrap();
+    let suspended = host.exec_ctx.suspended.as_ref().unwrap();
+    assert_eq!(suspended.block, 2 * STATE_BC_SIZE);
+    assert_eq!(suspended.stack, [Value::I32(4711)]);
+    assert_eq!(host.exec_ctx.regs[0], 0);
+
+    // another block leaves the suspension alone
+    run_block(&image, other_block, &mut host).unwrap();
+    assert_eq!(host.exec_ctx.suspended_block(), Some(2 * STATE_BC_SIZE));
+
+    run_block(&image, 2 * STATE_BC_SIZE, &mut host).unwrap();
+    assert_eq!(host.exec_ctx.suspended.as_ref().unwrap().ticks, 1);
+    assert_eq!(host.exec_ctx.regs[0], 0);
+    run_block(&image, 2 * STATE_BC_SIZE, &mut host).unwrap();
+    assert!(host.exec_ctx.suspended.is_none());
+    assert_eq!(host.exec_ctx.regs[0], 4711);
+
+    // a state change abandons the suspended block
+    run_block(&image, 2 * STATE_BC_SIZE, &mut host).unwrap();
+    host.exec_ctx.jump_label("test::b").unwrap();
+    assert!(host.exec_ctx.suspended.is_none());
+}
@@ -4,6 +4,8 @@ use prelude::ms::Loadable;
 use std::{
     collections::HashMap,
     io::{Cursor, Read, Write},
+    path::Path,
+    rc::Rc,
 };
 pub mod ms;
 pub use anyhow::Result;
@@ -16,69 +18,71 @@ pub mod prelude {
 #[cfg(feature = "compiler")]
 pub mod compiler;
 
+pub mod disasm;
+pub mod host;
 pub mod opcode;
+pub mod trace;
+pub mod verify;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub enum Function {
-    #[default]
-    None,
-    ThinkStand,
-    ThinkPath,
-    ThinkChase,
-    ThinkDogChase,
-    ActionDie,
-    ActionShoot,
-    ActionBite,
-}
-impl Function {
-    pub fn try_from_identifier(name: &str) -> Option<Self> {
-        Some(match name {
-            "None" => Self::None,
-            "ThinkStand" => Self::ThinkStand,
-            "ThinkPath" => Self::ThinkPath,
-            "ThinkChase" => Self::ThinkChase,
-            "ThinkDogChase" => Self::ThinkDogChase,
-            "ActionDie" => Self::ActionDie,
-            "ActionShoot" => Self::ActionShoot,
-            "ActionBite" => Self::ActionBite,
-            _ => return None,
-        })
-    }
-}
+/// Generates the `Function` enum from the list of native functions below. This list is the only
+/// place where the numbering is defined: .st files refer to functions by name (`call ThinkStand`)
+/// and the compiler resolves them against it.
+macro_rules! native_functions {
+    ($($name:ident = $id:literal,)*) => {
+        /// Native functions callable from bytecode (CALL)
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+        #[repr(u8)]
+        pub enum Function {
+            #[default]
+            $($name = $id,)*
+        }
 
-impl TryFrom<u8> for Function {
-    type Error = anyhow::Error;
+        impl Function {
+            pub const ALL: &'static [Function] = &[$(Function::$name,)*];
 
-    fn try_from(value: u8) -> Result<Self> {
-        Ok(match value {
-            // KEEP IN SYNC WITH .st files!
-            0 => Self::None,
-            1 => Self::ThinkStand,
-            2 => Self::ThinkPath,
-            3 => Self::ThinkChase,
-            4 => Self::ThinkDogChase,
-            5 => Self::ActionDie,
-            6 => Self::ActionShoot,
-            7 => Self::ActionBite,
-            x => return Err(anyhow!("unhandled Think discriminator {x}")),
-        })
-    }
-}
+            pub fn try_from_identifier(name: &str) -> Option<Self> {
+                Some(match name {
+                    $(stringify!($name) => Self::$name,)*
+                    _ => return None,
+                })
+            }
 
-impl From<Function> for u8 {
-    fn from(val: Function) -> Self {
-        match val {
-            // KEEP IN SYNC WITH .st files!
-            Function::None => 0,
-            Function::ThinkStand => 1,
-            Function::ThinkPath => 2,
-            Function::ThinkChase => 3,
-            Function::ThinkDogChase => 4,
-            Function::ActionDie => 5,
-            Function::ActionShoot => 6,
-            Function::ActionBite => 7,
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($name),)*
+                }
+            }
         }
-    }
+
+        impl TryFrom<u8> for Function {
+            type Error = anyhow::Error;
+
+            fn try_from(value: u8) -> Result<Self> {
+                Ok(match value {
+                    $($id => Self::$name,)*
+                    x => return Err(anyhow!("unhandled Function discriminator {x}")),
+                })
+            }
+        }
+
+        impl From<Function> for u8 {
+            fn from(val: Function) -> Self {
+                val as u8
+            }
+        }
+    };
+}
+
+// the first entry is the default
+native_functions! {
+    None = 0,
+    ThinkStand = 1,
+    ThinkPath = 2,
+    ThinkChase = 3,
+    ThinkDogChase = 4,
+    ActionDie = 5,
+    ActionShoot = 6,
+    ActionBite = 7,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -221,7 +225,10 @@ pub struct EnemySpawnInfo {
     pub id: i32,
     pub direction: Direction,
     pub state: String,
-    pub spawn_on_death: Option<i32>,
+    /// name of the spawn block, see `SpawnInfos::find_enemy_info`
+    pub enemy: String,
+    /// lowest skill level the enemy appears on (0: easy, 1: medium, 2: hard)
+    pub skill: u8,
 }
 
 impl ms::Loadable for EnemySpawnInfo {
@@ -229,18 +236,15 @@ impl ms::Loadable for EnemySpawnInfo {
         let id = r.readi32()?;
         let direction = Direction::read_from(r)?;
         let state = String::read_from(r)?;
-
-        let spawn_on_death = match r.readi32()? {
-            x if x >= 0 => Some(x),
-            _ => None,
-        };
+        let enemy = String::read_from(r)?;
+        let skill = r.readu8()?;
 
         Ok(Self {
             id,
             direction,
             state,
-
-            spawn_on_death,
+            enemy,
+            skill,
         })
     }
 }
@@ -250,8 +254,102 @@ impl ms::Writable for EnemySpawnInfo {
         w.writei32(self.id)?;
         self.direction.write(w)?;
         self.state.write(w)?;
+        self.enemy.write(w)?;
+        w.writeu8(self.skill)?;
+        Ok(())
+    }
+}
+
+/// Reaction of an enemy to a hit it survives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PainBehavior {
+    /// jump to `pain1` or `pain2`, depending on the remaining health
+    Alternate,
+    /// keep going
+    Never,
+}
+
+/// Properties of an enemy type, declared in its spawn block
+#[derive(Debug, Clone)]
+pub struct EnemyInfo {
+    pub name: String,
+    /// hit points on easy, medium and hard
+    pub health: [i32; 3],
+    pub can_open_doors: bool,
+    /// map id of the thing spawned on death
+    pub drop: Option<i32>,
+    /// movement per tick while patrolling (16.16 fixed point)
+    pub path_speed: i32,
+    /// movement per tick while chasing (16.16 fixed point)
+    pub chase_speed: i32,
+    pub points: i32,
+    pub pain: PainBehavior,
+}
+
+impl EnemyInfo {
+    /// Defaults for the properties a spawn block doesn't declare
+    pub fn new(name: &str) -> Self {
+        EnemyInfo {
+            name: name.to_string(),
+            health: [25; 3],
+            can_open_doors: true,
+            drop: None,
+            path_speed: 1 << 9,
+            chase_speed: 1 << 10,
+            points: 0,
+            pain: PainBehavior::Alternate,
+        }
+    }
+}
+
+impl ms::Loadable for EnemyInfo {
+    fn read_from(r: &mut dyn Read) -> Result<Self> {
+        let name = String::read_from(r)?;
+        let mut health = [0; 3];
+        for h in &mut health {
+            *h = r.readi32()?;
+        }
+        let can_open_doors = r.readu8()? != 0;
+        let drop = match r.readi32()? {
+            x if x >= 0 => Some(x),
+            _ => None,
+        };
+        let path_speed = r.readi32()?;
+        let chase_speed = r.readi32()?;
+        let points = r.readi32()?;
+        let pain = match r.readu8()? {
+            0 => PainBehavior::Alternate,
+            1 => PainBehavior::Never,
+            x => return Err(anyhow!("unrecognized PainBehavior discriminator {x}")),
+        };
+        Ok(Self {
+            name,
+            health,
+            can_open_doors,
+            drop,
+            path_speed,
+            chase_speed,
+            points,
+            pain,
+        })
+    }
+}
 
-        w.writei32(self.spawn_on_death.unwrap_or(-1))?;
+impl ms::Writable for EnemyInfo {
+    fn write(&self, w: &mut dyn Write) -> Result<()> {
+        self.name.write(w)?;
+        for h in &self.health {
+            w.writei32(*h)?;
+        }
+        w.writeu8(if self.can_open_doors { 1 } else { 0 })?;
+        w.writei32(self.drop.unwrap_or(-1))?;
+        w.writei32(self.path_speed)?;
+        w.writei32(self.chase_speed)?;
+        w.writei32(self.points)?;
+        w.writeu8(match self.pain {
+            PainBehavior::Alternate => 0,
+            PainBehavior::Never => 1,
+        })?;
         Ok(())
     }
 }
@@ -312,56 +410,383 @@ impl StateBc {
     }
 }
 
+/// Magic number at the start of every image
+pub const IMAGE_MAGIC: [u8; 4] = *b"STBC";
+/// Version of the image layout. Bump it on any change to the header, label table, `SpawnInfos`
+/// or bytecode encoding.
+pub const IMAGE_VERSION: u32 = 3;
+
+/// Fixed size header in front of every image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageHeader {
+    pub version: u32,
+    /// `enum_hash` of the enums the image was compiled against
+    pub enum_hash: u64,
+    /// `content_hash` of everything following the header. Savegames record it to detect a
+    /// different image.
+    pub image_hash: u64,
+}
+
+impl ImageHeader {
+    pub const SIZE: usize = 24;
+}
+
+impl ms::Loadable for ImageHeader {
+    fn read_from(r: &mut dyn Read) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != IMAGE_MAGIC {
+            return Err(anyhow!("not a state image (bad magic {magic:02x?})"));
+        }
+        Ok(ImageHeader {
+            version: r.readu32()?,
+            enum_hash: r.readu64()?,
+            image_hash: r.readu64()?,
+        })
+    }
+}
+
+impl ms::Writable for ImageHeader {
+    fn write(&self, w: &mut dyn Write) -> Result<()> {
+        w.write_all(&IMAGE_MAGIC)?;
+        w.writeu32(self.version)?;
+        w.writeu64(self.enum_hash)?;
+        w.writeu64(self.image_hash)?;
+        Ok(())
+    }
+}
+
+/// 64 bit FNV-1a. Unlike `DefaultHasher` it is stable across builds, so it can be stored.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Hash of an enum table (name, value), independent of the order of the entries
+pub fn enum_hash<'a>(enums: impl IntoIterator<Item = (&'a str, i32)>) -> u64 {
+    let mut enums: Vec<_> = enums.into_iter().collect();
+    enums.sort_unstable();
+    let mut bytes = Vec::new();
+    for (name, value) in enums {
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    content_hash(&bytes)
+}
+
 #[derive(Debug)]
 pub struct ExecImage {
-    pub code: &'static [u8],
+    pub header: ImageHeader,
+    pub code: Vec<u8>,
+    /// length of the state table at the start of `code`, see `read_states`
+    pub num_states: i32,
     pub labels: HashMap<String, i32>,
     pub spawn_infos: SpawnInfos,
 }
 
 #[derive(Debug)]
 pub struct ExecCtx {
-    pub image: &'static ExecImage,
+    pub image: Rc<ExecImage>,
+    /// offset of `state` in the image
+    pub ptr: i32,
     pub state: StateBc,
+    /// registers accessed by LOAD_I32 / STORE_I32. They keep their value across ticks and state changes.
+    pub regs: [i32; opcode::NUM_REGS],
+    /// id of the actor running this context in traces (see `trace`). Set by the host, not saved.
+    pub actor: Option<usize>,
+    /// block waiting in a WAIT instruction. While it is set the state holds: hosts only run the
+    /// suspended block (see `host::run_block`) instead of counting down the state's ticks.
+    pub suspended: Option<Suspended>,
 }
 
-#[derive(Debug)]
+/// Execution state of a block suspended by WAIT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suspended {
+    /// offset of the suspended block, running it again counts down the wait
+    pub block: i32,
+    /// offset of the instruction following the WAIT
+    pub resume: i32,
+    /// remaining ticks until the block resumes
+    pub ticks: i32,
+    pub stack: Vec<opcode::Value>,
+}
+
+#[derive(Debug, Default)]
 pub struct SpawnInfos {
     pub spawn_infos: Vec<EnemySpawnInfo>,
+    pub enemy_infos: Vec<EnemyInfo>,
 }
 
 impl ExecCtx {
-    pub fn new(initial_label: &str, image: &'static ExecImage) -> Result<Self> {
-        let state = image.read_state_by_label(initial_label)?;
-        Ok(ExecCtx { image, state })
+    pub fn new(initial_label: &str, image: Rc<ExecImage>) -> Result<Self> {
+        let ptr = image
+            .get_state_offs_by_label(initial_label)
+            .ok_or(anyhow!("unknown label {initial_label}"))?;
+        let state = image.read_state(ptr)?;
+        Ok(ExecCtx {
+            image,
+            ptr,
+            state,
+            regs: Default::default(),
+            actor: None,
+            suspended: None,
+        })
+    }
+    /// Offset of the block suspended by WAIT, if any
+    pub fn suspended_block(&self) -> Option<i32> {
+        self.suspended.as_ref().map(|suspended| suspended.block)
     }
+    /// Switch to the state at `ptr`. A suspended block is abandoned, it belongs to the old state.
     pub fn jump(&mut self, ptr: i32) -> Result<()> {
         self.state = self.image.read_state(ptr)?;
+        self.ptr = ptr;
+        self.suspended = None;
+        if trace::is_enabled() {
+            if let Some((label, index)) = self.image.find_label_for_ptr(ptr) {
+                let label = label.to_string();
+                trace::record(self.actor, trace::TraceEvent::State { label, index });
+            }
+        }
         Ok(())
     }
     pub fn jump_label(&mut self, name: &str) -> Result<()> {
-        self.state = self.image.read_state_by_label(name)?;
-        Ok(())
+        let ptr = self
+            .image
+            .get_state_offs_by_label(name)
+            .ok_or(anyhow!("unknown label {name}"))?;
+        self.jump(ptr)
+    }
+
+    /// Switch to `image`, e.g. after a recompile of the state sources. The current state is
+    /// looked up by its enclosing label and its index relative to that label; the remaining
+    /// ticks and the registers are kept. A suspended block is kept if its code did not change
+    /// (see `SavedSuspension`). Returns false and keeps running on the old image if the state has
+    /// no counterpart in `image`.
+    pub fn reload(&mut self, image: &Rc<ExecImage>) -> bool {
+        let Some((label, index)) = self.image.find_label_for_ptr(self.ptr) else {
+            return false;
+        };
+        let Some(ptr) = image.resolve_label_index(label, index) else {
+            return false;
+        };
+        let Ok(mut state) = image.read_state(ptr) else {
+            return false;
+        };
+        let saved = self.saved_suspension().ok().flatten();
+        state.ticks = self.state.ticks;
+        self.image = image.clone();
+        self.ptr = ptr;
+        self.state = state;
+        self.suspended = saved.and_then(|saved| self.restore_suspension(saved));
+        true
+    }
+
+    fn saved_suspension(&self) -> Result<Option<SavedSuspension>> {
+        let Some(suspended) = &self.suspended else {
+            return Ok(None);
+        };
+        let kind = if suspended.block == self.state.think_offs {
+            BlockKind::Think
+        } else if suspended.block == self.state.action_offs {
+            BlockKind::Action
+        } else {
+            return Err(anyhow!(
+                "suspended block 0x{:x} is not a block of the state at 0x{:x}",
+                suspended.block,
+                self.ptr
+            ));
+        };
+        Ok(Some(SavedSuspension {
+            kind,
+            resume: suspended.resume - suspended.block,
+            ticks: suspended.ticks,
+            stack: suspended.stack.clone(),
+            code_hash: block_hash(&self.image, suspended.block)?,
+        }))
+    }
+
+    /// Resolve `saved` against the current state. Logs and returns None if the block's code
+    /// changed, the stack and resume position would not fit it anymore.
+    fn restore_suspension(&self, saved: SavedSuspension) -> Option<Suspended> {
+        let block = match saved.kind {
+            BlockKind::Think => self.state.think_offs,
+            BlockKind::Action => self.state.action_offs,
+        };
+        if block_hash(&self.image, block).ok() != Some(saved.code_hash) {
+            let (label, index) = self.image.find_label_for_ptr(self.ptr).unwrap_or(("?", 0));
+            eprintln!(
+                "{label}+{index}: dropping the suspended {:?} block, its code changed",
+                saved.kind
+            );
+            return None;
+        }
+        Some(Suspended {
+            block,
+            resume: block + saved.resume,
+            ticks: saved.ticks,
+            stack: saved.stack,
+        })
     }
 }
 
+/// Savegames store the current state as its enclosing label and the index within it rather than
+/// a raw `StateBc`, so they stay loadable when a recompile moves states around. A suspended block
+/// is stored relative to the state as well, see `SavedSuspension`.
 impl ExecCtx {
-    pub fn read_from(r: &mut dyn Read, image: &'static ExecImage) -> Result<Self> {
-        let state = StateBc::read_from(r)?;
-        Ok(ExecCtx { image, state })
+    pub fn read_from(r: &mut dyn Read, image: Rc<ExecImage>) -> Result<Self> {
+        let label = String::read_from(r)?;
+        let index = r.readi32()?;
+        let ticks = r.readi32()?;
+        let mut regs = [0; opcode::NUM_REGS];
+        for reg in &mut regs {
+            *reg = r.readi32()?;
+        }
+        let ptr = image
+            .resolve_label_index(&label, index)
+            .ok_or_else(|| anyhow!("saved state {label}+{index} does not exist in the image"))?;
+        let mut state = image.read_state(ptr)?;
+        state.ticks = ticks;
+        let saved = Option::<SavedSuspension>::read_from(r)?;
+        let mut exec_ctx = ExecCtx {
+            image,
+            ptr,
+            state,
+            regs,
+            actor: None,
+            suspended: None,
+        };
+        exec_ctx.suspended = saved.and_then(|saved| exec_ctx.restore_suspension(saved));
+        Ok(exec_ctx)
     }
 }
 
 impl ms::Writable for ExecCtx {
     fn write(&self, w: &mut dyn Write) -> Result<()> {
-        self.state.write(w)?;
-        Ok(())
+        let (label, index) = self
+            .image
+            .find_label_for_ptr(self.ptr)
+            .ok_or_else(|| anyhow!("no label for state at 0x{:x}", self.ptr))?;
+        label.to_string().write(w)?;
+        w.writei32(index)?;
+        w.writei32(self.state.ticks)?;
+        for reg in &self.regs {
+            w.writei32(*reg)?;
+        }
+        self.saved_suspension()?.write(w)
+    }
+}
+
+/// Block of the current state a suspension belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Think,
+    Action,
+}
+
+/// `Suspended` as stored in savegames and carried over by `ExecCtx::reload`: the block is named
+/// by its kind within the current state and the resume position is relative to the block start.
+/// It is only restored if the block's code still hashes to `code_hash`.
+struct SavedSuspension {
+    kind: BlockKind,
+    resume: i32,
+    ticks: i32,
+    stack: Vec<opcode::Value>,
+    code_hash: u64,
+}
+
+/// Hash of the code of the block at `offs`
+fn block_hash(image: &ExecImage, offs: i32) -> Result<u64> {
+    let offs = usize::try_from(offs)?;
+    let instructions = opcode::decode_block(&image.code, offs)?;
+    let end = instructions
+        .last()
+        .map_or(offs, |(pos, instruction)| pos + instruction.size());
+    Ok(content_hash(&image.code[offs..end]))
+}
+
+impl ms::Loadable for SavedSuspension {
+    fn read_from(r: &mut dyn Read) -> Result<Self> {
+        let kind = match r.readu8()? {
+            0 => BlockKind::Think,
+            1 => BlockKind::Action,
+            x => return Err(anyhow!("unhandled block kind {x}")),
+        };
+        Ok(SavedSuspension {
+            kind,
+            resume: r.readi32()?,
+            ticks: r.readi32()?,
+            stack: Vec::read_from(r)?,
+            code_hash: r.readu64()?,
+        })
+    }
+}
+
+impl ms::Writable for SavedSuspension {
+    fn write(&self, w: &mut dyn Write) -> Result<()> {
+        w.writeu8(match self.kind {
+            BlockKind::Think => 0,
+            BlockKind::Action => 1,
+        })?;
+        w.writei32(self.resume)?;
+        w.writei32(self.ticks)?;
+        self.stack.write(w)?;
+        w.writeu64(self.code_hash)
+    }
+}
+
+impl ms::Loadable for opcode::Value {
+    fn read_from(r: &mut dyn Read) -> Result<Self> {
+        Ok(match r.readu8()? {
+            0 => opcode::Value::None,
+            1 => opcode::Value::U8(r.readu8()?),
+            2 => opcode::Value::I32(r.readi32()?),
+            3 => opcode::Value::Bool(r.readu8()? != 0),
+            x => return Err(anyhow!("unhandled Value discriminator {x}")),
+        })
+    }
+}
+
+impl ms::Writable for opcode::Value {
+    fn write(&self, w: &mut dyn Write) -> Result<()> {
+        match self {
+            opcode::Value::None => w.writeu8(0),
+            opcode::Value::U8(v) => {
+                w.writeu8(1)?;
+                w.writeu8(*v)
+            }
+            opcode::Value::I32(v) => {
+                w.writeu8(2)?;
+                w.writei32(*v)
+            }
+            opcode::Value::Bool(v) => {
+                w.writeu8(3)?;
+                w.writeu8(*v as u8)
+            }
+        }
     }
 }
 
 impl ExecImage {
-    pub fn from_bytes(code: &'static [u8]) -> Result<ExecImage> {
-        let mut f = Cursor::new(code);
+    pub fn load(path: impl AsRef<Path>) -> Result<ExecImage> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|err| anyhow!("failed to read {}: {err}", path.display()))?;
+        ExecImage::from_bytes(&bytes)
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<ExecImage> {
+        let mut f = Cursor::new(bytes);
+        let header = ImageHeader::read_from(&mut f)?;
+        if header.version != IMAGE_VERSION {
+            return Err(anyhow!(
+                "image format version {} is not supported (expected {IMAGE_VERSION}), recompile it",
+                header.version
+            ));
+        }
+        if content_hash(&bytes[ImageHeader::SIZE..]) != header.image_hash {
+            return Err(anyhow!("image is corrupt (content hash mismatch)"));
+        }
         let num_labels = f.readi32()?;
         let mut labels = HashMap::new();
         // let mut tmp = [0u8; 16];
@@ -376,13 +801,26 @@ impl ExecImage {
         let spawn_infos = SpawnInfos::read_from(&mut f)?;
         // println!("labels: {labels:?}");
         let code_offs = f.position() as usize;
+        let code = bytes[code_offs..].to_vec();
+        verify::verify_code(&code, &labels)?;
+        let num_states = read_state_table(&code)?.len() as i32;
         Ok(ExecImage {
-            code: &code[code_offs..],
+            header,
+            code,
+            num_states,
             labels,
             spawn_infos,
         })
     }
+    /// Read the state at `ptr`. Fails if `ptr` is not the start of a state in the state table,
+    /// e.g. for a bad GOSTATE pointer computed at runtime.
     pub fn read_state(&self, ptr: i32) -> Result<StateBc> {
+        if ptr < 0 || ptr % STATE_BC_SIZE != 0 || ptr / STATE_BC_SIZE >= self.num_states {
+            return Err(anyhow!(
+                "state pointer 0x{ptr:x} is not a state ({} states)",
+                self.num_states
+            ));
+        }
         StateBc::read_from(&mut std::io::Cursor::new(&self.code[(ptr as usize)..]))
     }
 
@@ -400,6 +838,50 @@ impl ExecImage {
     pub fn get_state_offs_by_label(&self, label: &str) -> Option<i32> {
         self.labels.get(label).cloned()
     }
+
+    /// Read the whole state table. It starts at offset 0 and ends where the first bytecode block
+    /// referenced by any state begins.
+    pub fn read_states(&self) -> Result<Vec<StateBc>> {
+        read_state_table(&self.code)
+    }
+
+    /// Pointer to the `index`th state after `label`, if it still belongs to that label (the
+    /// inverse of `find_label_for_ptr`)
+    pub fn resolve_label_index(&self, label: &str, index: i32) -> Option<i32> {
+        let ptr = self.get_state_offs_by_label(label)? + index * STATE_BC_SIZE;
+        // the label may have lost states, don't run into the next one
+        let same_label = self
+            .find_label_for_ptr(ptr)
+            .is_some_and(|(found, _)| self.labels[found] == self.labels[label]);
+        let in_table = ptr / STATE_BC_SIZE < self.num_states;
+        (index >= 0 && same_label && in_table).then_some(ptr)
+    }
+    /// Find the closest label at or before the state at `ptr`. Returns the label name and the
+    /// index of the state relative to that label.
+    pub fn find_label_for_ptr(&self, ptr: i32) -> Option<(&str, i32)> {
+        self.labels
+            .iter()
+            .filter(|(_, label_ptr)| **label_ptr <= ptr)
+            .max_by(|(name_a, ptr_a), (name_b, ptr_b)| {
+                // on ambiguity (e.g. 'sight:' and 'chase:' on the same state) prefer the
+                // alphabetically first name, so the result is stable
+                ptr_a.cmp(ptr_b).then(name_b.cmp(name_a))
+            })
+            .map(|(name, label_ptr)| (name.as_str(), (ptr - label_ptr) / STATE_BC_SIZE))
+    }
+}
+
+fn read_state_table(code: &[u8]) -> Result<Vec<StateBc>> {
+    let mut states = Vec::new();
+    let mut end = code.len() as i32;
+    let mut ptr = 0;
+    while ptr + STATE_BC_SIZE <= end {
+        let state = StateBc::read_from(&mut Cursor::new(&code[(ptr as usize)..]))?;
+        end = end.min(state.think_offs).min(state.action_offs);
+        states.push(state);
+        ptr += STATE_BC_SIZE;
+    }
+    Ok(states)
 }
 
 impl SpawnInfos {
@@ -410,6 +892,9 @@ impl SpawnInfos {
     pub fn find_spawn_info(&self, id: i32) -> Option<&EnemySpawnInfo> {
         self.spawn_infos.iter().find(|&info| info.id == id)
     }
+    pub fn find_enemy_info(&self, name: &str) -> Option<&EnemyInfo> {
+        self.enemy_infos.iter().find(|&info| info.name == name)
+    }
 }
 
 impl ms::Loadable for SpawnInfos {
@@ -420,8 +905,16 @@ impl ms::Loadable for SpawnInfos {
         for _ in 0..num {
             spawn_infos.push(EnemySpawnInfo::read_from(r)?)
         }
+        let num = r.readi32()?;
+        let mut enemy_infos = Vec::new();
+        for _ in 0..num {
+            enemy_infos.push(EnemyInfo::read_from(r)?)
+        }
 
-        Ok(Self { spawn_infos })
+        Ok(Self {
+            spawn_infos,
+            enemy_infos,
+        })
     }
 }
 
@@ -431,6 +924,10 @@ impl ms::Writable for SpawnInfos {
         for spawn_info in &self.spawn_infos {
             spawn_info.write(w)?;
         }
+        w.writei32(self.enemy_infos.len() as i32)?;
+        for enemy_info in &self.enemy_infos {
+            enemy_info.write(w)?;
+        }
         Ok(())
     }
 }
@@ -442,3 +939,159 @@ fn test_state_bc_size() {
     ms::Writable::write(&state_bc, &mut c).unwrap();
     assert_eq!(c.into_inner().len(), STATE_BC_SIZE as usize);
 }
+
+#[test]
+fn test_function_ids() {
+    assert_eq!(Function::default(), Function::None);
+    for function in Function::ALL {
+        assert_eq!(Function::try_from(u8::from(*function)).unwrap(), *function);
+        assert_eq!(
+            Function::try_from_identifier(function.name()),
+            Some(*function)
+        );
+    }
+    assert!(Function::try_from(Function::ALL.len() as u8).is_err());
+}
+
+#[cfg(test)]
+fn test_header() -> ImageHeader {
+    ImageHeader {
+        version: IMAGE_VERSION,
+        enum_hash: 0,
+        image_hash: 0,
+    }
+}
+
+#[cfg(test)]
+fn test_image(labels: &[(&str, i32)], num_states: i32) -> Rc<ExecImage> {
+    use ms::Writable;
+    let mut code = Vec::new();
+    for id in 0..num_states {
+        StateBc {
+            id,
+            ticks: 10,
+            think_offs: num_states * STATE_BC_SIZE,
+            action_offs: num_states * STATE_BC_SIZE,
+            ..Default::default()
+        }
+        .write(&mut code)
+        .unwrap();
+    }
+    code.extend(opcode::Codegen::default().stop().finalize());
+    Rc::new(ExecImage {
+        header: test_header(),
+        code,
+        num_states,
+        labels: labels
+            .iter()
+            .map(|(name, state)| (name.to_string(), state * STATE_BC_SIZE))
+            .collect(),
+        spawn_infos: Default::default(),
+    })
+}
+
+#[test]
+fn test_read_state() {
+    let image = test_image(&[("a::stand", 0)], 3);
+    assert_eq!(image.read_state(2 * STATE_BC_SIZE).unwrap().id, 2);
+    for ptr in [-STATE_BC_SIZE, 1, 3 * STATE_BC_SIZE, i32::MAX] {
+        assert!(image.read_state(ptr).is_err(), "{ptr}");
+    }
+    let mut ctx = ExecCtx::new("a::stand", image).unwrap();
+    assert!(ctx.jump(STATE_BC_SIZE + 4).is_err());
+    assert_eq!(ctx.ptr, 0);
+}
+
+#[test]
+fn test_reload() {
+    let old = test_image(&[("a::stand", 0), ("a::chase", 1)], 3);
+    let mut ctx = ExecCtx::new("a::chase", old.clone()).unwrap();
+    ctx.jump(2 * STATE_BC_SIZE).unwrap();
+    ctx.state.ticks = 3;
+
+    // a state was added in front, the second state of 'chase' moved
+    let new = test_image(&[("a::stand", 0), ("a::chase", 2)], 4);
+    assert!(ctx.reload(&new));
+    assert!(Rc::ptr_eq(&ctx.image, &new));
+    assert_eq!(ctx.ptr, 3 * STATE_BC_SIZE);
+    assert_eq!(ctx.state.id, 3);
+    assert_eq!(ctx.state.ticks, 3);
+
+    // the suspended action block moves along with the state
+    ctx.suspended = Some(Suspended {
+        block: 4 * STATE_BC_SIZE,
+        resume: 4 * STATE_BC_SIZE + 1,
+        ticks: 2,
+        stack: Vec::new(),
+    });
+    assert!(ctx.reload(&old));
+    assert_eq!(ctx.suspended_block(), Some(3 * STATE_BC_SIZE));
+    assert_eq!(
+        ctx.suspended.as_ref().unwrap().resume,
+        3 * STATE_BC_SIZE + 1
+    );
+    assert!(ctx.reload(&new));
+
+    // 'chase' lost its second state: keep running on the old image
+    let shrunk = test_image(&[("a::stand", 0), ("a::chase", 1), ("a::die", 2)], 3);
+    assert!(!ctx.reload(&shrunk));
+    assert!(Rc::ptr_eq(&ctx.image, &new));
+}
+
+#[test]
+fn test_exec_ctx_savegame() {
+    use ms::Writable;
+    let old = test_image(&[("a::stand", 0), ("a::chase", 1)], 3);
+    let mut ctx = ExecCtx::new("a::chase", old).unwrap();
+    ctx.jump(2 * STATE_BC_SIZE).unwrap();
+    ctx.state.ticks = 3;
+    ctx.regs[1] = 42;
+    let mut saved = Vec::new();
+    ctx.write(&mut saved).unwrap();
+
+    // loaded into a recompiled image with the states moved
+    let new = test_image(&[("a::stand", 0), ("a::chase", 2)], 4);
+    let loaded = ExecCtx::read_from(&mut Cursor::new(&saved), new.clone()).unwrap();
+    assert_eq!(loaded.ptr, 3 * STATE_BC_SIZE);
+    assert_eq!(loaded.state.id, 3);
+    assert_eq!(loaded.state.ticks, 3);
+    assert_eq!(loaded.regs[1], 42);
+
+    let shrunk = test_image(&[("a::stand", 0), ("a::chase", 1), ("a::die", 2)], 3);
+    let err = ExecCtx::read_from(&mut Cursor::new(&saved), shrunk).unwrap_err();
+    assert!(err.to_string().contains("a::chase+1"));
+
+    // the suspended block follows the state into the recompiled image
+    let suspended = Suspended {
+        block: 3 * STATE_BC_SIZE,
+        resume: 3 * STATE_BC_SIZE + 1,
+        ticks: 2,
+        stack: vec![opcode::Value::I32(-1), opcode::Value::Bool(true)],
+    };
+    ctx.suspended = Some(suspended.clone());
+    let mut saved = Vec::new();
+    ctx.write(&mut saved).unwrap();
+    let loaded = ExecCtx::read_from(&mut Cursor::new(&saved), ctx.image.clone()).unwrap();
+    assert_eq!(loaded.suspended, Some(suspended.clone()));
+    let loaded = ExecCtx::read_from(&mut Cursor::new(&saved), new.clone()).unwrap();
+    let moved = loaded.suspended.unwrap();
+    assert_eq!(
+        (moved.block, moved.resume),
+        (4 * STATE_BC_SIZE, 4 * STATE_BC_SIZE + 1)
+    );
+    assert_eq!(moved.stack, suspended.stack);
+
+    // ..but not if its code changed
+    let mut changed = Rc::try_unwrap(test_image(&[("a::stand", 0), ("a::chase", 2)], 4)).unwrap();
+    changed.code.truncate(4 * STATE_BC_SIZE as usize);
+    changed.code.extend(
+        opcode::Codegen::default()
+            .loadi_i32(1)
+            .wait()
+            .stop()
+            .finalize(),
+    );
+    let loaded = ExecCtx::read_from(&mut Cursor::new(&saved), Rc::new(changed)).unwrap();
+    assert_eq!(loaded.ptr, 3 * STATE_BC_SIZE);
+    assert_eq!(loaded.suspended, None);
+}
@@ -18,6 +18,10 @@ pub trait WriteExt: io::Write {
     fn writeu32(&mut self, v: u32) -> Result<()> {
         Ok(self.write_all(&v.to_le_bytes())?)
     }
+    #[inline]
+    fn writeu64(&mut self, v: u64) -> Result<()> {
+        Ok(self.write_all(&v.to_le_bytes())?)
+    }
 }
 impl<W: io::Write + ?Sized> WriteExt for W {}
 pub trait ReadExt: io::Read {
@@ -45,5 +49,11 @@ pub trait ReadExt: io::Read {
         self.read_exact(&mut buf)?;
         Ok(u32::from_le_bytes(buf))
     }
+    #[inline]
+    fn readu64(&mut self) -> Result<u64> {
+        let mut buf = [0u8; 8];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
 }
 impl<R: io::Read + ?Sized> ReadExt for R {}
@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     io::{Read, Seek, SeekFrom},
 };
@@ -17,6 +18,28 @@ const NOT: u8 = 7;
 const DUP: u8 = 8;
 const TRAP: u8 = 9;
 const GOSTATE: u8 = 10;
+const LOAD_I32: u8 = 11;
+const STORE_I32: u8 = 12;
+const SUB: u8 = 13;
+const MUL: u8 = 14;
+const DIV: u8 = 15;
+const NEG: u8 = 16;
+const CLT: u8 = 17;
+const CGT: u8 = 18;
+const CLE: u8 = 19;
+const CGE: u8 = 20;
+const AND: u8 = 21;
+const OR: u8 = 22;
+const POP: u8 = 23;
+const SWAP: u8 = 24;
+const JMP: u8 = 25;
+/// inverted JRC: jump if the condition is false. Only emitted by the optimizer.
+const JRNC: u8 = 26;
+/// suspend the block for the number of ticks on the stack, see `host::run_block`
+const WAIT: u8 = 27;
+
+/// Number of i32 registers each ExecCtx carries between invocations (addressed by LOAD_I32 / STORE_I32)
+pub const NUM_REGS: usize = 8;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Value {
@@ -26,24 +49,71 @@ pub enum Value {
     Bool(bool),
 }
 
-#[derive(Default)]
+/// Default for `Env::budget`. Blocks are short and only loop a few times, anything near this is
+/// a runaway loop.
+pub const DEFAULT_BUDGET: u32 = 10_000;
+
 pub struct Env {
     pub stack: Vec<Value>,
+    pub regs: [i32; NUM_REGS],
+    /// number of opcodes executed, for tracing
+    pub executed: u32,
+    /// maximum number of opcodes to execute before `exec` fails with `BudgetExhausted`
+    pub budget: u32,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env::with_regs(Default::default())
+    }
+}
+
+impl Env {
+    /// Create Env operating on a copy of the registers of an ExecCtx. The host is responsible to
+    /// write back `regs` after execution.
+    pub fn with_regs(regs: [i32; NUM_REGS]) -> Self {
+        Env {
+            stack: Vec::new(),
+            regs,
+            executed: 0,
+            budget: DEFAULT_BUDGET,
+        }
+    }
+}
+
+/// Error of `exec` when the `Env::budget` is used up, i.e. the bytecode most likely loops
+/// forever. Hosts can tell it from other errors with `anyhow::Error::is`.
+#[derive(Debug)]
+pub struct BudgetExhausted {
+    pub budget: u32,
 }
 
+impl std::fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "instruction budget of {} exhausted", self.budget)
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
 #[derive(Debug)]
 pub enum Event {
     Stop,
     Call(Function),
-    // Load(u8),
-    // Store(u8),
     Trap,
     GoState,
+    /// WAIT: the tick count is still on the stack
+    Wait,
 }
 
 pub fn exec<R: Read + Seek>(bc: &mut R, env: &mut Env) -> Result<Event> {
     loop {
-        match bc.readu8()? {
+        if env.executed >= env.budget {
+            return Err(BudgetExhausted { budget: env.budget }.into());
+        }
+        let op = bc.readu8()?;
+        env.executed += 1;
+        match op {
             STOP => return Ok(Event::Stop),
             PUSH_U8 => env.stack.push(Value::U8(bc.readu8()?)),
             CALL => {
@@ -60,16 +130,7 @@ pub fn exec<R: Read + Seek>(bc: &mut R, env: &mut Env) -> Result<Event> {
             TRAP => {
                 return Ok(Event::Trap);
             }
-            ADD => {
-                let a = env.stack.pop();
-                let b = env.stack.pop();
-                match (a, b) {
-                    (Some(Value::I32(a)), Some(Value::I32(b))) => {
-                        env.stack.push(Value::I32(a + b));
-                    }
-                    (a, b) => return Err(anyhow!("unhandled ADD operands: {a:?} {b:?}")),
-                }
-            }
+            ADD => arith(env, "ADD", i32::checked_add)?,
             CEQ => {
                 let a = env.stack.pop();
                 let b = env.stack.pop();
@@ -101,14 +162,321 @@ pub fn exec<R: Read + Seek>(bc: &mut R, env: &mut Env) -> Result<Event> {
                     x => return Err(anyhow!("unhandled jrc operand {x:?}")),
                 }
             }
+            JRNC => {
+                let offs = bc.readi32()?;
+                match env.stack.pop() {
+                    Some(Value::Bool(b)) => {
+                        if !b {
+                            bc.seek(SeekFrom::Current(offs as i64))?;
+                        }
+                    }
+                    x => return Err(anyhow!("unhandled jrnc operand {x:?}")),
+                }
+            }
             GOSTATE => {
                 return Ok(Event::GoState);
             }
+            WAIT => {
+                return Ok(Event::Wait);
+            }
+            LOAD_I32 => {
+                let addr = bc.readu8()? as usize;
+                match env.regs.get(addr) {
+                    Some(v) => env.stack.push(Value::I32(*v)),
+                    None => return Err(anyhow!("register address out of range: {addr}")),
+                }
+            }
+            STORE_I32 => {
+                let addr = bc.readu8()? as usize;
+                let v = match env.stack.pop() {
+                    Some(Value::I32(v)) => v,
+                    x => return Err(anyhow!("unhandled STORE_I32 operand {x:?}")),
+                };
+                match env.regs.get_mut(addr) {
+                    Some(reg) => *reg = v,
+                    None => return Err(anyhow!("register address out of range: {addr}")),
+                }
+            }
+            SUB => arith(env, "SUB", i32::checked_sub)?,
+            MUL => arith(env, "MUL", i32::checked_mul)?,
+            DIV => arith(env, "DIV", i32::checked_div)?,
+            NEG => match env.stack.pop() {
+                Some(Value::I32(v)) => match v.checked_neg() {
+                    Some(v) => env.stack.push(Value::I32(v)),
+                    None => return Err(anyhow!("NEG of {v} overflows")),
+                },
+                x => return Err(anyhow!("unhandled NEG operand {x:?}")),
+            },
+            CLT => compare(env, "CLT", Ordering::is_lt)?,
+            CGT => compare(env, "CGT", Ordering::is_gt)?,
+            CLE => compare(env, "CLE", Ordering::is_le)?,
+            CGE => compare(env, "CGE", Ordering::is_ge)?,
+            AND | OR => {
+                let a = env.stack.pop();
+                let b = env.stack.pop();
+                match (b, a) {
+                    (Some(Value::Bool(b)), Some(Value::Bool(a))) => env
+                        .stack
+                        .push(Value::Bool(if op == AND { b && a } else { b || a })),
+                    (b, a) => return Err(anyhow!("unhandled AND/OR operands: {b:?} {a:?}")),
+                }
+            }
+            POP => {
+                if env.stack.pop().is_none() {
+                    return Err(anyhow!("pop on empty stack"));
+                }
+            }
+            SWAP => {
+                let len = env.stack.len();
+                if len < 2 {
+                    return Err(anyhow!("swap needs two values on the stack"));
+                }
+                env.stack.swap(len - 1, len - 2);
+            }
+            JMP => {
+                let offs = bc.readi32()?;
+                bc.seek(SeekFrom::Current(offs as i64))?;
+            }
             x => return Err(anyhow!("unhandled opcode {x:?}")),
         }
     }
 }
 
+/// Pop the two topmost I32 operands. Returned as (b, a) with a being the topmost value, so
+/// binary operators compute `b op a` (i.e. in the order the operands were pushed).
+fn pop_i32_operands(env: &mut Env, name: &str) -> Result<(i32, i32)> {
+    let a = env.stack.pop();
+    let b = env.stack.pop();
+    match (b, a) {
+        (Some(Value::I32(b)), Some(Value::I32(a))) => Ok((b, a)),
+        (b, a) => Err(anyhow!("unhandled {name} operands: {b:?} {a:?}")),
+    }
+}
+
+/// Arithmetic `b op a` on i32 operands. Overflow is an error like division by zero, matching
+/// what `compiler::optimize::fold` refuses to fold at compile time.
+fn arith(env: &mut Env, name: &str, f: fn(i32, i32) -> Option<i32>) -> Result<()> {
+    let (b, a) = pop_i32_operands(env, name)?;
+    match f(b, a) {
+        Some(v) => {
+            env.stack.push(Value::I32(v));
+            Ok(())
+        }
+        None => Err(anyhow!(
+            "{name} of {b} and {a} overflows or divides by zero"
+        )),
+    }
+}
+
+/// Magnitude comparison `b op a`, accepting the same operand types as CEQ
+fn compare(env: &mut Env, name: &str, f: fn(Ordering) -> bool) -> Result<()> {
+    let a = env.stack.pop();
+    let b = env.stack.pop();
+    let ordering = match (b, a) {
+        (Some(Value::I32(b)), Some(Value::I32(a))) => b.cmp(&a),
+        (Some(Value::U8(b)), Some(Value::U8(a))) => b.cmp(&a),
+        (b, a) => return Err(anyhow!("unhandled {name} operands: {b:?} {a:?}")),
+    };
+    env.stack.push(Value::Bool(f(ordering)));
+    Ok(())
+}
+
+/// Decoded form of a single instruction. Used by tools that inspect bytecode without executing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Stop,
+    PushU8(u8),
+    Call,
+    LoadiI32(i32),
+    Add,
+    Jrc(i32),
+    Ceq,
+    Not,
+    Dup,
+    Trap,
+    GoState,
+    LoadI32(u8),
+    StoreI32(u8),
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    Clt,
+    Cgt,
+    Cle,
+    Cge,
+    And,
+    Or,
+    Pop,
+    Swap,
+    Jmp(i32),
+    Jrnc(i32),
+    Wait,
+}
+
+impl Instruction {
+    pub fn decode<R: Read>(bc: &mut R) -> Result<Instruction> {
+        Ok(match bc.readu8()? {
+            STOP => Instruction::Stop,
+            PUSH_U8 => Instruction::PushU8(bc.readu8()?),
+            CALL => Instruction::Call,
+            LOADI_I32 => Instruction::LoadiI32(bc.readi32()?),
+            ADD => Instruction::Add,
+            JRC => Instruction::Jrc(bc.readi32()?),
+            CEQ => Instruction::Ceq,
+            NOT => Instruction::Not,
+            DUP => Instruction::Dup,
+            TRAP => Instruction::Trap,
+            GOSTATE => Instruction::GoState,
+            LOAD_I32 => Instruction::LoadI32(bc.readu8()?),
+            STORE_I32 => Instruction::StoreI32(bc.readu8()?),
+            SUB => Instruction::Sub,
+            MUL => Instruction::Mul,
+            DIV => Instruction::Div,
+            NEG => Instruction::Neg,
+            CLT => Instruction::Clt,
+            CGT => Instruction::Cgt,
+            CLE => Instruction::Cle,
+            CGE => Instruction::Cge,
+            AND => Instruction::And,
+            OR => Instruction::Or,
+            POP => Instruction::Pop,
+            SWAP => Instruction::Swap,
+            JMP => Instruction::Jmp(bc.readi32()?),
+            JRNC => Instruction::Jrnc(bc.readi32()?),
+            WAIT => Instruction::Wait,
+            x => return Err(anyhow!("unhandled opcode {x:?}")),
+        })
+    }
+
+    /// Append the encoded instruction to `code`, the inverse of `decode`
+    pub fn encode(&self, code: &mut Vec<u8>) {
+        let (opcode, operand) = match *self {
+            Instruction::Stop => (STOP, None),
+            Instruction::PushU8(v) => (PUSH_U8, Some(v as i32)),
+            Instruction::Call => (CALL, None),
+            Instruction::LoadiI32(v) => (LOADI_I32, Some(v)),
+            Instruction::Add => (ADD, None),
+            Instruction::Jrc(offs) => (JRC, Some(offs)),
+            Instruction::Ceq => (CEQ, None),
+            Instruction::Not => (NOT, None),
+            Instruction::Dup => (DUP, None),
+            Instruction::Trap => (TRAP, None),
+            Instruction::GoState => (GOSTATE, None),
+            Instruction::LoadI32(addr) => (LOAD_I32, Some(addr as i32)),
+            Instruction::StoreI32(addr) => (STORE_I32, Some(addr as i32)),
+            Instruction::Sub => (SUB, None),
+            Instruction::Mul => (MUL, None),
+            Instruction::Div => (DIV, None),
+            Instruction::Neg => (NEG, None),
+            Instruction::Clt => (CLT, None),
+            Instruction::Cgt => (CGT, None),
+            Instruction::Cle => (CLE, None),
+            Instruction::Cge => (CGE, None),
+            Instruction::And => (AND, None),
+            Instruction::Or => (OR, None),
+            Instruction::Pop => (POP, None),
+            Instruction::Swap => (SWAP, None),
+            Instruction::Jmp(offs) => (JMP, Some(offs)),
+            Instruction::Jrnc(offs) => (JRNC, Some(offs)),
+            Instruction::Wait => (WAIT, None),
+        };
+        code.push(opcode);
+        match (self.size(), operand) {
+            (2, Some(v)) => code.push(v as u8),
+            (5, Some(v)) => code.extend_from_slice(&v.to_le_bytes()),
+            _ => (),
+        }
+    }
+
+    /// Encoded size in bytes (opcode + immediate operand)
+    pub fn size(&self) -> usize {
+        match self {
+            Instruction::PushU8(_) | Instruction::LoadI32(_) | Instruction::StoreI32(_) => 2,
+            Instruction::LoadiI32(_)
+            | Instruction::Jrc(_)
+            | Instruction::Jmp(_)
+            | Instruction::Jrnc(_) => 5,
+            _ => 1,
+        }
+    }
+
+    /// Name of the instruction as written in .st function blocks
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Stop => "stop",
+            Instruction::PushU8(_) => "loadiu8",
+            Instruction::Call => "call",
+            Instruction::LoadiI32(_) => "loadii32",
+            Instruction::Add => "add",
+            Instruction::Jrc(_) => "jrc",
+            Instruction::Ceq => "ceq",
+            Instruction::Not => "not",
+            Instruction::Dup => "dup",
+            Instruction::Trap => "trap",
+            Instruction::GoState => "gostate",
+            Instruction::LoadI32(_) => "loadi32",
+            Instruction::StoreI32(_) => "storei32",
+            Instruction::Sub => "sub",
+            Instruction::Mul => "mul",
+            Instruction::Div => "div",
+            Instruction::Neg => "neg",
+            Instruction::Clt => "clt",
+            Instruction::Cgt => "cgt",
+            Instruction::Cle => "cle",
+            Instruction::Cge => "cge",
+            Instruction::And => "and",
+            Instruction::Or => "or",
+            Instruction::Pop => "pop",
+            Instruction::Swap => "swap",
+            Instruction::Jmp(_) => "jmp",
+            Instruction::Jrnc(_) => "jrnc",
+            Instruction::Wait => "wait",
+        }
+    }
+
+    /// Absolute target of a jump instruction located at `pos`
+    pub fn jump_target(&self, pos: usize) -> Option<usize> {
+        match self {
+            Instruction::Jrc(offs) | Instruction::Jmp(offs) | Instruction::Jrnc(offs) => {
+                Some((pos + self.size()).wrapping_add_signed(*offs as isize))
+            }
+            _ => None,
+        }
+    }
+
+    /// true if execution never continues with the following instruction
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Stop | Instruction::GoState | Instruction::Jmp(_)
+        )
+    }
+}
+
+/// Decode the block starting at `offs` up to its last terminator. The block extends past a
+/// terminator as long as there are forward jumps pointing behind it.
+pub fn decode_block(code: &[u8], offs: usize) -> Result<Vec<(usize, Instruction)>> {
+    let mut cursor = std::io::Cursor::new(code);
+    cursor.set_position(offs as u64);
+    let mut instructions = Vec::new();
+    let mut max_target = offs;
+    loop {
+        let pos = cursor.position() as usize;
+        if pos >= code.len() {
+            return Err(anyhow!("block @{offs:04x} runs past end of code"));
+        }
+        let instruction = Instruction::decode(&mut cursor)?;
+        if let Some(target) = instruction.jump_target(pos) {
+            max_target = max_target.max(target);
+        }
+        instructions.push((pos, instruction));
+        if instruction.is_terminator() && cursor.position() as usize > max_target {
+            return Ok(instructions);
+        }
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct Codegen {
     code: Vec<u8>,
@@ -131,6 +499,16 @@ impl Codegen {
         self.code.extend_from_slice(&v.to_le_bytes());
         self
     }
+    pub fn load_i32(mut self, addr: u8) -> Self {
+        self.code.push(LOAD_I32);
+        self.code.push(addr);
+        self
+    }
+    pub fn store_i32(mut self, addr: u8) -> Self {
+        self.code.push(STORE_I32);
+        self.code.push(addr);
+        self
+    }
     pub fn loadi_u8(mut self, v: u8) -> Self {
         self.code.push(PUSH_U8);
         self.code.push(v);
@@ -152,6 +530,54 @@ impl Codegen {
         self.code.push(NOT);
         self
     }
+    pub fn sub(mut self) -> Self {
+        self.code.push(SUB);
+        self
+    }
+    pub fn mul(mut self) -> Self {
+        self.code.push(MUL);
+        self
+    }
+    pub fn div(mut self) -> Self {
+        self.code.push(DIV);
+        self
+    }
+    pub fn bin_neg(mut self) -> Self {
+        self.code.push(NEG);
+        self
+    }
+    pub fn clt(mut self) -> Self {
+        self.code.push(CLT);
+        self
+    }
+    pub fn cgt(mut self) -> Self {
+        self.code.push(CGT);
+        self
+    }
+    pub fn cle(mut self) -> Self {
+        self.code.push(CLE);
+        self
+    }
+    pub fn cge(mut self) -> Self {
+        self.code.push(CGE);
+        self
+    }
+    pub fn bin_and(mut self) -> Self {
+        self.code.push(AND);
+        self
+    }
+    pub fn bin_or(mut self) -> Self {
+        self.code.push(OR);
+        self
+    }
+    pub fn pop(mut self) -> Self {
+        self.code.push(POP);
+        self
+    }
+    pub fn swap(mut self) -> Self {
+        self.code.push(SWAP);
+        self
+    }
     pub fn call(mut self) -> Self {
         self.code.push(CALL);
         self
@@ -175,6 +601,17 @@ impl Codegen {
         self.code.extend_from_slice(&0i32.to_le_bytes());
         self
     }
+    pub fn jmp(mut self, offs: i32) -> Self {
+        self.code.push(JMP);
+        self.code.extend_from_slice(&offs.to_le_bytes());
+        self
+    }
+    pub fn jmp_label(mut self, name: &str) -> Self {
+        self.code.push(JMP);
+        self.label_refs.push((name.into(), self.code.len()));
+        self.code.extend_from_slice(&0i32.to_le_bytes());
+        self
+    }
     pub fn label(mut self, label: &str) -> Self {
         self.labels.insert(label.into(), self.code.len());
         self
@@ -189,6 +626,10 @@ impl Codegen {
         self.code.push(GOSTATE);
         self
     }
+    pub fn wait(mut self) -> Codegen {
+        self.code.push(WAIT);
+        self
+    }
     pub fn with_state_label_ptrs(mut self, label_ptrs: &HashMap<String, i32>) -> Codegen {
         self.state_labels = label_ptrs.clone();
         self
@@ -328,3 +769,197 @@ fn test_loop() {
     let e = exec(&mut c, &mut env);
     assert!(matches!(e, Ok(Event::Stop)));
 }
+
+#[test]
+fn test_budget() {
+    let bc = Codegen::default()
+        .label("loop")
+        .loadi_i32(1)
+        .pop()
+        .jmp_label("loop")
+        .finalize();
+    let mut env = Env {
+        budget: 30,
+        ..Default::default()
+    };
+    let err = exec(&mut std::io::Cursor::new(bc), &mut env).unwrap_err();
+    assert_eq!(err.downcast_ref::<BudgetExhausted>().unwrap().budget, 30);
+    assert_eq!(env.executed, 30);
+}
+#[test]
+fn test_load_store() {
+    let mut env = Env::with_regs([0, 0, 0, 0, 0, 0, 0, 4711]);
+    let bc = Codegen::default()
+        .load_i32(7) // copy r7 to r1, incremented by one
+        .loadi_i32(1)
+        .add()
+        .store_i32(1)
+        .loadi_i32(-5)
+        .store_i32(0)
+        .load_i32(1)
+        .stop()
+        .finalize();
+
+    let mut c = std::io::Cursor::new(bc);
+    let e = exec(&mut c, &mut env);
+    assert!(matches!(e, Ok(Event::Stop)));
+    assert_eq!(env.stack, [Value::I32(4712)]);
+    assert_eq!(env.regs, [-5, 4712, 0, 0, 0, 0, 0, 4711]);
+
+    let bc = Codegen::default()
+        .load_i32(NUM_REGS as u8)
+        .stop()
+        .finalize();
+    let e = exec(&mut std::io::Cursor::new(bc), &mut env);
+    assert!(e.is_err());
+}
+#[test]
+fn test_decode() {
+    let bc = Codegen::default()
+        .loadi_u8(1)
+        .label("loop")
+        .loadi_i32(-1)
+        .jrc_label("loop")
+        .store_i32(2)
+        .wait()
+        .gostate()
+        .finalize();
+    let mut c = std::io::Cursor::new(&bc);
+    let mut decoded = Vec::new();
+    while (c.position() as usize) < bc.len() {
+        let pos = c.position() as usize;
+        let instruction = Instruction::decode(&mut c).unwrap();
+        assert_eq!(c.position() as usize, pos + instruction.size());
+        decoded.push((pos, instruction));
+    }
+    assert_eq!(
+        decoded,
+        [
+            (0, Instruction::PushU8(1)),
+            (2, Instruction::LoadiI32(-1)),
+            (7, Instruction::Jrc(-10)),
+            (12, Instruction::StoreI32(2)),
+            (14, Instruction::Wait),
+            (15, Instruction::GoState),
+        ]
+    );
+    assert_eq!(decoded[2].1.jump_target(7), Some(2));
+    assert!(!decoded[4].1.is_terminator());
+    assert!(decoded[5].1.is_terminator());
+    let mut encoded = Vec::new();
+    for (_, instruction) in &decoded {
+        instruction.encode(&mut encoded);
+    }
+    assert_eq!(encoded, bc);
+}
+#[test]
+fn test_arith() {
+    let mut env = Env::default();
+    let bc = Codegen::default()
+        .loadi_i32(10)
+        .loadi_i32(3)
+        .sub() // 7
+        .loadi_i32(6)
+        .mul() // 42
+        .loadi_i32(5)
+        .div() // 8
+        .bin_neg() // -8
+        .loadi_i32(1)
+        .loadi_i32(2)
+        .swap()
+        .pop() // leaves 2
+        .stop()
+        .finalize();
+    let e = exec(&mut std::io::Cursor::new(bc), &mut env);
+    assert!(matches!(e, Ok(Event::Stop)));
+    assert_eq!(env.stack, [Value::I32(-8), Value::I32(2)]);
+
+    // division by zero and overflow fail instead of panicking
+    let overflowing = [
+        Codegen::default().loadi_i32(1).loadi_i32(0).div(),
+        Codegen::default().loadi_i32(i32::MIN).loadi_i32(-1).div(),
+        Codegen::default().loadi_i32(i32::MAX).loadi_i32(1).add(),
+        Codegen::default().loadi_i32(i32::MIN).loadi_i32(1).sub(),
+        Codegen::default().loadi_i32(i32::MAX).loadi_i32(2).mul(),
+        Codegen::default().loadi_i32(i32::MIN).bin_neg(),
+    ];
+    for codegen in overflowing {
+        let bc = codegen.stop().finalize();
+        let e = exec(&mut std::io::Cursor::new(bc), &mut Env::default());
+        assert!(e.is_err());
+    }
+}
+#[test]
+fn test_compare() {
+    let mut env = Env::default();
+    let bc = Codegen::default()
+        .loadi_i32(1)
+        .loadi_i32(2)
+        .clt() // true
+        .loadi_i32(1)
+        .loadi_i32(2)
+        .cgt() // false
+        .loadi_i32(2)
+        .loadi_i32(2)
+        .cle() // true
+        .loadi_u8(1)
+        .loadi_u8(2)
+        .cge() // false
+        .stop()
+        .finalize();
+    let e = exec(&mut std::io::Cursor::new(bc), &mut env);
+    assert!(matches!(e, Ok(Event::Stop)));
+    assert_eq!(
+        env.stack,
+        [
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Bool(true),
+            Value::Bool(false)
+        ]
+    );
+
+    let mut env = Env::default();
+    let bc = Codegen::default()
+        .loadi_u8(0)
+        .loadi_u8(0)
+        .ceq()
+        .dup()
+        .bin_not()
+        .bin_and() // false
+        .loadi_u8(0)
+        .loadi_u8(0)
+        .ceq()
+        .bin_or() // true
+        .stop()
+        .finalize();
+    let e = exec(&mut std::io::Cursor::new(bc), &mut env);
+    assert!(matches!(e, Ok(Event::Stop)));
+    assert_eq!(env.stack, [Value::Bool(true)]);
+}
+#[test]
+fn test_jmp() {
+    let mut env = Env::default();
+    // clamp a value to 100 using a forward jump and an unconditional jump back
+    let bc = Codegen::default()
+        .loadi_i32(150)
+        .jmp_label("clamp")
+        .label("done")
+        .stop()
+        .label("clamp")
+        .dup()
+        .loadi_i32(100)
+        .cgt()
+        .bin_not()
+        .jrc_label("done")
+        .pop()
+        .loadi_i32(100)
+        .jmp_label("done")
+        .finalize();
+    let e = exec(&mut std::io::Cursor::new(&bc), &mut env);
+    assert!(matches!(e, Ok(Event::Stop)));
+    assert_eq!(env.stack, [Value::I32(100)]);
+
+    let instructions = decode_block(&bc, 0).unwrap();
+    assert_eq!(instructions.last().unwrap().1, Instruction::Jmp(-25));
+}
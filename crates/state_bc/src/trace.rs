@@ -0,0 +1,267 @@
+//! Execution tracing, to find out what a misbehaving actor did and which states are hot or dead.
+//! Tracing is off by default. Once enabled with `enable`, the interpreter records the executed
+//! blocks, state transitions, calls and traps of the current thread into a ring buffer, and counts
+//! how often each state label is entered and how many opcodes run per label and per tick.
+
+use crate::{ExecImage, Function};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, VecDeque},
+    io::Write,
+};
+
+#[derive(Debug, Clone)]
+pub enum TraceEvent {
+    /// bytecode block at `offs` ran `ops` opcodes
+    Block {
+        offs: i32,
+        ops: u32,
+    },
+    /// entered the `index`th state after `label`
+    State {
+        label: String,
+        index: i32,
+    },
+    Call(Function),
+    Trap(String),
+    /// block suspended by WAIT for the given number of ticks
+    Wait(i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceRecord {
+    pub tick: u32,
+    /// `ExecCtx::actor` of the context that caused the event
+    pub actor: Option<usize>,
+    pub event: TraceEvent,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LabelStats {
+    pub entries: u64,
+    /// opcodes executed in the blocks of the label's states
+    pub ops: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Tracer {
+    capacity: usize,
+    records: VecDeque<TraceRecord>,
+    pub tick: u32,
+    ops_this_tick: u64,
+    pub max_ops_per_tick: u64,
+    pub total_ops: u64,
+    pub labels: BTreeMap<String, LabelStats>,
+    /// label of the last state entered by each actor
+    current_labels: HashMap<Option<usize>, String>,
+}
+
+impl Tracer {
+    pub fn new(capacity: usize) -> Self {
+        Tracer {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, actor: Option<usize>, event: TraceEvent) {
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(TraceRecord {
+            tick: self.tick,
+            actor,
+            event,
+        });
+    }
+
+    /// The most recent records, oldest first
+    pub fn records(&self) -> impl Iterator<Item = &TraceRecord> {
+        self.records.iter()
+    }
+
+    pub fn dump(&self, w: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(w, "last {} trace records:", self.records.len())?;
+        for record in &self.records {
+            let actor = match record.actor {
+                Some(actor) => actor.to_string(),
+                None => "-".to_string(),
+            };
+            let event = match &record.event {
+                TraceEvent::Block { offs, ops } => format!("block {offs:04x}: {ops} ops"),
+                TraceEvent::State { label, index } => format!("state {label}+{index}"),
+                TraceEvent::Call(function) => format!("call {function:?}"),
+                TraceEvent::Trap(trap) => format!("trap {trap}"),
+                TraceEvent::Wait(ticks) => format!("wait {ticks}"),
+            };
+            writeln!(w, "{:6} {actor:>5} {event}", record.tick)?;
+        }
+        Ok(())
+    }
+
+    /// Per label counters, hottest first. Labels of `image` that were never entered are listed
+    /// at the end.
+    pub fn report(&self, image: &ExecImage, w: &mut dyn Write) -> std::io::Result<()> {
+        let completed_ticks = self.tick.max(1) as u64;
+        writeln!(
+            w,
+            "{} ticks, {} ops/tick average, {} max",
+            self.tick,
+            self.total_ops / completed_ticks,
+            self.max_ops_per_tick
+        )?;
+        let mut labels: Vec<_> = self.labels.iter().collect();
+        labels.sort_by_key(|(name, stats)| (std::cmp::Reverse(stats.ops), *name));
+        writeln!(w, "{:>10} {:>8} label", "ops", "entries")?;
+        for (name, stats) in labels {
+            writeln!(w, "{:>10} {:>8} {name}", stats.ops, stats.entries)?;
+        }
+        let mut never_entered: Vec<_> = image
+            .labels
+            .keys()
+            .filter(|name| !self.labels.contains_key(*name))
+            .collect();
+        never_entered.sort();
+        writeln!(w, "never entered:")?;
+        for name in never_entered {
+            writeln!(w, "  {name}")?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+}
+
+/// Start tracing on this thread, keeping the last `capacity` records
+pub fn enable(capacity: usize) {
+    TRACER.with(|tracer| *tracer.borrow_mut() = Some(Tracer::new(capacity)));
+}
+
+/// Stop tracing, returns what was recorded
+pub fn disable() -> Option<Tracer> {
+    TRACER.with(|tracer| tracer.borrow_mut().take())
+}
+
+pub fn is_enabled() -> bool {
+    TRACER.with(|tracer| tracer.borrow().is_some())
+}
+
+/// Run `f` on the tracer, if tracing is enabled
+pub fn with_tracer<R>(f: impl FnOnce(&mut Tracer) -> R) -> Option<R> {
+    TRACER.with(|tracer| tracer.borrow_mut().as_mut().map(f))
+}
+
+pub fn record(actor: Option<usize>, event: TraceEvent) {
+    with_tracer(|tracer| {
+        match &event {
+            TraceEvent::State { label, index } => {
+                // following the states of a label is not another entry
+                let previous = tracer.current_labels.insert(actor, label.clone());
+                if *index == 0 || previous.is_some_and(|previous| previous != *label) {
+                    tracer.labels.entry(label.clone()).or_default().entries += 1;
+                }
+            }
+            TraceEvent::Block { ops, .. } => {
+                tracer.ops_this_tick += *ops as u64;
+                tracer.total_ops += *ops as u64;
+            }
+            _ => (),
+        }
+        tracer.push(actor, event);
+    });
+}
+
+/// Attribute `ops` executed opcodes to `label`
+pub fn count_ops(label: &str, ops: u32) {
+    with_tracer(|tracer| {
+        if let Some(stats) = tracer.labels.get_mut(label) {
+            stats.ops += ops as u64;
+        } else {
+            // running since before tracing was enabled
+            tracer.labels.insert(
+                label.to_string(),
+                LabelStats {
+                    entries: 0,
+                    ops: ops as u64,
+                },
+            );
+        }
+    });
+}
+
+/// Called by the game after each game tick
+pub fn end_tick() {
+    with_tracer(|tracer| {
+        tracer.max_ops_per_tick = tracer.max_ops_per_tick.max(tracer.ops_this_tick);
+        tracer.ops_this_tick = 0;
+        tracer.tick += 1;
+    });
+}
+
+/// Dump the ring buffer to stderr when the thread panics, e.g. on a bytecode error
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        // the tracer may be borrowed by the code that panicked
+        let _ = TRACER.try_with(|tracer| match tracer.try_borrow() {
+            Ok(tracer) => {
+                if let Some(tracer) = tracer.as_ref() {
+                    let _ = tracer.dump(&mut std::io::stderr().lock());
+                }
+            }
+            Err(_) => eprintln!("trace not available, tracer was in use"),
+        });
+    }));
+}
+
+#[test]
+fn test_tracer() {
+    use crate::{ExecCtx, STATE_BC_SIZE};
+
+    let image = crate::test_image(&[("a::stand", 0), ("a::chase", 1), ("a::die", 3)], 4);
+    let mut ctx = ExecCtx::new("a::stand", image.clone()).unwrap();
+    ctx.actor = Some(3);
+    ctx.jump(STATE_BC_SIZE).unwrap(); // not traced yet
+
+    enable(2);
+    ctx.jump(2 * STATE_BC_SIZE).unwrap();
+    record(ctx.actor, TraceEvent::Block { offs: 0, ops: 5 });
+    count_ops("a::chase", 5);
+    end_tick();
+    ctx.jump(0).unwrap();
+    let tracer = disable().unwrap();
+    assert!(!is_enabled());
+
+    // only the last two records are kept
+    let records: Vec<_> = tracer.records().collect();
+    assert_eq!(records.len(), 2);
+    assert!(matches!(records[0].event, TraceEvent::Block { ops: 5, .. }));
+    assert!(matches!(
+        &records[1].event,
+        TraceEvent::State { label, index: 0 } if label == "a::stand"
+    ));
+    assert_eq!(records[1].actor, Some(3));
+    assert_eq!(records[1].tick, 1);
+    // a::chase was entered before tracing
+    assert_eq!(tracer.labels["a::chase"].entries, 0);
+    assert_eq!(tracer.labels["a::chase"].ops, 5);
+    assert_eq!(tracer.labels["a::stand"].entries, 1);
+    assert_eq!(tracer.max_ops_per_tick, 5);
+
+    let mut report = Vec::new();
+    tracer.report(&image, &mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.ends_with("never entered:\n  a::die\n"));
+
+    // each label counts once per entry, not once per state
+    enable(8);
+    for ptr in [1, 2, 3, 1] {
+        ctx.jump(ptr * STATE_BC_SIZE).unwrap();
+    }
+    let tracer = disable().unwrap();
+    assert_eq!(tracer.labels["a::chase"].entries, 2);
+    assert_eq!(tracer.labels["a::die"].entries, 1);
+}
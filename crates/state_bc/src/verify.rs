@@ -0,0 +1,293 @@
+//! Static checks for compiled images. Everything that would otherwise only show up as an error
+//! (or worse, silently wrong behavior) in the middle of a game is rejected up front: unknown
+//! opcodes, jumps outside the bytecode or into an operand, unbalanced stacks and broken state links.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use anyhow::anyhow;
+
+use crate::{
+    opcode::{Instruction, NUM_REGS},
+    read_state_table, Result, STATE_BC_SIZE,
+};
+
+/// Stack depth at an instruction. Becomes `Unknown` after a TRAP: the trap number is popped, but
+/// the host decides how many more values it pops and pushes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Depth {
+    Known(usize),
+    Unknown,
+}
+
+/// Verify the state table, the label pointers and every think / action block referenced from
+/// it. `code` is the image code section (state table followed by bytecode). All problems are
+/// collected and reported in a single error.
+pub fn verify_code<'a>(
+    code: &[u8],
+    labels: impl IntoIterator<Item = (&'a String, &'a i32)>,
+) -> Result<()> {
+    let mut errors = Vec::new();
+    let states = read_state_table(code)?;
+    let states_end = states.len() as i32 * STATE_BC_SIZE;
+    let is_state_ptr = |ptr: i32| ptr >= 0 && ptr < states_end && ptr % STATE_BC_SIZE == 0;
+
+    let mut labels: Vec<_> = labels.into_iter().collect();
+    labels.sort();
+    for (name, ptr) in labels {
+        if !is_state_ptr(*ptr) {
+            errors.push(format!("label {name}: {ptr} is not a state pointer"));
+        }
+    }
+
+    // several states usually share the same block, only check each one once
+    let mut blocks = BTreeMap::new();
+    for (i, state) in states.iter().enumerate() {
+        let ptr = i as i32 * STATE_BC_SIZE;
+        if !is_state_ptr(state.next) {
+            errors.push(format!(
+                "state @{ptr:04x}: next {} is not a state pointer",
+                state.next
+            ));
+        }
+        for (kind, offs) in [("think", state.think_offs), ("action", state.action_offs)] {
+            if offs < states_end || offs as usize >= code.len() {
+                errors.push(format!(
+                    "state @{ptr:04x}: {kind} offset {offs} is outside of the bytecode section"
+                ));
+            } else {
+                blocks.entry(offs as usize).or_insert(ptr);
+            }
+        }
+    }
+    for (offs, ptr) in blocks {
+        if let Err(err) = verify_block(code, states_end as usize, offs) {
+            errors.push(format!(
+                "block @{offs:04x} (used by state @{ptr:04x}): {err}"
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "image verification failed:\n  {}",
+            errors.join("\n  ")
+        ))
+    }
+}
+
+/// Check the code reachable from `offs`: all instructions decode, jumps land inside the bytecode
+/// section (`bytecode_start..`), no two instructions overlap (a jump into an operand), the stack
+/// never underflows, every merge point is reached with the same stack depth and every path ends
+/// in STOP or GOSTATE. Jumps may leave the block, the compiler shares identical tails of blocks.
+pub fn verify_block(code: &[u8], bytecode_start: usize, offs: usize) -> Result<()> {
+    // reachable instructions and the stack depth they are reached with
+    let mut instructions: BTreeMap<usize, (Instruction, Depth)> = BTreeMap::new();
+    let mut worklist = VecDeque::from([(offs, Depth::Known(0))]);
+    while let Some((pos, depth)) = worklist.pop_front() {
+        let (instruction, merged) = match instructions.get(&pos) {
+            None => {
+                let mut cursor = std::io::Cursor::new(code);
+                cursor.set_position(pos as u64);
+                let instruction = Instruction::decode(&mut cursor)
+                    .map_err(|err| anyhow!("at @{pos:04x}: {err}"))?;
+                (instruction, depth)
+            }
+            Some(&(instruction, Depth::Known(a))) => match depth {
+                Depth::Known(b) if a != b => {
+                    return Err(anyhow!(
+                        "inconsistent stack depth at @{pos:04x} ({a} vs {b})"
+                    ))
+                }
+                Depth::Known(_) => continue,
+                Depth::Unknown => (instruction, Depth::Unknown),
+            },
+            Some((_, Depth::Unknown)) => continue,
+        };
+        instructions.insert(pos, (instruction, merged));
+
+        let after = match (merged, stack_effect(&instruction)) {
+            (Depth::Unknown, _) => Depth::Unknown,
+            (Depth::Known(d), effect) => {
+                let (pops, pushes) = effect.unwrap_or((1, 0));
+                if d < pops {
+                    return Err(anyhow!(
+                        "stack underflow at @{pos:04x}: {} needs {pops} value(s), {d} available",
+                        instruction.mnemonic()
+                    ));
+                }
+                match effect {
+                    Some(_) => Depth::Known(d - pops + pushes),
+                    None => Depth::Unknown,
+                }
+            }
+        };
+        match instruction {
+            Instruction::LoadI32(addr) | Instruction::StoreI32(addr)
+                if addr as usize >= NUM_REGS =>
+            {
+                return Err(anyhow!(
+                    "register address {addr} out of range at @{pos:04x}"
+                ));
+            }
+            _ => (),
+        }
+        if let Some(target) = instruction.jump_target(pos) {
+            if !(bytecode_start..code.len()).contains(&target) {
+                return Err(anyhow!(
+                    "jump at @{pos:04x} to @{target:04x} leaves the bytecode section"
+                ));
+            }
+            worklist.push_back((target, after));
+        }
+        if !instruction.is_terminator() {
+            let next = pos + instruction.size();
+            if next >= code.len() {
+                return Err(anyhow!(
+                    "code at @{pos:04x} runs past the end without STOP or GOSTATE"
+                ));
+            }
+            worklist.push_back((next, after));
+        }
+    }
+
+    let mut prev_end = 0;
+    for (pos, (instruction, _)) in &instructions {
+        if *pos < prev_end {
+            return Err(anyhow!(
+                "jump to @{pos:04x} does not hit an instruction boundary"
+            ));
+        }
+        prev_end = pos + instruction.size();
+    }
+    Ok(())
+}
+
+/// (pops, pushes) of an instruction, or None if the effect is unknown statically. Instructions
+/// with an unknown effect still need one value, the TRAP number.
+fn stack_effect(instruction: &Instruction) -> Option<(usize, usize)> {
+    Some(match instruction {
+        Instruction::Stop => (0, 0),
+        Instruction::PushU8(_) | Instruction::LoadiI32(_) | Instruction::LoadI32(_) => (0, 1),
+        Instruction::Call | Instruction::GoState | Instruction::Wait | Instruction::StoreI32(_) => {
+            (1, 0)
+        }
+        Instruction::Jrc(_) | Instruction::Jrnc(_) => (1, 0),
+        Instruction::Jmp(_) => (0, 0),
+        Instruction::Add
+        | Instruction::Sub
+        | Instruction::Mul
+        | Instruction::Div
+        | Instruction::Ceq
+        | Instruction::Clt
+        | Instruction::Cgt
+        | Instruction::Cle
+        | Instruction::Cge
+        | Instruction::And
+        | Instruction::Or => (2, 1),
+        Instruction::Not | Instruction::Neg => (1, 1),
+        Instruction::Dup => (1, 2),
+        Instruction::Pop => (1, 0),
+        Instruction::Swap => (2, 2),
+        // the trap number is always popped, the rest is up to the host
+        Instruction::Trap => return None,
+    })
+}
+
+#[cfg(test)]
+fn image(next: i32, block: crate::opcode::Codegen) -> Vec<u8> {
+    use crate::ms::Writable;
+    let mut code = Vec::new();
+    let state = crate::StateBc {
+        id: 0,
+        ticks: 1,
+        directional: false,
+        think_offs: STATE_BC_SIZE,
+        action_offs: STATE_BC_SIZE,
+        next,
+    };
+    state.write(&mut code).unwrap();
+    code.extend(block.finalize());
+    code
+}
+
+#[cfg(test)]
+fn labels(ptr: i32) -> std::collections::HashMap<String, i32> {
+    std::collections::HashMap::from([("test::start".to_string(), ptr)])
+}
+
+#[test]
+fn test_valid() {
+    let block = crate::opcode::Codegen::default()
+        .loadi_u8(1)
+        .jrc_label("end")
+        .loadi_i32(0)
+        .gostate()
+        .label("end")
+        .stop();
+    verify_code(&image(0, block), &labels(0)).unwrap();
+}
+
+#[test]
+fn test_trap() {
+    let block = crate::opcode::Codegen::default()
+        .loadi_u8(0)
+        .trap()
+        .dup()
+        .stop();
+    verify_code(&image(0, block), &labels(0)).unwrap();
+
+    // no trap number on the stack
+    let block = crate::opcode::Codegen::default().trap().stop();
+    let err = verify_code(&image(0, block), &labels(0)).unwrap_err();
+    assert!(err.to_string().contains("stack underflow"), "{err}");
+}
+
+#[test]
+fn test_bad_state_links() {
+    let block = crate::opcode::Codegen::default().stop();
+    assert!(verify_code(&image(3, block.clone()), &labels(0)).is_err());
+    assert!(verify_code(&image(0, block), &labels(STATE_BC_SIZE)).is_err());
+}
+
+#[test]
+fn test_jump_out_of_block() {
+    let block = crate::opcode::Codegen::default()
+        .loadi_u8(1)
+        .jrc(-20)
+        .stop();
+    assert!(verify_code(&image(0, block), &labels(0)).is_err());
+    // jump into the middle of the loadii32 operand
+    let block = crate::opcode::Codegen::default()
+        .loadi_u8(1)
+        .jrc(1)
+        .loadi_i32(0)
+        .stop()
+        .stop();
+    assert!(verify_code(&image(0, block), &labels(0)).is_err());
+}
+
+#[test]
+fn test_stack_underflow() {
+    let block = crate::opcode::Codegen::default().loadi_u8(1).add().stop();
+    assert!(verify_code(&image(0, block), &labels(0)).is_err());
+}
+
+#[test]
+fn test_inconsistent_depth() {
+    // pushes one more value on every iteration
+    let block = crate::opcode::Codegen::default()
+        .label("loop")
+        .loadi_u8(1)
+        .loadi_u8(1)
+        .jrc_label("loop")
+        .stop();
+    assert!(verify_code(&image(0, block), &labels(0)).is_err());
+}
+
+#[test]
+fn test_missing_terminator() {
+    let block = crate::opcode::Codegen::default().loadi_u8(1).dup();
+    assert!(verify_code(&image(0, block), &labels(0)).is_err());
+}
@@ -0,0 +1,118 @@
+//! In-game debugger for the enemy state machines. An enemy picked with the crosshair is frozen
+//! and can be single-stepped, breakpoints on state labels pause the world
+//! (`Mainloop::stop_the_world_mode`) when any enemy enters them.
+
+use crate::prelude::*;
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+pub struct Debugger {
+    /// unique_id of the inspected enemy. It does not run until released or single-stepped.
+    pub selected: Option<usize>,
+    pub breakpoints: BTreeSet<String>,
+    /// the breakpoint that paused the world last: enemy and label
+    pub last_break: Option<(usize, String)>,
+}
+
+fn enemy(things: &Things, unique_id: usize) -> Option<&Enemy> {
+    match &things.things.get(unique_id)?.actor {
+        Actor::Enemy { enemy } => Some(enemy),
+        _ => None,
+    }
+}
+
+/// Label of the state `enemy` is in, and the index of the state within it
+fn current_label(enemy: &Enemy) -> Option<(String, i32)> {
+    let exec_ctx = &enemy.exec_ctx;
+    exec_ctx
+        .image
+        .find_label_for_ptr(exec_ctx.ptr)
+        .map(|(label, index)| (label.to_string(), index))
+}
+
+impl Debugger {
+    /// Select the enemy under the crosshair, or release the selected one
+    pub fn toggle_select(&mut self, thing: Option<usize>) {
+        self.selected = match self.selected {
+            Some(_) => None,
+            None => thing,
+        };
+        self.last_break = None;
+    }
+
+    /// Set or clear a breakpoint on the label of the selected enemy's current state
+    pub fn toggle_breakpoint(&mut self, things: &Things) {
+        let Some((label, _)) = self
+            .selected
+            .and_then(|id| enemy(things, id))
+            .and_then(current_label)
+        else {
+            return;
+        };
+        if !self.breakpoints.remove(&label) {
+            self.breakpoints.insert(label);
+        }
+    }
+
+    /// State pointers of all enemies, to be passed to `check_breakpoints` after the update.
+    /// Empty without breakpoints.
+    pub fn snapshot(&self, things: &Things) -> Vec<i32> {
+        if self.breakpoints.is_empty() {
+            return Vec::new();
+        }
+        things
+            .things
+            .iter()
+            .map(|thing| match &thing.actor {
+                Actor::Enemy { enemy } => enemy.exec_ctx.ptr,
+                _ => -1,
+            })
+            .collect()
+    }
+
+    /// Returns true if an enemy entered a breakpoint label since `snapshot`. That enemy gets
+    /// selected.
+    pub fn check_breakpoints(&mut self, snapshot: &[i32], things: &Things) -> bool {
+        for (unique_id, old_ptr) in snapshot.iter().enumerate() {
+            let Some(enemy) = enemy(things, unique_id) else {
+                continue;
+            };
+            if enemy.exec_ctx.ptr == *old_ptr {
+                continue;
+            }
+            match current_label(enemy) {
+                Some((label, 0)) if self.breakpoints.contains(&label) => {
+                    println!("break: enemy {unique_id} entered {label}");
+                    self.selected = Some(unique_id);
+                    self.last_break = Some((unique_id, label));
+                    return true;
+                }
+                _ => (),
+            }
+        }
+        false
+    }
+
+    pub fn draw<D: Draw + ?Sized>(&self, things: &Things, buffer: &mut D) {
+        let mut lines = Vec::new();
+        if let Some((unique_id, enemy)) = self
+            .selected
+            .and_then(|id| enemy(things, id).map(|enemy| (id, enemy)))
+        {
+            lines.push(format!("debug: enemy {unique_id}"));
+            if let Some((label, index)) = current_label(enemy) {
+                lines.push(format!("state: {label}+{index}"));
+            }
+            lines.extend(enemy.debug_lines());
+        }
+        if let Some((unique_id, label)) = &self.last_break {
+            lines.push(format!("break: {unique_id} at {label}"));
+        }
+        for label in &self.breakpoints {
+            lines.push(format!("bp: {label}"));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            draw_string8x8(line, buffer, 0, i as i32 * 8);
+        }
+    }
+}
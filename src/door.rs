@@ -1,7 +1,10 @@
-use crate::prelude::*;
+use crate::{enums::DoorTrap, prelude::*};
 use anyhow::anyhow;
-use state_bc::opcode::Value;
-use std::{collections::HashSet, io::Cursor};
+use state_bc::{
+    host::{run_block, Host, TrapHandler},
+    opcode::Value,
+};
+use std::collections::HashSet;
 
 impl From<Fp16> for opcode::Value {
     fn from(value: Fp16) -> Self {
@@ -18,8 +21,53 @@ impl TryFrom<opcode::Value> for Fp16 {
         }
     }
 }
+/// Door plus the per-update inputs its bytecode needs
+struct DoorHost<'a> {
+    door: &'a mut Door,
+    trigger: bool,
+    blocked: bool,
+    audio_service: &'a mut dyn AudioService,
+}
+
+impl TrapHandler for DoorHost<'_> {
+    type Trap = DoorTrap;
+
+    fn trap(&mut self, trap: DoorTrap, env: &mut opcode::Env) -> Result<()> {
+        match trap {
+            DoorTrap::GetOpen => env.stack.push(self.door.open_f.into()),
+            DoorTrap::SetOpen => match env.stack.pop() {
+                Some(opcode::Value::I32(v)) => self.door.open_f.v = v,
+                Some(x) => return Err(anyhow!("unhandled opcode::Value {x:?}")),
+                None => return Err(anyhow!("stack underflow")),
+            },
+            DoorTrap::Trigger => env.stack.push(Value::Bool(self.trigger)),
+            DoorTrap::Blocked => env.stack.push(Value::Bool(self.blocked)),
+            DoorTrap::PlaySound => {
+                if let Some(Value::U8(snd_id)) = env.stack.pop() {
+                    self.audio_service.play_sound(snd_id as i32);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Host for DoorHost<'_> {
+    fn exec_ctx(&mut self) -> &mut ExecCtx {
+        &mut self.door.exec_ctx
+    }
+
+    fn dispatch_call(&mut self, function: Function) -> Result<()> {
+        Err(anyhow!("door has no function {function:?}"))
+    }
+
+    fn safe_label(&mut self) -> Option<String> {
+        Some("door::closed".to_string())
+    }
+}
+
 pub struct Door {
-    exec_ctx: ExecCtx,
+    pub exec_ctx: ExecCtx,
     pub open_f: Fp16,
     pub blockers: HashSet<i32>,
 }
@@ -27,7 +75,7 @@ pub struct Door {
 impl Default for Door {
     fn default() -> Self {
         Self {
-            exec_ctx: ExecCtx::new("door::closed", &IMG_WL6).unwrap(),
+            exec_ctx: ExecCtx::new("door::closed", img_wl6()).unwrap(),
             open_f: FP16_ZERO,
             blockers: Default::default(),
         }
@@ -36,6 +84,12 @@ impl Default for Door {
 
 impl Door {
     pub fn update(&mut self, trigger: bool, blocked: bool, audio_service: &mut dyn AudioService) {
+        // a block waiting in WAIT holds the state
+        if let Some(block) = self.exec_ctx.suspended_block() {
+            self.exec_code(block, trigger, blocked, audio_service)
+                .expect("exec_code failed");
+            return;
+        }
         if self.exec_ctx.state.ticks <= 0 {
             self.exec_ctx.jump(self.exec_ctx.state.next).unwrap();
         }
@@ -59,54 +113,24 @@ impl Door {
         blocked: bool,
         audio_service: &mut dyn AudioService,
     ) -> Result<()> {
-        let mut env = opcode::Env::default();
-        let mut cursor = Cursor::new(&self.exec_ctx.image.code[code_offs as usize..]);
-        loop {
-            let state = opcode::exec(&mut cursor, &mut env).expect("error on bytecode exec");
-            match state {
-                opcode::Event::Stop => break,
-                opcode::Event::Call(function) => self.dispatch_call(function, trigger, blocked),
-                opcode::Event::Trap => match env.stack.pop() {
-                    Some(Value::U8(0)) => env.stack.push(self.open_f.into()),
-                    Some(Value::U8(1)) => match env.stack.pop() {
-                        Some(opcode::Value::I32(v)) => self.open_f.v = v,
-                        Some(x) => return Err(anyhow!("unhandled opcode::Value {x:?}")),
-                        None => return Err(anyhow!("stack underflow")),
-                    },
-                    Some(Value::U8(2)) => env.stack.push(Value::Bool(trigger)),
-                    Some(Value::U8(3)) => env.stack.push(Value::Bool(blocked)),
-                    Some(Value::U8(4)) => {
-                        if let Some(Value::U8(snd_id)) = env.stack.pop() {
-                            audio_service.play_sound(snd_id as i32);
-                        }
-                    }
-                    x => panic!("unexpected trap code {x:?}"),
-                },
-                opcode::Event::GoState => {
-                    match env.stack.pop() {
-                        Some(Value::I32(offs)) => {
-                            self.exec_ctx
-                                .jump(offs)
-                                .unwrap_or_else(|_| panic!("jump to {offs} failed."));
-                        }
-                        Some(x) => return Err(anyhow!("unhandled opcode::Value {x:?}")),
-                        None => return Err(anyhow!("stack underflow")),
-                    };
-                    break;
-                } // x => todo!("unhandled opcode::Event {x:?}"),
-            }
-        }
-        Ok(())
-    }
-    fn dispatch_call(&mut self, _function: Function, _trigger: bool, _blocked: bool) {
-        todo!()
+        let image = self.exec_ctx.image.clone();
+        run_block(
+            &image,
+            code_offs,
+            &mut DoorHost {
+                door: self,
+                trigger,
+                blocked,
+                audio_service,
+            },
+        )
     }
 }
 
 impl ms::Loadable for Door {
     fn read_from(r: &mut dyn std::io::Read) -> Result<Self> {
         let open_f = Fp16::read_from(r)?;
-        let exec_ctx = ExecCtx::read_from(r, &IMG_WL6)?;
+        let exec_ctx = ExecCtx::read_from(r, img_wl6())?;
 
         let num_blockers = r.readu32()?;
         let mut blockers = HashSet::new();
@@ -1,6 +1,77 @@
-use crate::{fp16::FP16_FRAC_64, prelude::*, thing_def::get_capabilities_by_name};
+use crate::{enums::EnemyTrap, fp16::FP16_FRAC_64, prelude::*};
 use anyhow::anyhow;
-use std::io::{Cursor, Read, Write};
+use state_bc::host::{run_block, Host, TrapHandler};
+use std::io::{Read, Write};
+
+/// Enemy plus the per-update inputs its bytecode needs
+struct EnemyHost<'a> {
+    enemy: &'a mut Enemy,
+    map: &'a mut Map,
+    things: &'a Things,
+    unique_id: usize,
+    player: &'a mut Player,
+    audio_service: &'a mut dyn AudioService,
+}
+
+impl TrapHandler for EnemyHost<'_> {
+    type Trap = EnemyTrap;
+
+    fn trap(&mut self, trap: EnemyTrap, env: &mut opcode::Env) -> Result<()> {
+        match trap {
+            // pops N, then N sound ids and plays one of them
+            EnemyTrap::PlayRandomSound => {
+                let Some(opcode::Value::U8(snd_num)) = env.stack.pop() else {
+                    return Err(anyhow!("failed to get sound num"));
+                };
+                if snd_num == 0 || env.stack.len() < snd_num as usize {
+                    return Err(anyhow!("stack underflow: not enough sound ids"));
+                }
+                let new_len = env.stack.len() - snd_num as usize;
+                let sound_choice = (randu8() % snd_num) as usize;
+                let opcode::Value::U8(snd_id) = env.stack[new_len + sound_choice] else {
+                    return Err(anyhow!("expected U8 as sound id"));
+                };
+                self.audio_service.play_sound(snd_id as i32);
+                env.stack.truncate(new_len);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Host for EnemyHost<'_> {
+    fn exec_ctx(&mut self) -> &mut ExecCtx {
+        &mut self.enemy.exec_ctx
+    }
+
+    fn dispatch_call(&mut self, function: Function) -> Result<()> {
+        self.enemy
+            .dispatch_call(function, self.map, self.things, self.unique_id, self.player);
+        Ok(())
+    }
+
+    /// KEEP IN SYNC with the `entry enemy.*` declarations in states/wl6.st
+    fn safe_label(&mut self) -> Option<String> {
+        let enemy = &mut *self.enemy;
+        let name = &enemy.enemy_type_name;
+        if enemy.health <= 0 || enemy.dead {
+            // skip the rest of the die states, the corpse must not look alive
+            enemy.dead = true;
+            return Some(format!("{name}::dead"));
+        }
+        // idle enemies keep waiting for the player, awake ones keep chasing it
+        let image = &enemy.exec_ctx.image;
+        let idle = image
+            .find_label_for_ptr(enemy.exec_ctx.ptr)
+            .is_some_and(|(label, _)| label.ends_with("::stand") || label.ends_with("::path"));
+        let stand = format!("{name}::stand");
+        if idle && image.get_state_offs_by_label(&stand).is_some() {
+            Some(stand)
+        } else {
+            Some(format!("{name}::chase"))
+        }
+    }
+}
 
 impl Enemy {
     fn check_player_sight(&mut self, things: &Things, map: &mut Map, _static_index: usize) -> bool {
@@ -195,7 +266,7 @@ impl Enemy {
                 self.direction = dir;
             }
         }
-        self.move_default(map, unique_id, FP16_FRAC_64);
+        self.move_default(map, unique_id, self.chase_speed());
     }
     fn think_path(&mut self, map: &mut Map, things: &Things, unique_id: usize) {
         if self.notify || self.check_player_sight(things, map, unique_id) {
@@ -208,7 +279,7 @@ impl Enemy {
             self.direction = self.try_update_pathdir(map).unwrap_or(self.direction);
             self.path_action = self.try_find_pathaction(map, things);
         }
-        self.move_default(map, unique_id, FP16_FRAC_128);
+        self.move_default(map, unique_id, self.path_speed());
     }
 
     fn think_stand(&mut self, map: &mut Map, things: &Things, unique_id: usize) {
@@ -233,7 +304,7 @@ impl Enemy {
             self.set_state("jump");
         }
 
-        self.move_default(map, unique_id, FP16_FRAC_64);
+        self.move_default(map, unique_id, self.chase_speed());
     }
 }
 
@@ -246,7 +317,7 @@ impl Enemy {
     fn action_shoot(
         &mut self,
         map: &mut Map,
-        _things: &Things,
+        things: &Things,
         _unique_id: usize,
         player: &mut Player,
     ) {
@@ -273,7 +344,7 @@ impl Enemy {
             let boost = 2 - dx.max(dy).min(2);
             let base_hitpoints = 7;
             let hitpoints = base_hitpoints + ((boost * 5) * (randu8() as i32)) / 255;
-            player.health -= hitpoints;
+            player.health -= things.thing_defs.difficulty.scale_damage(hitpoints);
         }
     }
 }
@@ -423,7 +494,7 @@ impl Enemy {
                         dist: FP16_ONE,
                         door_id: *door_id,
                     })
-                } else if get_capabilities_by_name(&self.enemy_type_name).can_open_doors {
+                } else if self.info().can_open_doors {
                     self.path_action = None;
                 }
             }
@@ -501,7 +572,7 @@ impl ms::Writable for PathAction {
 }
 
 pub struct Enemy {
-    exec_ctx: ExecCtx,
+    pub exec_ctx: ExecCtx,
     // enemy_type: EnemyType,
     enemy_type_name: String,
     direction: Direction,
@@ -515,7 +586,7 @@ pub struct Enemy {
 
 impl ms::Loadable for Enemy {
     fn read_from(r: &mut dyn Read) -> Result<Self> {
-        let exec_ctx = ExecCtx::read_from(r, &IMG_WL6)?;
+        let exec_ctx = ExecCtx::read_from(r, img_wl6())?;
         // let enemy_type = EnemyType::read_from(r)?;
         let enemy_type_name = String::read_from(r)?;
         let direction = Direction::read_from(r)?;
@@ -562,7 +633,6 @@ impl Enemy {
         self.exec_ctx
             .jump_label(&label)
             .unwrap_or_else(|err| panic!("failed to jump to state {label}: {err:?}"));
-        println!("state: {self:?}");
         self.dead = name == "dead";
     }
     pub fn dispatch_call(
@@ -593,45 +663,20 @@ impl Enemy {
         player: &mut Player,
         audio_service: &mut dyn AudioService,
     ) {
-        let mut env = opcode::Env::default();
-        let mut cursor = Cursor::new(&self.exec_ctx.image.code[code_offs as usize..]);
-        loop {
-            let state = opcode::exec(&mut cursor, &mut env).expect("error on bytecode exec");
-            match state {
-                opcode::Event::Stop => break,
-                opcode::Event::Call(function) => {
-                    self.dispatch_call(function, map, things, unique_id, player)
-                }
-                // opcode::Event::Load(_) => todo!(),
-                // opcode::Event::Store(_) => todo!(),
-                opcode::Event::Trap => match env.stack.pop() {
-                    Some(opcode::Value::U8(0)) => {
-                        let Some(opcode::Value::U8(snd_num)) = env.stack.pop() else {
-                            panic!("failed to get sound num")
-                        };
-
-                        if env.stack.len() < snd_num as usize {
-                            panic!("stack underflow: not enough sound ids");
-                        }
-                        let new_len = env.stack.len() - snd_num as usize;
-                        let sound_choice = (randu8() % snd_num) as usize;
-                        let opcode::Value::U8(snd_id) = env.stack[new_len + sound_choice] else {
-                            panic!("expected U8 as sound id")
-                        };
-                        audio_service.play_sound(snd_id as i32);
-                        env.stack.truncate(new_len);
-
-                        // Some(opcode::Value::U8(snd_id)) => {
-                        //     // println!("play sound {snd_id}");
-                        //     audio_service.play_sound(snd_id as i32);
-                        // }
-                        // x => panic!("invalid snd id {x:?}"),
-                    }
-                    x => panic!("unexpected stack top {x:?}"),
-                },
-                opcode::Event::GoState => todo!(),
-            }
-        }
+        let image = self.exec_ctx.image.clone();
+        run_block(
+            &image,
+            code_offs,
+            &mut EnemyHost {
+                enemy: self,
+                map,
+                things,
+                unique_id,
+                player,
+                audio_service,
+            },
+        )
+        .expect("error on bytecode exec");
     }
     pub fn update(
         &mut self,
@@ -645,6 +690,18 @@ impl Enemy {
         // // this is different from wolf3d where actions execute on state exit (don't understand why...)
         // if let Some(action_offs) = self.exec_ctx.state.take_action_offs() {}
 
+        // a block waiting in WAIT holds the state, only it runs until it finishes
+        if let Some(block) = self.exec_ctx.suspended_block() {
+            self.exec_code(block, map, things, unique_id, player, audio_service);
+            let action_finished = self.exec_ctx.suspended.is_none()
+                && block == self.exec_ctx.state.action_offs
+                && self.exec_ctx.state.ticks <= 0;
+            if action_finished {
+                self.exec_ctx.jump(self.exec_ctx.state.next).unwrap();
+            }
+            return;
+        }
+
         if self.exec_ctx.state.ticks <= 0 {
             self.exec_code(
                 self.exec_ctx.state.action_offs,
@@ -654,6 +711,9 @@ impl Enemy {
                 player,
                 audio_service,
             );
+            if self.exec_ctx.suspended.is_some() {
+                return;
+            }
             self.exec_ctx.jump(self.exec_ctx.state.next).unwrap();
         }
 
@@ -668,18 +728,56 @@ impl Enemy {
 
         // self.states[self.cur].2();
 
-        self.exec_ctx.state.ticks -= 1;
+        if self.exec_ctx.suspended.is_none() {
+            self.exec_ctx.state.ticks -= 1;
+        }
     }
 
-    pub fn hit(&mut self, hitpoints: i32) {
+    /// Returns the points scored if the hit was fatal
+    pub fn hit(&mut self, hitpoints: i32) -> i32 {
         self.health -= hitpoints;
 
+        let (points, pain) = (self.info().points, self.info().pain);
         if self.health <= 0 {
             self.set_state("die");
-        } else if self.health % 2 == 0 {
-            self.set_state("pain1");
-        } else {
-            self.set_state("pain2");
+            return points;
+        }
+        match pain {
+            PainBehavior::Alternate if self.health % 2 == 0 => self.set_state("pain1"),
+            PainBehavior::Alternate => self.set_state("pain2"),
+            PainBehavior::Never => (),
+        }
+        0
+    }
+    /// Enemy state for the debugger overlay
+    pub fn debug_lines(&self) -> Vec<String> {
+        vec![
+            format!("ticks: {}", self.exec_ctx.state.ticks),
+            format!(
+                "waiting: {:?}",
+                self.exec_ctx.suspended.as_ref().map(|s| s.ticks)
+            ),
+            format!("path: {:?}", self.path_action),
+            format!("health: {}", self.health),
+            format!("direction: {:?}", self.direction),
+        ]
+    }
+    /// Properties of the enemy type from the spawn block
+    pub fn info(&self) -> &EnemyInfo {
+        self.exec_ctx
+            .image
+            .spawn_infos
+            .find_enemy_info(&self.enemy_type_name)
+            .unwrap_or_else(|| panic!("no spawn block for {}", self.enemy_type_name))
+    }
+    fn path_speed(&self) -> Fp16 {
+        Fp16 {
+            v: self.info().path_speed,
+        }
+    }
+    fn chase_speed(&self) -> Fp16 {
+        Fp16 {
+            v: self.info().chase_speed,
         }
     }
     pub fn get_sprite(&self) -> (SpriteIndex, Fp16, Fp16) {
@@ -691,16 +789,19 @@ impl Enemy {
         (id, self.x, self.y)
     }
 
-    pub fn spawn(enemy_spawn_info: &EnemySpawnInfo, thing_def: &ThingDef) -> Enemy {
-        let exec_ctx = ExecCtx::new(&enemy_spawn_info.state, &IMG_WL6).unwrap();
-
-        // FIXME: hack. find better solution for 'enemy type name'
-        let enemy_type_name = enemy_spawn_info
-            .state
-            .split("::")
-            .next()
-            .unwrap()
-            .to_string();
+    pub fn spawn(
+        enemy_spawn_info: &EnemySpawnInfo,
+        thing_def: &ThingDef,
+        difficulty: Difficulty,
+    ) -> Enemy {
+        let exec_ctx = ExecCtx::new(&enemy_spawn_info.state, img_wl6()).unwrap();
+        let enemy_type_name = enemy_spawn_info.enemy.clone();
+        let health = exec_ctx
+            .image
+            .spawn_infos
+            .find_enemy_info(&enemy_type_name)
+            .unwrap_or_else(|| panic!("no spawn block for {enemy_type_name}"))
+            .health[difficulty.index()];
 
         Enemy {
             direction: enemy_spawn_info.direction,
@@ -708,7 +809,7 @@ impl Enemy {
             exec_ctx,
             // enemy_type,
             enemy_type_name,
-            health: 25,
+            health,
             x: thing_def.x,
             y: thing_def.y,
             notify: false,
@@ -7,6 +7,8 @@ pub fn draw_status_bar<D: Draw + ?Sized>(buffer: &mut D, mainloop: &Mainloop) {
     y += 8;
     draw_string8x8(&format!("ammo: {}", player.weapon.ammo), buffer, 0, y);
     y += 8;
+    draw_string8x8(&format!("score: {}", player.score), buffer, 0, y);
+    y += 8;
     draw_string8x8(
         &format!("weapon: {:?}", player.weapon.selected_weapon),
         buffer,
@@ -5,6 +5,7 @@ use wl6::DigiSounds;
 use wl6::{ChunkProvider, SpritePosts, VswapFile};
 
 pub mod block_map;
+pub mod debugger;
 pub mod door;
 pub mod draw;
 pub mod enemy;
@@ -37,6 +38,7 @@ pub mod prelude {
         door::Door,
         draw::Draw,
         enemy::Enemy,
+        enums::{Sound, Sprite},
         fa::{
             fa_cos, fa_cot, fa_fix_angle, fa_sin, fa_tan, FA_FRAC_PI_2, FA_PI, FA_PI_FRAC_PI_2,
             FA_SCALEF, FA_STEPS, FA_TAU, PIS_IN_180, QUADRANT_1, QUADRANT_2, QUADRANT_3,
@@ -47,7 +49,7 @@ pub mod prelude {
             Fp16, FP16_F, FP16_FOUR, FP16_FRAC_128, FP16_FRAC_64, FP16_HALF, FP16_ONE, FP16_SCALE,
             FP16_ZERO,
         },
-        hud,
+        hud, img_wl6,
         mainloop::{AudioService, InputState, Mainloop, SpawnInfo},
         map::{DoorAction, DoorState, Map, PushwallAction, PushwallState},
         map_def::{bresenham_trace, DoorType, MapDef, MapTile, PlaneOrientation, MAP_SIZE},
@@ -56,14 +58,18 @@ pub mod prelude {
         player::{Player, PlayerVel},
         randu8,
         render::{self, COL_ANGLE},
+        set_img_wl6,
         sprite::{self, Directionality, SpriteDef, SpriteIndex},
         state_bc::opcode,
-        state_bc::{Direction, EnemySpawnInfo, ExecCtx, Function, StateBc},
+        state_bc::{
+            Direction, EnemyInfo, EnemySpawnInfo, ExecCtx, ExecImage, Function, PainBehavior,
+            StateBc,
+        },
         thing::{Actor, Collectible, Item, Thing, Things},
-        thing_def::{ThingDef, ThingDefs, ThingType},
+        thing_def::{Difficulty, ThingDef, ThingDefs, ThingType},
         voxel::Voxel,
         weapon::{Weapon, WeaponType},
-        Resources, Result, HALF_HEIGHT, HEIGHT, IMG_WL6, MID, VIEW_HEIGHT, WIDTH,
+        Resources, Result, HALF_HEIGHT, HEIGHT, MID, VIEW_HEIGHT, WIDTH,
     };
 }
 
@@ -193,18 +199,32 @@ impl Resources {
 
 pub mod palette;
 
+/// Enums of the state sources, generated by build.rs
+pub mod enums {
+    include!("out.img.rs");
+}
+
 const WL6_IMAGE: &[u8] = include_bytes!("out.img");
 // const WL6_SPAWN_INFO: &[u8] = include_bytes!("out.spawn");
 
 lazy_static! {
-    pub static ref IMG_WL6: ExecImage = ExecImage::from_bytes(WL6_IMAGE).unwrap();
     // pub static ref SPAWN_INFO_WL6: SpawnInfos = SpawnInfos::from_bytes(WL6_SPAWN_INFO).unwrap();
     // pub static ref RNG: std::sync::Mutex<oorandom::Rand32> = std::sync::Mutex::new(oorandom::Rand32::new(4711));
 }
 
 thread_local! {
+    /// image new ExecCtxs are created from. Starts as the image built by build.rs and can be
+    /// replaced at runtime, see `Mainloop::reload_image`.
+    static IMG_WL6: std::cell::RefCell<std::rc::Rc<ExecImage>> =
+        std::cell::RefCell::new(std::rc::Rc::new(ExecImage::from_bytes(WL6_IMAGE).unwrap()));
     pub static RNG: std::cell::RefCell<oorandom::Rand32>  = std::cell::RefCell::new(oorandom::Rand32::new(4711));
 }
+pub fn img_wl6() -> std::rc::Rc<ExecImage> {
+    IMG_WL6.with(|img| img.borrow().clone())
+}
+pub fn set_img_wl6(image: std::rc::Rc<ExecImage>) {
+    IMG_WL6.with(|img| *img.borrow_mut() = image);
+}
 pub fn randu8() -> u8 {
     let v = RNG.with(|r| r.borrow_mut().rand_u32().to_ne_bytes());
     v[0] ^ v[1] ^ v[2] ^ v[3] // TODO: is this smart?
@@ -24,10 +24,15 @@ fn input_state_from_sdl_events(events: &mut EventPump) -> InputState {
                 Scancode::F1 => input_state.restart = true,
                 Scancode::F2 => input_state.prev_level = true,
                 Scancode::F3 => input_state.next_level = true,
+                Scancode::F4 => input_state.reload_image = true,
                 Scancode::F5 => input_state.save = true,
                 Scancode::F6 => input_state.load = true,
                 Scancode::F7 => input_state.toggle_stop_the_world = true,
+                Scancode::F8 => input_state.dump_trace = true,
                 Scancode::F9 => input_state.toggle_render_alternative = true, // can be used e.g. to toggle between different draw impls at runtime
+                Scancode::F10 => input_state.debug_select = true,
+                Scancode::F11 => input_state.debug_step = true,
+                Scancode::F12 => input_state.debug_breakpoint = true,
                 Scancode::Tab => input_state.toggle_automap = true,
                 Scancode::Grave => input_state.toggle_mouse_grab = true,
                 Scancode::Num1 => input_state.select_weapon = Some(1),
@@ -195,7 +200,18 @@ fn raycast_mainloop(
     let resources = Resources::load_wl6("vswap.wl6");
     let mut sound_chunks = SdlSoundChunks::new(&resources);
     let mut maps_file = wl6::MapsFile::open("maphead.wl6", "gamemaps.wl6");
-    let mut mainloop = Mainloop::spawn(SpawnInfo::StartLevel(0, None), &mut maps_file);
+    let difficulty = std::env::args()
+        .skip_while(|arg| arg != "--skill")
+        .nth(1)
+        .map(|name| {
+            Difficulty::from_name(&name).unwrap_or_else(|| panic!("unknown skill level {name}"))
+        })
+        .unwrap_or_default();
+    if std::env::args().any(|arg| arg == "--trace") {
+        state_bc::trace::enable(4096);
+        state_bc::trace::install_panic_hook();
+    }
+    let mut mainloop = Mainloop::spawn(SpawnInfo::StartLevel(0, difficulty, None), &mut maps_file);
     let mut mouse_grabbed = false;
     let mut initial_ungrabbed = true;
     let mut last_misc_selection = 0;
@@ -206,6 +222,13 @@ fn raycast_mainloop(
         if input_state.quit {
             break;
         }
+        if input_state.load {
+            if let Err(err) = mainloop::check_savegame(mainloop::SAVEGAME, &mut maps_file) {
+                println!("{err:#}");
+                mainloop.show_message(format!("load failed: {}", err.root_cause()));
+                input_state.load = false;
+            }
+        }
         mainloop.use_mouse_move = mouse_grabbed;
         mainloop.run(&input_state, &mut buffer, &resources, &mut sound_chunks);
         sound_chunks.update();
@@ -233,7 +256,10 @@ fn voxel_mainloop(
     let voxel_res = voxel::res::VoxelRes::from_dir("comanche2").unwrap();
     // let voxel_res = voxel::res::VoxelRes::from_dir("comanche").unwrap();
 
-    let mut voxel = Voxel::spawn(SpawnInfo::StartLevel(0, None), &voxel_res);
+    let mut voxel = Voxel::spawn(
+        SpawnInfo::StartLevel(0, Default::default(), None),
+        &voxel_res,
+    );
     let mut mouse_grabbed = false;
     let mut initial_ungrabbed = true;
     let mut last_misc_selection = 0;
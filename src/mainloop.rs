@@ -1,7 +1,9 @@
-use std::time::Instant;
+use std::{rc::Rc, time::Instant};
 
 // use minifb::{Key, KeyRepeat, Window, WindowOptions};
 use crate::{
+    debugger::Debugger,
+    enums::ENUM_NAMES,
     ms::{Loadable, Writable},
     prelude::*,
     sprite::SpriteSceenSetup,
@@ -9,7 +11,121 @@ use crate::{
     Resources,
 };
 
-include!("out.img.enums");
+pub const SAVEGAME: &str = "save.bin";
+/// Magic number at the start of every savegame
+pub const SAVEGAME_MAGIC: [u8; 4] = *b"WLSG";
+/// Version of the savegame layout. Bump it on any change to what is saved (see
+/// `Mainloop::run`), older savegames are rejected.
+pub const SAVEGAME_VERSION: u32 = 1;
+
+/// Header in front of every savegame
+struct SavegameHeader {
+    version: u32,
+    /// `ImageHeader::image_hash` of the state image the game was saved with
+    image_hash: u64,
+}
+
+impl Loadable for SavegameHeader {
+    fn read_from(r: &mut dyn std::io::Read) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != SAVEGAME_MAGIC {
+            return Err(anyhow::anyhow!(
+                "not a savegame or from an older version (bad magic {magic:02x?})"
+            ));
+        }
+        Ok(SavegameHeader {
+            version: r.readu32()?,
+            image_hash: r.readu64()?,
+        })
+    }
+}
+
+impl Writable for SavegameHeader {
+    fn write(&self, w: &mut dyn std::io::Write) -> Result<()> {
+        w.write_all(&SAVEGAME_MAGIC)?;
+        w.writeu32(self.version)?;
+        w.writeu64(self.image_hash)?;
+        Ok(())
+    }
+}
+
+struct Savegame {
+    level_id: i32,
+    player: Player,
+    map: Map,
+    things: Things,
+}
+
+/// Read the savegame at `path`. The static map data is reused if it belongs to the saved level,
+/// otherwise it is loaded from `maps`.
+fn read_savegame(
+    path: &str,
+    maps: &mut MapsFile,
+    static_map_data: Option<StaticMapData>,
+) -> Result<Savegame> {
+    let mut f = std::fs::File::open(path)
+        .map_err(|err| anyhow::anyhow!("failed to open savegame {path}: {err}"))?;
+    let header = SavegameHeader::read_from(&mut f)?;
+    if header.version != SAVEGAME_VERSION {
+        return Err(anyhow::anyhow!(
+            "savegame version {} is not supported (expected {SAVEGAME_VERSION})",
+            header.version
+        ));
+    }
+    let level_id = f.readi32()?;
+    if !(0..maps.num_maps()).contains(&level_id) {
+        return Err(anyhow::anyhow!("savegame has a bad level id {level_id}"));
+    }
+    let difficulty = Difficulty::read_from(&mut f)?;
+    let player = Player::read_from(&mut f)?;
+    let (map, things) = match static_map_data {
+        Some(StaticMapData {
+            map_def,
+            thing_defs,
+            level_id: y,
+        }) if level_id == y && thing_defs.difficulty == difficulty => {
+            println!("load savegame. re-using static map data");
+            let map = Map::read_and_wrap(&mut f, map_def)?;
+            (map, Things::read_from(&mut f, thing_defs)?)
+        }
+        _ => {
+            println!(
+                "load savegame. load static map data {}",
+                maps.get_map_name(level_id)
+            );
+            let (plane0, plane1) = maps.get_map_planes(level_id);
+            let map = Map::read_and_wrap(&mut f, MapDef::from_map_planes(&plane0, &plane1))?;
+            let thing_defs = ThingDefs::from_map_plane(&plane1, difficulty);
+            (map, Things::read_from(&mut f, thing_defs)?)
+        }
+    };
+    Ok(Savegame {
+        level_id,
+        player,
+        map,
+        things,
+    })
+}
+
+/// Check that the savegame at `path` can be loaded by reading all of it, so a broken savegame
+/// is reported before the running game is torn down for `SpawnInfo::LoadSavegame`. Saves from a
+/// different image are accepted: actors store their state by label, which is re-resolved against
+/// the current image, and fail here if a label is gone.
+pub fn check_savegame(path: &str, maps: &mut MapsFile) -> Result<()> {
+    let mut f = std::fs::File::open(path)
+        .map_err(|err| anyhow::anyhow!("failed to open savegame {path}: {err}"))?;
+    let image_hash = SavegameHeader::read_from(&mut f)?.image_hash;
+    let current = img_wl6().header.image_hash;
+    if image_hash != current {
+        println!(
+            "savegame {path} was written with a different state image ({image_hash:016x}, running {current:016x}), re-resolving states by label"
+        );
+    }
+    read_savegame(path, maps, None)
+        .map(|_| ())
+        .map_err(|err| err.context(format!("failed to load savegame {path}")))
+}
 
 pub struct StaticMapData {
     pub level_id: i32,
@@ -18,7 +134,7 @@ pub struct StaticMapData {
 }
 
 pub enum SpawnInfo {
-    StartLevel(i32, Option<StaticMapData>),
+    StartLevel(i32, Difficulty, Option<StaticMapData>),
     LoadSavegame(Option<StaticMapData>),
 }
 
@@ -37,6 +153,11 @@ pub struct InputState {
     pub toggle_automap: bool,
     pub toggle_stop_the_world: bool,
     pub toggle_mouse_grab: bool,
+    pub reload_image: bool,
+    pub dump_trace: bool,
+    pub debug_select: bool,
+    pub debug_step: bool,
+    pub debug_breakpoint: bool,
     pub select_weapon: Option<i32>,
 
     // press state
@@ -77,10 +198,18 @@ pub struct Mainloop {
     player_vel: PlayerVel,
     automap: bool,
     stop_the_world_mode: bool,
+    debugger: Debugger,
     pub use_mouse_move: bool, // needs to be managed from outside
+    /// message shown to the player and the number of frames it stays on screen
+    pub message: Option<(String, i32)>,
 }
 
 impl Mainloop {
+    /// Show `message` above the status bar for a few seconds
+    pub fn show_message(&mut self, message: String) {
+        self.message = Some((message, 3 * 60));
+    }
+
     pub fn spawn(spawn: SpawnInfo, maps: &mut MapsFile) -> Mainloop {
         let map;
         let level_id;
@@ -88,13 +217,13 @@ impl Mainloop {
         let things;
 
         match spawn {
-            SpawnInfo::StartLevel(id, existing_static_map_data) => {
+            SpawnInfo::StartLevel(id, difficulty, existing_static_map_data) => {
                 match existing_static_map_data {
                     Some(StaticMapData {
                         map_def,
                         thing_defs,
                         level_id: y,
-                    }) if id == y => {
+                    }) if id == y && thing_defs.difficulty == difficulty => {
                         println!("starting level. re-using static map data");
                         map = Map::wrap(map_def);
                         things = Things::from_thing_defs(thing_defs);
@@ -108,7 +237,8 @@ impl Mainloop {
                         let (plane0, plane1) = maps.get_map_planes(id);
 
                         map = Map::wrap(MapDef::from_map_planes(&plane0, &plane1));
-                        things = Things::from_thing_defs(ThingDefs::from_map_plane(&plane1));
+                        things =
+                            Things::from_thing_defs(ThingDefs::from_map_plane(&plane1, difficulty));
                         level_id = id;
                     }
                 }
@@ -125,40 +255,18 @@ impl Mainloop {
                         shoot_timeout: 0,
                         weapon: Default::default(), // TODO
                         health: 100,
+                        score: 0,
                     })
                     .unwrap_or_default();
             }
 
             SpawnInfo::LoadSavegame(existing_static_map_data) => {
-                let mut f = std::fs::File::open("save.bin").unwrap();
-                level_id = f.readi32().unwrap();
-
-                player = Player::read_from(&mut f).expect("failed to load Player from savegame");
-                match existing_static_map_data {
-                    Some(StaticMapData {
-                        map_def,
-                        thing_defs,
-                        level_id: y,
-                    }) if level_id == y => {
-                        println!("load savegame. re-using static map data");
-                        map = Map::read_and_wrap(&mut f, map_def)
-                            .expect("failed to load MapDynamic from savegame");
-                        things = Things::read_from(&mut f, thing_defs)
-                            .expect("failed to load Things from savegame");
-                    }
-                    _ => {
-                        println!(
-                            "load savegame. load static map data {}",
-                            maps.get_map_name(level_id)
-                        );
-                        let (plane0, plane1) = maps.get_map_planes(level_id);
-
-                        map = Map::read_and_wrap(&mut f, MapDef::from_map_planes(&plane0, &plane1))
-                            .expect("failed to load MapDynamic from savegame");
-                        things = Things::read_from(&mut f, ThingDefs::from_map_plane(&plane1))
-                            .expect("failed to load Things from savegame");
-                    }
-                }
+                let savegame = read_savegame(SAVEGAME, maps, existing_static_map_data)
+                    .expect("savegame was verified by check_savegame");
+                level_id = savegame.level_id;
+                player = savegame.player;
+                map = savegame.map;
+                things = savegame.things;
             }
         }
 
@@ -177,7 +285,9 @@ impl Mainloop {
             player_vel,
             automap: false,
             stop_the_world_mode: false,
+            debugger: Default::default(),
             use_mouse_move: false,
+            message: None,
         }
     }
 
@@ -233,6 +343,19 @@ impl Mainloop {
         }
 
         self.stop_the_world_mode ^= input_events.toggle_stop_the_world;
+        if input_events.reload_image {
+            self.reload_image();
+        }
+        if input_events.dump_trace {
+            state_bc::trace::with_tracer(|tracer| {
+                let mut stdout = std::io::stdout().lock();
+                tracer.dump(&mut stdout).expect("failed to write trace");
+                tracer
+                    .report(&img_wl6(), &mut stdout)
+                    .expect("failed to write trace report");
+            })
+            .unwrap_or_else(|| println!("tracing is off, start with --trace"));
+        }
         let fast_forward = input_events.fast_forward_mode;
 
         self.player.shoot = input_events.shoot;
@@ -249,12 +372,34 @@ impl Mainloop {
             println!("notify room {room_id:x}");
         }
 
+        let frozen = self.debugger.selected;
         for _ in 0..num_ticks {
             self.things.player_x = self.player.x.get_int();
             self.things.player_y = self.player.y.get_int();
-            self.things
-                .update(&mut self.player, &mut self.map_dynamic, audio_service);
+            let snapshot = self.debugger.snapshot(&self.things);
+            self.things.update(
+                &mut self.player,
+                &mut self.map_dynamic,
+                audio_service,
+                |unique_id| Some(unique_id) != frozen,
+            );
             self.map_dynamic.update(&self.player, audio_service);
+            state_bc::trace::end_tick();
+            if self.debugger.check_breakpoints(&snapshot, &self.things) {
+                self.stop_the_world_mode = true;
+                break;
+            }
+        }
+        if let (true, Some(selected)) = (input_events.debug_step, self.debugger.selected) {
+            self.things.update(
+                &mut self.player,
+                &mut self.map_dynamic,
+                audio_service,
+                |unique_id| unique_id == selected,
+            );
+        }
+        if input_events.debug_breakpoint {
+            self.debugger.toggle_breakpoint(&self.things);
         }
         self.player.apply_vel(
             &self.player_vel,
@@ -319,35 +464,11 @@ impl Mainloop {
                 self.map_dynamic.notifications.insert(room_id);
             }
 
-            for sprite in &sprite_screen_setup {
-                const WIDTH_HALF: i32 = (WIDTH as i32) / 2;
-
-                let zbound = zbuffer[WIDTH_HALF as usize];
-                if !(self.things.things[sprite.owner].actor.can_be_shot() && sprite.z < zbound) {
-                    continue;
-                }
-                // FIXME: this is quite redundant with the calculations in sprite drawings. Maybe store the bounds in the screenspace setup struct.
-                const C: i32 = MID;
-                let offs = if sprite.z > FP16_ZERO {
-                    (C << FP16_SCALE) / sprite.z.v
-                } else {
-                    C
-                };
-                if !((sprite.screen_x + offs >= 0) && (sprite.screen_x - offs < WIDTH as i32)) {
-                    continue;
-                }
-
-                // println!(
-                //     "offs: {offs} {} {:?}",
-                //     sprite.screen_x, self.things.things[sprite.owner].actor
-                // );
-                let offs_scale = 2; // fixme: general fettgesicht is probably wider...
-                let min = (WIDTH as i32 / 2) - offs / offs_scale;
-                let max = (WIDTH as i32 / 2) + offs / offs_scale;
-                if (min..max).contains(&sprite.screen_x) {
-                    hit_thing = Some(sprite.owner);
-                }
-            }
+            hit_thing = self.thing_under_crosshair(&sprite_screen_setup, &zbuffer);
+        }
+        if input_events.debug_select {
+            let thing = self.thing_under_crosshair(&sprite_screen_setup, &zbuffer);
+            self.debugger.toggle_select(thing);
         }
         sprite_screen_setup.push(self.player.weapon.get_sprite());
 
@@ -358,11 +479,7 @@ impl Mainloop {
                 id: input_events.misc_selection,
                 owner: 0,
             });
-            let name = ENUM_NAMES
-                .iter()
-                .find(|(_, id)| *id == input_events.misc_selection)
-                .unwrap()
-                .0;
+            let name = Sprite::from_id(input_events.misc_selection).map_or("?", Sprite::name);
 
             draw_string8x8(name, &mut buffer[..], 100, 160);
         }
@@ -385,6 +502,14 @@ impl Mainloop {
 
         // draw_string8x8("Get Psyched!", &mut buffer[..], 100, 160);
         hud::draw_status_bar(&mut buffer[..], self);
+        if let Some((message, frames)) = &mut self.message {
+            draw_string8x8(message, &mut buffer[..], 0, VIEW_HEIGHT - 8);
+            *frames -= 1;
+            if *frames <= 0 {
+                self.message = None;
+            }
+        }
+        self.debugger.draw(&self.things, &mut buffer[..]);
 
         if let Some(hit_thing) = hit_thing {
             if let Some((x, y)) = &self.things.things[hit_thing].actor.get_pos() {
@@ -394,7 +519,7 @@ impl Mainloop {
                 let base_hitpoints = 7;
                 let hitpoints = base_hitpoints + ((boost * 7) * (randu8() as u32)) / 255;
                 println!("hit: {}", hitpoints - base_hitpoints);
-                self.things.things[hit_thing].actor.shoot(hitpoints as i32);
+                self.player.score += self.things.things[hit_thing].actor.shoot(hitpoints as i32);
             }
         }
 
@@ -408,8 +533,19 @@ impl Mainloop {
         // );
 
         if input_events.save {
-            let mut f = std::fs::File::create("save.bin").unwrap();
+            let mut f = std::fs::File::create(SAVEGAME).unwrap();
+            SavegameHeader {
+                version: SAVEGAME_VERSION,
+                image_hash: img_wl6().header.image_hash,
+            }
+            .write(&mut f)
+            .expect("failed to write savegame header");
             f.writei32(self.level_id).unwrap();
+            self.things
+                .thing_defs
+                .difficulty
+                .write(&mut f)
+                .expect("failed to write Difficulty to savegame");
             self.player
                 .write(&mut f)
                 .expect("failed to write Player to savegame");
@@ -422,10 +558,104 @@ impl Mainloop {
         }
     }
 
+    /// The shootable thing in the center of the screen, the last one drawn if several overlap
+    fn thing_under_crosshair(
+        &self,
+        sprite_screen_setup: &[SpriteSceenSetup],
+        zbuffer: &[Fp16],
+    ) -> Option<usize> {
+        let mut hit_thing = None;
+        for sprite in sprite_screen_setup {
+            const WIDTH_HALF: i32 = (WIDTH as i32) / 2;
+
+            let zbound = zbuffer[WIDTH_HALF as usize];
+            if !(self.things.things[sprite.owner].actor.can_be_shot() && sprite.z < zbound) {
+                continue;
+            }
+            // FIXME: this is quite redundant with the calculations in sprite drawings. Maybe store the bounds in the screenspace setup struct.
+            const C: i32 = MID;
+            let offs = if sprite.z > FP16_ZERO {
+                (C << FP16_SCALE) / sprite.z.v
+            } else {
+                C
+            };
+            if !((sprite.screen_x + offs >= 0) && (sprite.screen_x - offs < WIDTH as i32)) {
+                continue;
+            }
+
+            // println!(
+            //     "offs: {offs} {} {:?}",
+            //     sprite.screen_x, self.things.things[sprite.owner].actor
+            // );
+            let offs_scale = 2; // fixme: general fettgesicht is probably wider...
+            let min = (WIDTH as i32 / 2) - offs / offs_scale;
+            let max = (WIDTH as i32 / 2) + offs / offs_scale;
+            if (min..max).contains(&sprite.screen_x) {
+                hit_thing = Some(sprite.owner);
+            }
+        }
+        hit_thing
+    }
+
+    /// Recompile the state sources and move all ExecCtxs over to the new image. Actors whose
+    /// current state has no counterpart in the new image keep running on the old one. The enums
+    /// (`ENUM_NAMES`) and native functions are compiled into the game and can't change, an image
+    /// with different enums is rejected.
+    fn reload_image(&mut self) {
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/states/wl6.st");
+        let output = match state_bc::compiler::compile_file(source) {
+            Ok(output) => output,
+            Err(diagnostics) => {
+                println!("{diagnostics}");
+                return;
+            }
+        };
+        let image = match output.exec_image() {
+            Ok(image) => Rc::new(image),
+            Err(err) => {
+                println!("failed to load reloaded image: {err:#}");
+                return;
+            }
+        };
+        if image.header.enum_hash != state_bc::enum_hash(ENUM_NAMES) {
+            println!("not reloading {source}: the enums changed, this needs a rebuild");
+            return;
+        }
+        set_img_wl6(image.clone());
+
+        let enemy_ctxs = self
+            .things
+            .things
+            .iter_mut()
+            .filter_map(|thing| match &mut thing.actor {
+                Actor::Enemy { enemy } => Some(&mut enemy.exec_ctx),
+                _ => None,
+            });
+        let door_ctxs = self
+            .map_dynamic
+            .door_states
+            .iter_mut()
+            .map(|door| &mut door.exec_ctx);
+        let (mut reloaded, mut kept) = (0, 0);
+        for exec_ctx in enemy_ctxs
+            .chain(door_ctxs)
+            .chain([&mut self.player.weapon.exec_ctx])
+        {
+            if exec_ctx.reload(&image) {
+                reloaded += 1;
+            } else {
+                kept += 1;
+            }
+        }
+        println!("reloaded {source}: {reloaded} actors moved, {kept} left on the old image");
+    }
+
     pub fn deconstruct(self, input_events: &InputState) -> SpawnInfo {
+        let difficulty = self.things.thing_defs.difficulty;
         if input_events.prev_level && self.level_id > 0 {
             SpawnInfo::StartLevel(
                 self.level_id - 1,
+                difficulty,
                 Some(StaticMapData {
                     level_id: self.level_id,
                     map_def: self.map_dynamic.release(),
@@ -435,6 +665,7 @@ impl Mainloop {
         } else if input_events.next_level && self.level_id < 59 {
             SpawnInfo::StartLevel(
                 self.level_id + 1,
+                difficulty,
                 Some(StaticMapData {
                     level_id: self.level_id,
                     map_def: self.map_dynamic.release(),
@@ -455,6 +686,7 @@ impl Mainloop {
 
             SpawnInfo::StartLevel(
                 self.level_id,
+                difficulty,
                 Some(StaticMapData {
                     level_id: self.level_id,
                     map_def: self.map_dynamic.release(),
@@ -0,0 +1,2062 @@
+// generated by the state compiler, do not edit
+
+pub const ENUM_NAMES: [(&str, i32); 487] = [
+    ("ALP_DEAD", 334),
+    ("ALP_DIE1", 329),
+    ("ALP_DIE2", 330),
+    ("ALP_DIE3", 331),
+    ("ALP_DIE4", 332),
+    ("ALP_DIE5", 333),
+    ("ALP_FIRE1", 327),
+    ("ALP_FIRE2", 328),
+    ("ALP_SHOOT", 326),
+    ("ALP_W1", 322),
+    ("ALP_W2", 323),
+    ("ALP_W3", 324),
+    ("ALP_W4", 325),
+    ("BLINKY_W1", 289),
+    ("BLINKY_W2", 290),
+    ("BLUE_DEAD", 184),
+    ("BLUE_DIE_1", 180),
+    ("BLUE_DIE_2", 181),
+    ("BLUE_DIE_3", 182),
+    ("BLUE_PAIN_1", 179),
+    ("BLUE_PAIN_2", 183),
+    ("BLUE_SHOOT1", 185),
+    ("BLUE_SHOOT2", 186),
+    ("BLUE_SHOOT3", 187),
+    ("BLUE_S_1", 139),
+    ("BLUE_S_2", 140),
+    ("BLUE_S_3", 141),
+    ("BLUE_S_4", 142),
+    ("BLUE_S_5", 143),
+    ("BLUE_S_6", 144),
+    ("BLUE_S_7", 145),
+    ("BLUE_S_8", 146),
+    ("BLUE_W1_1", 147),
+    ("BLUE_W1_2", 148),
+    ("BLUE_W1_3", 149),
+    ("BLUE_W1_4", 150),
+    ("BLUE_W1_5", 151),
+    ("BLUE_W1_6", 152),
+    ("BLUE_W1_7", 153),
+    ("BLUE_W1_8", 154),
+    ("BLUE_W2_1", 155),
+    ("BLUE_W2_2", 156),
+    ("BLUE_W2_3", 157),
+    ("BLUE_W2_4", 158),
+    ("BLUE_W2_5", 159),
+    ("BLUE_W2_6", 160),
+    ("BLUE_W2_7", 161),
+    ("BLUE_W2_8", 162),
+    ("BLUE_W3_1", 163),
+    ("BLUE_W3_2", 164),
+    ("BLUE_W3_3", 165),
+    ("BLUE_W3_4", 166),
+    ("BLUE_W3_5", 167),
+    ("BLUE_W3_6", 168),
+    ("BLUE_W3_7", 169),
+    ("BLUE_W3_8", 170),
+    ("BLUE_W4_1", 171),
+    ("BLUE_W4_2", 172),
+    ("BLUE_W4_3", 173),
+    ("BLUE_W4_4", 174),
+    ("BLUE_W4_5", 175),
+    ("BLUE_W4_6", 176),
+    ("BLUE_W4_7", 177),
+    ("BLUE_W4_8", 178),
+    ("BOOM_1", 383),
+    ("BOOM_2", 384),
+    ("BOOM_3", 385),
+    ("BOSS_DEAD", 304),
+    ("BOSS_DIE1", 305),
+    ("BOSS_DIE2", 306),
+    ("BOSS_DIE3", 307),
+    ("BOSS_SHOOT1", 301),
+    ("BOSS_SHOOT2", 302),
+    ("BOSS_SHOOT3", 303),
+    ("BOSS_W1", 297),
+    ("BOSS_W2", 298),
+    ("BOSS_W3", 299),
+    ("BOSS_W4", 300),
+    ("BROWN_DEAD", 96),
+    ("BROWN_DIE_1", 92),
+    ("BROWN_DIE_2", 93),
+    ("BROWN_DIE_3", 94),
+    ("BROWN_PAIN_1", 91),
+    ("BROWN_PAIN_2", 95),
+    ("BROWN_SHOOT1", 97),
+    ("BROWN_SHOOT2", 98),
+    ("BROWN_SHOOT3", 99),
+    ("BROWN_S_1", 51),
+    ("BROWN_S_2", 52),
+    ("BROWN_S_3", 53),
+    ("BROWN_S_4", 54),
+    ("BROWN_S_5", 55),
+    ("BROWN_S_6", 56),
+    ("BROWN_S_7", 57),
+    ("BROWN_S_8", 58),
+    ("BROWN_W1_1", 59),
+    ("BROWN_W1_2", 60),
+    ("BROWN_W1_3", 61),
+    ("BROWN_W1_4", 62),
+    ("BROWN_W1_5", 63),
+    ("BROWN_W1_6", 64),
+    ("BROWN_W1_7", 65),
+    ("BROWN_W1_8", 66),
+    ("BROWN_W2_1", 67),
+    ("BROWN_W2_2", 68),
+    ("BROWN_W2_3", 69),
+    ("BROWN_W2_4", 70),
+    ("BROWN_W2_5", 71),
+    ("BROWN_W2_6", 72),
+    ("BROWN_W2_7", 73),
+    ("BROWN_W2_8", 74),
+    ("BROWN_W3_1", 75),
+    ("BROWN_W3_2", 76),
+    ("BROWN_W3_3", 77),
+    ("BROWN_W3_4", 78),
+    ("BROWN_W3_5", 79),
+    ("BROWN_W3_6", 80),
+    ("BROWN_W3_7", 81),
+    ("BROWN_W3_8", 82),
+    ("BROWN_W4_1", 83),
+    ("BROWN_W4_2", 84),
+    ("BROWN_W4_3", 85),
+    ("BROWN_W4_4", 86),
+    ("BROWN_W4_5", 87),
+    ("BROWN_W4_6", 88),
+    ("BROWN_W4_7", 89),
+    ("BROWN_W4_8", 90),
+    ("CLYDE_W1", 293),
+    ("CLYDE_W2", 294),
+    ("DEATHCAM", 2),
+    ("DEMO", 1),
+    ("FETTGEISCHT_DEAD", 408),
+    ("FETTGEISCHT_DIE1", 405),
+    ("FETTGEISCHT_DIE2", 406),
+    ("FETTGEISCHT_DIE3", 407),
+    ("FETTGEISCHT_SHOOT1", 401),
+    ("FETTGEISCHT_SHOOT2", 402),
+    ("FETTGEISCHT_SHOOT3", 403),
+    ("FETTGEISCHT_SHOOT4", 404),
+    ("FETTGEISCHT_W1", 397),
+    ("FETTGEISCHT_W2", 398),
+    ("FETTGEISCHT_W3", 399),
+    ("FETTGEISCHT_W4", 400),
+    ("FURRY_DEAD", 135),
+    ("FURRY_DIE_1", 132),
+    ("FURRY_DIE_2", 133),
+    ("FURRY_DIE_3", 134),
+    ("FURRY_JUMP1", 136),
+    ("FURRY_JUMP2", 137),
+    ("FURRY_JUMP3", 138),
+    ("FURRY_W1_1", 100),
+    ("FURRY_W1_2", 101),
+    ("FURRY_W1_3", 102),
+    ("FURRY_W1_4", 103),
+    ("FURRY_W1_5", 104),
+    ("FURRY_W1_6", 105),
+    ("FURRY_W1_7", 106),
+    ("FURRY_W1_8", 107),
+    ("FURRY_W2_1", 108),
+    ("FURRY_W2_2", 109),
+    ("FURRY_W2_3", 110),
+    ("FURRY_W2_4", 111),
+    ("FURRY_W2_5", 112),
+    ("FURRY_W2_6", 113),
+    ("FURRY_W2_7", 114),
+    ("FURRY_W2_8", 115),
+    ("FURRY_W3_1", 116),
+    ("FURRY_W3_2", 117),
+    ("FURRY_W3_3", 118),
+    ("FURRY_W3_4", 119),
+    ("FURRY_W3_5", 120),
+    ("FURRY_W3_6", 121),
+    ("FURRY_W3_7", 122),
+    ("FURRY_W3_8", 123),
+    ("FURRY_W4_1", 124),
+    ("FURRY_W4_2", 125),
+    ("FURRY_W4_3", 126),
+    ("FURRY_W4_4", 127),
+    ("FURRY_W4_5", 128),
+    ("FURRY_W4_6", 129),
+    ("FURRY_W4_7", 130),
+    ("FURRY_W4_8", 131),
+    ("GOOD_DR_DEAD", 317),
+    ("GOOD_DR_DIE1", 314),
+    ("GOOD_DR_DIE2", 315),
+    ("GOOD_DR_DIE3", 316),
+    ("GOOD_DR_SHOOT1", 312),
+    ("GOOD_DR_SHOOT2", 313),
+    ("GOOD_DR_W1", 308),
+    ("GOOD_DR_W2", 309),
+    ("GOOD_DR_W3", 310),
+    ("GOOD_DR_W4", 311),
+    ("GRETEL_DEAD", 393),
+    ("GRETEL_DIE1", 394),
+    ("GRETEL_DIE2", 395),
+    ("GRETEL_DIE3", 396),
+    ("GRETEL_SHOOT1", 390),
+    ("GRETEL_SHOOT2", 391),
+    ("GRETEL_SHOOT3", 392),
+    ("GRETEL_W1", 386),
+    ("GRETEL_W2", 387),
+    ("GRETEL_W3", 388),
+    ("GRETEL_W4", 389),
+    ("GROFAZ_DEAD", 353),
+    ("GROFAZ_DIE1", 354),
+    ("GROFAZ_DIE2", 355),
+    ("GROFAZ_DIE3", 356),
+    ("GROFAZ_DIE4", 357),
+    ("GROFAZ_DIE5", 358),
+    ("GROFAZ_DIE6", 359),
+    ("GROFAZ_DIE7", 360),
+    ("GROFAZ_SHOOT1", 350),
+    ("GROFAZ_SHOOT2", 351),
+    ("GROFAZ_SHOOT3", 352),
+    ("GROFAZ_W1", 346),
+    ("GROFAZ_W2", 347),
+    ("GROFAZ_W3", 348),
+    ("GROFAZ_W4", 349),
+    ("HERMANN_DEAD", 370),
+    ("HERMANN_DIE1", 367),
+    ("HERMANN_DIE2", 368),
+    ("HERMANN_DIE3", 369),
+    ("HERMANN_SHOOT1", 365),
+    ("HERMANN_SHOOT2", 366),
+    ("HERMANN_W1", 361),
+    ("HERMANN_W2", 362),
+    ("HERMANN_W3", 363),
+    ("HERMANN_W4", 364),
+    ("INKY_W1", 295),
+    ("INKY_W2", 296),
+    ("MAP_AMMO", 49),
+    ("MAP_GROFAZ", 224),
+    ("MAP_KEY1", 43),
+    ("MAP_KEY2", 44),
+    ("MECHA_DEAD", 342),
+    ("MECHA_DIE1", 343),
+    ("MECHA_DIE2", 344),
+    ("MECHA_DIE3", 345),
+    ("MECHA_SHOOT1", 339),
+    ("MECHA_SHOOT2", 340),
+    ("MECHA_SHOOT3", 341),
+    ("MECHA_W1", 335),
+    ("MECHA_W2", 336),
+    ("MECHA_W3", 337),
+    ("MECHA_W4", 338),
+    ("PARTY_O1", 318),
+    ("PARTY_O2", 319),
+    ("PARTY_O3", 320),
+    ("PARTY_O4", 321),
+    ("PINKY_W1", 291),
+    ("PINKY_W2", 292),
+    ("ROCKET_1", 371),
+    ("ROCKET_2", 372),
+    ("ROCKET_3", 373),
+    ("ROCKET_4", 374),
+    ("ROCKET_5", 375),
+    ("ROCKET_6", 376),
+    ("ROCKET_7", 377),
+    ("ROCKET_8", 378),
+    ("ROTTEN_DEAD", 234),
+    ("ROTTEN_DIE_1", 229),
+    ("ROTTEN_DIE_2", 230),
+    ("ROTTEN_DIE_3", 231),
+    ("ROTTEN_DIE_4", 233),
+    ("ROTTEN_PAIN_1", 228),
+    ("ROTTEN_PAIN_2", 232),
+    ("ROTTEN_SHOOT1", 235),
+    ("ROTTEN_SHOOT2", 236),
+    ("ROTTEN_SHOOT3", 237),
+    ("ROTTEN_SHOOT4", 238),
+    ("ROTTEN_S_1", 188),
+    ("ROTTEN_S_2", 189),
+    ("ROTTEN_S_3", 190),
+    ("ROTTEN_S_4", 191),
+    ("ROTTEN_S_5", 192),
+    ("ROTTEN_S_6", 193),
+    ("ROTTEN_S_7", 194),
+    ("ROTTEN_S_8", 195),
+    ("ROTTEN_W1_1", 196),
+    ("ROTTEN_W1_2", 197),
+    ("ROTTEN_W1_3", 198),
+    ("ROTTEN_W1_4", 199),
+    ("ROTTEN_W1_5", 200),
+    ("ROTTEN_W1_6", 201),
+    ("ROTTEN_W1_7", 202),
+    ("ROTTEN_W1_8", 203),
+    ("ROTTEN_W2_1", 204),
+    ("ROTTEN_W2_2", 205),
+    ("ROTTEN_W2_3", 206),
+    ("ROTTEN_W2_4", 207),
+    ("ROTTEN_W2_5", 208),
+    ("ROTTEN_W2_6", 209),
+    ("ROTTEN_W2_7", 210),
+    ("ROTTEN_W2_8", 211),
+    ("ROTTEN_W3_1", 212),
+    ("ROTTEN_W3_2", 213),
+    ("ROTTEN_W3_3", 214),
+    ("ROTTEN_W3_4", 215),
+    ("ROTTEN_W3_5", 216),
+    ("ROTTEN_W3_6", 217),
+    ("ROTTEN_W3_7", 218),
+    ("ROTTEN_W3_8", 219),
+    ("ROTTEN_W4_1", 220),
+    ("ROTTEN_W4_2", 221),
+    ("ROTTEN_W4_3", 222),
+    ("ROTTEN_W4_4", 223),
+    ("ROTTEN_W4_5", 224),
+    ("ROTTEN_W4_6", 225),
+    ("ROTTEN_W4_7", 226),
+    ("ROTTEN_W4_8", 227),
+    ("SELF1_W1", 409),
+    ("SELF_JUMP1", 413),
+    ("SELF_JUMP2", 414),
+    ("SELF_JUMP3", 415),
+    ("SELF_JUMP4", 416),
+    ("SELF_W2", 410),
+    ("SELF_W3", 411),
+    ("SELF_W4", 412),
+    ("SMOKE_1", 379),
+    ("SMOKE_2", 380),
+    ("SMOKE_3", 381),
+    ("SMOKE_4", 382),
+    ("SND_BREAKING", 30),
+    ("SND_DOOR_CLOSE", 2),
+    ("SND_DOOR_OPEN", 3),
+    ("SND_FART", 39),
+    ("SND_GIBB", 22),
+    ("SND_GREET_BLUE", 7),
+    ("SND_HALTSTOP", 0),
+    ("SND_LAUGH_ALP", 26),
+    ("SND_LAUGH_HUHUHAHA", 25),
+    ("SND_MECHA_STEP", 31),
+    ("SND_PLAYER_DAMAGE", 14),
+    ("SND_SCREAM_AAGH", 12),
+    ("SND_SCREAM_AIIII", 34),
+    ("SND_SCREAM_AYYY", 13),
+    ("SND_SCREAM_CHHHA", 40),
+    ("SND_SCREAM_HUUGH", 41),
+    ("SND_SCREAM_MEINLEBEN", 20),
+    ("SND_SCREAM_NEINSOWAS", 28),
+    ("SND_SCREAM_OOHHH", 35),
+    ("SND_SCREAM_ROTTEN_AAAH", 17),
+    ("SND_SCREAM_UWWWGH", 42),
+    ("SND_SECRET", 15),
+    ("SND_SHOT_BLUE", 11),
+    ("SND_SHOT_BOSS", 10),
+    ("SND_SHOT_BROWN", 21),
+    ("SND_SHOT_CHAIN", 6),
+    ("SND_SHOT_MG", 4),
+    ("SND_SHOT_PISTOL", 5),
+    ("SND_UTT_DONNERWETTER", 36),
+    ("SND_UTT_ERLAUBENSIEBITTE", 38),
+    ("SND_UTT_EVAAUFWIEDERSEHN", 19),
+    ("SND_UTT_GUTENTAG", 8),
+    ("SND_UTT_KEINDEUTSCHKANN", 43),
+    ("SND_UTT_KLEINEAMERIKANER", 37),
+    ("SND_UTT_MEINFUSSE", 44),
+    ("SND_UTT_MEINGOTTIMHIMMEL", 24),
+    ("SND_UTT_MUTTI", 9),
+    ("SND_UTT_RAPUNZ", 23),
+    ("SND_UTT_ROSENOSPE", 45),
+    ("SND_UTT_SCHEISS", 33),
+    ("SND_UTT_SCHWEINHUND", 18),
+    ("SND_UTT_SPION", 27),
+    ("SND_WAU_WAU", 29),
+    ("SND_WOOF", 1),
+    ("SND_YEAH", 32),
+    ("SND_YIFF", 16),
+    ("SPR_CHAINATK1", 433),
+    ("SPR_CHAINATK2", 434),
+    ("SPR_CHAINATK3", 435),
+    ("SPR_CHAINATK4", 436),
+    ("SPR_CHAINREADY", 432),
+    ("SPR_KNIFEATK1", 418),
+    ("SPR_KNIFEATK2", 419),
+    ("SPR_KNIFEATK3", 420),
+    ("SPR_KNIFEATK4", 421),
+    ("SPR_KNIFEREADY", 417),
+    ("SPR_MACHINEGUNATK1", 428),
+    ("SPR_MACHINEGUNATK2", 429),
+    ("SPR_MACHINEGUNATK3", 430),
+    ("SPR_MACHINEGUNATK4", 431),
+    ("SPR_MACHINEGUNREADY", 427),
+    ("SPR_NONE", 0),
+    ("SPR_PISTOLATK1", 423),
+    ("SPR_PISTOLATK2", 424),
+    ("SPR_PISTOLATK3", 425),
+    ("SPR_PISTOLATK4", 426),
+    ("SPR_PISTOLREADY", 422),
+    ("STAT_0", 3),
+    ("STAT_1", 4),
+    ("STAT_10", 13),
+    ("STAT_11", 14),
+    ("STAT_12", 15),
+    ("STAT_13", 16),
+    ("STAT_14", 17),
+    ("STAT_15", 18),
+    ("STAT_16", 19),
+    ("STAT_17", 20),
+    ("STAT_18", 21),
+    ("STAT_19", 22),
+    ("STAT_2", 5),
+    ("STAT_20", 23),
+    ("STAT_21", 24),
+    ("STAT_22", 25),
+    ("STAT_23", 26),
+    ("STAT_24", 27),
+    ("STAT_25", 28),
+    ("STAT_26", 29),
+    ("STAT_27", 30),
+    ("STAT_28", 31),
+    ("STAT_29", 32),
+    ("STAT_3", 6),
+    ("STAT_30", 33),
+    ("STAT_31", 34),
+    ("STAT_32", 35),
+    ("STAT_33", 36),
+    ("STAT_34", 37),
+    ("STAT_35", 38),
+    ("STAT_36", 39),
+    ("STAT_37", 40),
+    ("STAT_38", 41),
+    ("STAT_39", 42),
+    ("STAT_4", 7),
+    ("STAT_40", 43),
+    ("STAT_41", 44),
+    ("STAT_42", 45),
+    ("STAT_43", 46),
+    ("STAT_44", 47),
+    ("STAT_45", 48),
+    ("STAT_46", 49),
+    ("STAT_47", 50),
+    ("STAT_5", 8),
+    ("STAT_6", 9),
+    ("STAT_7", 10),
+    ("STAT_8", 11),
+    ("STAT_9", 12),
+    ("WHITE_DEAD", 285),
+    ("WHITE_DIE_1", 280),
+    ("WHITE_DIE_2", 281),
+    ("WHITE_DIE_3", 282),
+    ("WHITE_DIE_4", 284),
+    ("WHITE_PAIN_1", 279),
+    ("WHITE_PAIN_2", 283),
+    ("WHITE_SHOOT1", 286),
+    ("WHITE_SHOOT2", 287),
+    ("WHITE_SHOOT3", 288),
+    ("WHITE_S_1", 239),
+    ("WHITE_S_2", 240),
+    ("WHITE_S_3", 241),
+    ("WHITE_S_4", 242),
+    ("WHITE_S_5", 243),
+    ("WHITE_S_6", 244),
+    ("WHITE_S_7", 245),
+    ("WHITE_S_8", 246),
+    ("WHITE_W1_1", 247),
+    ("WHITE_W1_2", 248),
+    ("WHITE_W1_3", 249),
+    ("WHITE_W1_4", 250),
+    ("WHITE_W1_5", 251),
+    ("WHITE_W1_6", 252),
+    ("WHITE_W1_7", 253),
+    ("WHITE_W1_8", 254),
+    ("WHITE_W2_1", 255),
+    ("WHITE_W2_2", 256),
+    ("WHITE_W2_3", 257),
+    ("WHITE_W2_4", 258),
+    ("WHITE_W2_5", 259),
+    ("WHITE_W2_6", 260),
+    ("WHITE_W2_7", 261),
+    ("WHITE_W2_8", 262),
+    ("WHITE_W3_1", 263),
+    ("WHITE_W3_2", 264),
+    ("WHITE_W3_3", 265),
+    ("WHITE_W3_4", 266),
+    ("WHITE_W3_5", 267),
+    ("WHITE_W3_6", 268),
+    ("WHITE_W3_7", 269),
+    ("WHITE_W3_8", 270),
+    ("WHITE_W4_1", 271),
+    ("WHITE_W4_2", 272),
+    ("WHITE_W4_3", 273),
+    ("WHITE_W4_4", 274),
+    ("WHITE_W4_5", 275),
+    ("WHITE_W4_6", 276),
+    ("WHITE_W4_7", 277),
+    ("WHITE_W4_8", 278),
+];
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Sound {
+    SND_HALTSTOP = 0,
+    SND_WOOF = 1,
+    SND_DOOR_CLOSE = 2,
+    SND_DOOR_OPEN = 3,
+    SND_SHOT_MG = 4,
+    SND_SHOT_PISTOL = 5,
+    SND_SHOT_CHAIN = 6,
+    SND_GREET_BLUE = 7,
+    SND_UTT_GUTENTAG = 8,
+    SND_UTT_MUTTI = 9,
+    SND_SHOT_BOSS = 10,
+    SND_SHOT_BLUE = 11,
+    SND_SCREAM_AAGH = 12,
+    SND_SCREAM_AYYY = 13,
+    SND_PLAYER_DAMAGE = 14,
+    SND_SECRET = 15,
+    SND_YIFF = 16,
+    SND_SCREAM_ROTTEN_AAAH = 17,
+    SND_UTT_SCHWEINHUND = 18,
+    SND_UTT_EVAAUFWIEDERSEHN = 19,
+    SND_SCREAM_MEINLEBEN = 20,
+    SND_SHOT_BROWN = 21,
+    SND_GIBB = 22,
+    SND_UTT_RAPUNZ = 23,
+    SND_UTT_MEINGOTTIMHIMMEL = 24,
+    SND_LAUGH_HUHUHAHA = 25,
+    SND_LAUGH_ALP = 26,
+    SND_UTT_SPION = 27,
+    SND_SCREAM_NEINSOWAS = 28,
+    SND_WAU_WAU = 29,
+    SND_BREAKING = 30,
+    SND_MECHA_STEP = 31,
+    SND_YEAH = 32,
+    SND_UTT_SCHEISS = 33,
+    SND_SCREAM_AIIII = 34,
+    SND_SCREAM_OOHHH = 35,
+    SND_UTT_DONNERWETTER = 36,
+    SND_UTT_KLEINEAMERIKANER = 37,
+    SND_UTT_ERLAUBENSIEBITTE = 38,
+    SND_FART = 39,
+    SND_SCREAM_CHHHA = 40,
+    SND_SCREAM_HUUGH = 41,
+    SND_SCREAM_UWWWGH = 42,
+    SND_UTT_KEINDEUTSCHKANN = 43,
+    SND_UTT_MEINFUSSE = 44,
+    SND_UTT_ROSENOSPE = 45,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 46] = [
+        Sound::SND_HALTSTOP,
+        Sound::SND_WOOF,
+        Sound::SND_DOOR_CLOSE,
+        Sound::SND_DOOR_OPEN,
+        Sound::SND_SHOT_MG,
+        Sound::SND_SHOT_PISTOL,
+        Sound::SND_SHOT_CHAIN,
+        Sound::SND_GREET_BLUE,
+        Sound::SND_UTT_GUTENTAG,
+        Sound::SND_UTT_MUTTI,
+        Sound::SND_SHOT_BOSS,
+        Sound::SND_SHOT_BLUE,
+        Sound::SND_SCREAM_AAGH,
+        Sound::SND_SCREAM_AYYY,
+        Sound::SND_PLAYER_DAMAGE,
+        Sound::SND_SECRET,
+        Sound::SND_YIFF,
+        Sound::SND_SCREAM_ROTTEN_AAAH,
+        Sound::SND_UTT_SCHWEINHUND,
+        Sound::SND_UTT_EVAAUFWIEDERSEHN,
+        Sound::SND_SCREAM_MEINLEBEN,
+        Sound::SND_SHOT_BROWN,
+        Sound::SND_GIBB,
+        Sound::SND_UTT_RAPUNZ,
+        Sound::SND_UTT_MEINGOTTIMHIMMEL,
+        Sound::SND_LAUGH_HUHUHAHA,
+        Sound::SND_LAUGH_ALP,
+        Sound::SND_UTT_SPION,
+        Sound::SND_SCREAM_NEINSOWAS,
+        Sound::SND_WAU_WAU,
+        Sound::SND_BREAKING,
+        Sound::SND_MECHA_STEP,
+        Sound::SND_YEAH,
+        Sound::SND_UTT_SCHEISS,
+        Sound::SND_SCREAM_AIIII,
+        Sound::SND_SCREAM_OOHHH,
+        Sound::SND_UTT_DONNERWETTER,
+        Sound::SND_UTT_KLEINEAMERIKANER,
+        Sound::SND_UTT_ERLAUBENSIEBITTE,
+        Sound::SND_FART,
+        Sound::SND_SCREAM_CHHHA,
+        Sound::SND_SCREAM_HUUGH,
+        Sound::SND_SCREAM_UWWWGH,
+        Sound::SND_UTT_KEINDEUTSCHKANN,
+        Sound::SND_UTT_MEINFUSSE,
+        Sound::SND_UTT_ROSENOSPE,
+    ];
+    pub const NAMES: [&'static str; 46] = [
+        "SND_HALTSTOP",
+        "SND_WOOF",
+        "SND_DOOR_CLOSE",
+        "SND_DOOR_OPEN",
+        "SND_SHOT_MG",
+        "SND_SHOT_PISTOL",
+        "SND_SHOT_CHAIN",
+        "SND_GREET_BLUE",
+        "SND_UTT_GUTENTAG",
+        "SND_UTT_MUTTI",
+        "SND_SHOT_BOSS",
+        "SND_SHOT_BLUE",
+        "SND_SCREAM_AAGH",
+        "SND_SCREAM_AYYY",
+        "SND_PLAYER_DAMAGE",
+        "SND_SECRET",
+        "SND_YIFF",
+        "SND_SCREAM_ROTTEN_AAAH",
+        "SND_UTT_SCHWEINHUND",
+        "SND_UTT_EVAAUFWIEDERSEHN",
+        "SND_SCREAM_MEINLEBEN",
+        "SND_SHOT_BROWN",
+        "SND_GIBB",
+        "SND_UTT_RAPUNZ",
+        "SND_UTT_MEINGOTTIMHIMMEL",
+        "SND_LAUGH_HUHUHAHA",
+        "SND_LAUGH_ALP",
+        "SND_UTT_SPION",
+        "SND_SCREAM_NEINSOWAS",
+        "SND_WAU_WAU",
+        "SND_BREAKING",
+        "SND_MECHA_STEP",
+        "SND_YEAH",
+        "SND_UTT_SCHEISS",
+        "SND_SCREAM_AIIII",
+        "SND_SCREAM_OOHHH",
+        "SND_UTT_DONNERWETTER",
+        "SND_UTT_KLEINEAMERIKANER",
+        "SND_UTT_ERLAUBENSIEBITTE",
+        "SND_FART",
+        "SND_SCREAM_CHHHA",
+        "SND_SCREAM_HUUGH",
+        "SND_SCREAM_UWWWGH",
+        "SND_UTT_KEINDEUTSCHKANN",
+        "SND_UTT_MEINFUSSE",
+        "SND_UTT_ROSENOSPE",
+    ];
+
+    pub const fn id(self) -> i32 {
+        self as i32
+    }
+    pub fn from_id(id: i32) -> Option<Sound> {
+        Self::ALL.get(usize::try_from(id).ok()?).copied()
+    }
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+    pub fn from_name(name: &str) -> Option<Sound> {
+        let index = Self::NAMES.iter().position(|n| *n == name)?;
+        Some(Self::ALL[index])
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Sprite {
+    SPR_NONE = 0,
+    DEMO = 1,
+    DEATHCAM = 2,
+    STAT_0 = 3,
+    STAT_1 = 4,
+    STAT_2 = 5,
+    STAT_3 = 6,
+    STAT_4 = 7,
+    STAT_5 = 8,
+    STAT_6 = 9,
+    STAT_7 = 10,
+    STAT_8 = 11,
+    STAT_9 = 12,
+    STAT_10 = 13,
+    STAT_11 = 14,
+    STAT_12 = 15,
+    STAT_13 = 16,
+    STAT_14 = 17,
+    STAT_15 = 18,
+    STAT_16 = 19,
+    STAT_17 = 20,
+    STAT_18 = 21,
+    STAT_19 = 22,
+    STAT_20 = 23,
+    STAT_21 = 24,
+    STAT_22 = 25,
+    STAT_23 = 26,
+    STAT_24 = 27,
+    STAT_25 = 28,
+    STAT_26 = 29,
+    STAT_27 = 30,
+    STAT_28 = 31,
+    STAT_29 = 32,
+    STAT_30 = 33,
+    STAT_31 = 34,
+    STAT_32 = 35,
+    STAT_33 = 36,
+    STAT_34 = 37,
+    STAT_35 = 38,
+    STAT_36 = 39,
+    STAT_37 = 40,
+    STAT_38 = 41,
+    STAT_39 = 42,
+    STAT_40 = 43,
+    STAT_41 = 44,
+    STAT_42 = 45,
+    STAT_43 = 46,
+    STAT_44 = 47,
+    STAT_45 = 48,
+    STAT_46 = 49,
+    STAT_47 = 50,
+    BROWN_S_1 = 51,
+    BROWN_S_2 = 52,
+    BROWN_S_3 = 53,
+    BROWN_S_4 = 54,
+    BROWN_S_5 = 55,
+    BROWN_S_6 = 56,
+    BROWN_S_7 = 57,
+    BROWN_S_8 = 58,
+    BROWN_W1_1 = 59,
+    BROWN_W1_2 = 60,
+    BROWN_W1_3 = 61,
+    BROWN_W1_4 = 62,
+    BROWN_W1_5 = 63,
+    BROWN_W1_6 = 64,
+    BROWN_W1_7 = 65,
+    BROWN_W1_8 = 66,
+    BROWN_W2_1 = 67,
+    BROWN_W2_2 = 68,
+    BROWN_W2_3 = 69,
+    BROWN_W2_4 = 70,
+    BROWN_W2_5 = 71,
+    BROWN_W2_6 = 72,
+    BROWN_W2_7 = 73,
+    BROWN_W2_8 = 74,
+    BROWN_W3_1 = 75,
+    BROWN_W3_2 = 76,
+    BROWN_W3_3 = 77,
+    BROWN_W3_4 = 78,
+    BROWN_W3_5 = 79,
+    BROWN_W3_6 = 80,
+    BROWN_W3_7 = 81,
+    BROWN_W3_8 = 82,
+    BROWN_W4_1 = 83,
+    BROWN_W4_2 = 84,
+    BROWN_W4_3 = 85,
+    BROWN_W4_4 = 86,
+    BROWN_W4_5 = 87,
+    BROWN_W4_6 = 88,
+    BROWN_W4_7 = 89,
+    BROWN_W4_8 = 90,
+    BROWN_PAIN_1 = 91,
+    BROWN_DIE_1 = 92,
+    BROWN_DIE_2 = 93,
+    BROWN_DIE_3 = 94,
+    BROWN_PAIN_2 = 95,
+    BROWN_DEAD = 96,
+    BROWN_SHOOT1 = 97,
+    BROWN_SHOOT2 = 98,
+    BROWN_SHOOT3 = 99,
+    FURRY_W1_1 = 100,
+    FURRY_W1_2 = 101,
+    FURRY_W1_3 = 102,
+    FURRY_W1_4 = 103,
+    FURRY_W1_5 = 104,
+    FURRY_W1_6 = 105,
+    FURRY_W1_7 = 106,
+    FURRY_W1_8 = 107,
+    FURRY_W2_1 = 108,
+    FURRY_W2_2 = 109,
+    FURRY_W2_3 = 110,
+    FURRY_W2_4 = 111,
+    FURRY_W2_5 = 112,
+    FURRY_W2_6 = 113,
+    FURRY_W2_7 = 114,
+    FURRY_W2_8 = 115,
+    FURRY_W3_1 = 116,
+    FURRY_W3_2 = 117,
+    FURRY_W3_3 = 118,
+    FURRY_W3_4 = 119,
+    FURRY_W3_5 = 120,
+    FURRY_W3_6 = 121,
+    FURRY_W3_7 = 122,
+    FURRY_W3_8 = 123,
+    FURRY_W4_1 = 124,
+    FURRY_W4_2 = 125,
+    FURRY_W4_3 = 126,
+    FURRY_W4_4 = 127,
+    FURRY_W4_5 = 128,
+    FURRY_W4_6 = 129,
+    FURRY_W4_7 = 130,
+    FURRY_W4_8 = 131,
+    FURRY_DIE_1 = 132,
+    FURRY_DIE_2 = 133,
+    FURRY_DIE_3 = 134,
+    FURRY_DEAD = 135,
+    FURRY_JUMP1 = 136,
+    FURRY_JUMP2 = 137,
+    FURRY_JUMP3 = 138,
+    BLUE_S_1 = 139,
+    BLUE_S_2 = 140,
+    BLUE_S_3 = 141,
+    BLUE_S_4 = 142,
+    BLUE_S_5 = 143,
+    BLUE_S_6 = 144,
+    BLUE_S_7 = 145,
+    BLUE_S_8 = 146,
+    BLUE_W1_1 = 147,
+    BLUE_W1_2 = 148,
+    BLUE_W1_3 = 149,
+    BLUE_W1_4 = 150,
+    BLUE_W1_5 = 151,
+    BLUE_W1_6 = 152,
+    BLUE_W1_7 = 153,
+    BLUE_W1_8 = 154,
+    BLUE_W2_1 = 155,
+    BLUE_W2_2 = 156,
+    BLUE_W2_3 = 157,
+    BLUE_W2_4 = 158,
+    BLUE_W2_5 = 159,
+    BLUE_W2_6 = 160,
+    BLUE_W2_7 = 161,
+    BLUE_W2_8 = 162,
+    BLUE_W3_1 = 163,
+    BLUE_W3_2 = 164,
+    BLUE_W3_3 = 165,
+    BLUE_W3_4 = 166,
+    BLUE_W3_5 = 167,
+    BLUE_W3_6 = 168,
+    BLUE_W3_7 = 169,
+    BLUE_W3_8 = 170,
+    BLUE_W4_1 = 171,
+    BLUE_W4_2 = 172,
+    BLUE_W4_3 = 173,
+    BLUE_W4_4 = 174,
+    BLUE_W4_5 = 175,
+    BLUE_W4_6 = 176,
+    BLUE_W4_7 = 177,
+    BLUE_W4_8 = 178,
+    BLUE_PAIN_1 = 179,
+    BLUE_DIE_1 = 180,
+    BLUE_DIE_2 = 181,
+    BLUE_DIE_3 = 182,
+    BLUE_PAIN_2 = 183,
+    BLUE_DEAD = 184,
+    BLUE_SHOOT1 = 185,
+    BLUE_SHOOT2 = 186,
+    BLUE_SHOOT3 = 187,
+    ROTTEN_S_1 = 188,
+    ROTTEN_S_2 = 189,
+    ROTTEN_S_3 = 190,
+    ROTTEN_S_4 = 191,
+    ROTTEN_S_5 = 192,
+    ROTTEN_S_6 = 193,
+    ROTTEN_S_7 = 194,
+    ROTTEN_S_8 = 195,
+    ROTTEN_W1_1 = 196,
+    ROTTEN_W1_2 = 197,
+    ROTTEN_W1_3 = 198,
+    ROTTEN_W1_4 = 199,
+    ROTTEN_W1_5 = 200,
+    ROTTEN_W1_6 = 201,
+    ROTTEN_W1_7 = 202,
+    ROTTEN_W1_8 = 203,
+    ROTTEN_W2_1 = 204,
+    ROTTEN_W2_2 = 205,
+    ROTTEN_W2_3 = 206,
+    ROTTEN_W2_4 = 207,
+    ROTTEN_W2_5 = 208,
+    ROTTEN_W2_6 = 209,
+    ROTTEN_W2_7 = 210,
+    ROTTEN_W2_8 = 211,
+    ROTTEN_W3_1 = 212,
+    ROTTEN_W3_2 = 213,
+    ROTTEN_W3_3 = 214,
+    ROTTEN_W3_4 = 215,
+    ROTTEN_W3_5 = 216,
+    ROTTEN_W3_6 = 217,
+    ROTTEN_W3_7 = 218,
+    ROTTEN_W3_8 = 219,
+    ROTTEN_W4_1 = 220,
+    ROTTEN_W4_2 = 221,
+    ROTTEN_W4_3 = 222,
+    ROTTEN_W4_4 = 223,
+    ROTTEN_W4_5 = 224,
+    ROTTEN_W4_6 = 225,
+    ROTTEN_W4_7 = 226,
+    ROTTEN_W4_8 = 227,
+    ROTTEN_PAIN_1 = 228,
+    ROTTEN_DIE_1 = 229,
+    ROTTEN_DIE_2 = 230,
+    ROTTEN_DIE_3 = 231,
+    ROTTEN_PAIN_2 = 232,
+    ROTTEN_DIE_4 = 233,
+    ROTTEN_DEAD = 234,
+    ROTTEN_SHOOT1 = 235,
+    ROTTEN_SHOOT2 = 236,
+    ROTTEN_SHOOT3 = 237,
+    ROTTEN_SHOOT4 = 238,
+    WHITE_S_1 = 239,
+    WHITE_S_2 = 240,
+    WHITE_S_3 = 241,
+    WHITE_S_4 = 242,
+    WHITE_S_5 = 243,
+    WHITE_S_6 = 244,
+    WHITE_S_7 = 245,
+    WHITE_S_8 = 246,
+    WHITE_W1_1 = 247,
+    WHITE_W1_2 = 248,
+    WHITE_W1_3 = 249,
+    WHITE_W1_4 = 250,
+    WHITE_W1_5 = 251,
+    WHITE_W1_6 = 252,
+    WHITE_W1_7 = 253,
+    WHITE_W1_8 = 254,
+    WHITE_W2_1 = 255,
+    WHITE_W2_2 = 256,
+    WHITE_W2_3 = 257,
+    WHITE_W2_4 = 258,
+    WHITE_W2_5 = 259,
+    WHITE_W2_6 = 260,
+    WHITE_W2_7 = 261,
+    WHITE_W2_8 = 262,
+    WHITE_W3_1 = 263,
+    WHITE_W3_2 = 264,
+    WHITE_W3_3 = 265,
+    WHITE_W3_4 = 266,
+    WHITE_W3_5 = 267,
+    WHITE_W3_6 = 268,
+    WHITE_W3_7 = 269,
+    WHITE_W3_8 = 270,
+    WHITE_W4_1 = 271,
+    WHITE_W4_2 = 272,
+    WHITE_W4_3 = 273,
+    WHITE_W4_4 = 274,
+    WHITE_W4_5 = 275,
+    WHITE_W4_6 = 276,
+    WHITE_W4_7 = 277,
+    WHITE_W4_8 = 278,
+    WHITE_PAIN_1 = 279,
+    WHITE_DIE_1 = 280,
+    WHITE_DIE_2 = 281,
+    WHITE_DIE_3 = 282,
+    WHITE_PAIN_2 = 283,
+    WHITE_DIE_4 = 284,
+    WHITE_DEAD = 285,
+    WHITE_SHOOT1 = 286,
+    WHITE_SHOOT2 = 287,
+    WHITE_SHOOT3 = 288,
+    BLINKY_W1 = 289,
+    BLINKY_W2 = 290,
+    PINKY_W1 = 291,
+    PINKY_W2 = 292,
+    CLYDE_W1 = 293,
+    CLYDE_W2 = 294,
+    INKY_W1 = 295,
+    INKY_W2 = 296,
+    BOSS_W1 = 297,
+    BOSS_W2 = 298,
+    BOSS_W3 = 299,
+    BOSS_W4 = 300,
+    BOSS_SHOOT1 = 301,
+    BOSS_SHOOT2 = 302,
+    BOSS_SHOOT3 = 303,
+    BOSS_DEAD = 304,
+    BOSS_DIE1 = 305,
+    BOSS_DIE2 = 306,
+    BOSS_DIE3 = 307,
+    GOOD_DR_W1 = 308,
+    GOOD_DR_W2 = 309,
+    GOOD_DR_W3 = 310,
+    GOOD_DR_W4 = 311,
+    GOOD_DR_SHOOT1 = 312,
+    GOOD_DR_SHOOT2 = 313,
+    GOOD_DR_DIE1 = 314,
+    GOOD_DR_DIE2 = 315,
+    GOOD_DR_DIE3 = 316,
+    GOOD_DR_DEAD = 317,
+    PARTY_O1 = 318,
+    PARTY_O2 = 319,
+    PARTY_O3 = 320,
+    PARTY_O4 = 321,
+    ALP_W1 = 322,
+    ALP_W2 = 323,
+    ALP_W3 = 324,
+    ALP_W4 = 325,
+    ALP_SHOOT = 326,
+    ALP_FIRE1 = 327,
+    ALP_FIRE2 = 328,
+    ALP_DIE1 = 329,
+    ALP_DIE2 = 330,
+    ALP_DIE3 = 331,
+    ALP_DIE4 = 332,
+    ALP_DIE5 = 333,
+    ALP_DEAD = 334,
+    MECHA_W1 = 335,
+    MECHA_W2 = 336,
+    MECHA_W3 = 337,
+    MECHA_W4 = 338,
+    MECHA_SHOOT1 = 339,
+    MECHA_SHOOT2 = 340,
+    MECHA_SHOOT3 = 341,
+    MECHA_DEAD = 342,
+    MECHA_DIE1 = 343,
+    MECHA_DIE2 = 344,
+    MECHA_DIE3 = 345,
+    GROFAZ_W1 = 346,
+    GROFAZ_W2 = 347,
+    GROFAZ_W3 = 348,
+    GROFAZ_W4 = 349,
+    GROFAZ_SHOOT1 = 350,
+    GROFAZ_SHOOT2 = 351,
+    GROFAZ_SHOOT3 = 352,
+    GROFAZ_DEAD = 353,
+    GROFAZ_DIE1 = 354,
+    GROFAZ_DIE2 = 355,
+    GROFAZ_DIE3 = 356,
+    GROFAZ_DIE4 = 357,
+    GROFAZ_DIE5 = 358,
+    GROFAZ_DIE6 = 359,
+    GROFAZ_DIE7 = 360,
+    HERMANN_W1 = 361,
+    HERMANN_W2 = 362,
+    HERMANN_W3 = 363,
+    HERMANN_W4 = 364,
+    HERMANN_SHOOT1 = 365,
+    HERMANN_SHOOT2 = 366,
+    HERMANN_DIE1 = 367,
+    HERMANN_DIE2 = 368,
+    HERMANN_DIE3 = 369,
+    HERMANN_DEAD = 370,
+    ROCKET_1 = 371,
+    ROCKET_2 = 372,
+    ROCKET_3 = 373,
+    ROCKET_4 = 374,
+    ROCKET_5 = 375,
+    ROCKET_6 = 376,
+    ROCKET_7 = 377,
+    ROCKET_8 = 378,
+    SMOKE_1 = 379,
+    SMOKE_2 = 380,
+    SMOKE_3 = 381,
+    SMOKE_4 = 382,
+    BOOM_1 = 383,
+    BOOM_2 = 384,
+    BOOM_3 = 385,
+    GRETEL_W1 = 386,
+    GRETEL_W2 = 387,
+    GRETEL_W3 = 388,
+    GRETEL_W4 = 389,
+    GRETEL_SHOOT1 = 390,
+    GRETEL_SHOOT2 = 391,
+    GRETEL_SHOOT3 = 392,
+    GRETEL_DEAD = 393,
+    GRETEL_DIE1 = 394,
+    GRETEL_DIE2 = 395,
+    GRETEL_DIE3 = 396,
+    FETTGEISCHT_W1 = 397,
+    FETTGEISCHT_W2 = 398,
+    FETTGEISCHT_W3 = 399,
+    FETTGEISCHT_W4 = 400,
+    FETTGEISCHT_SHOOT1 = 401,
+    FETTGEISCHT_SHOOT2 = 402,
+    FETTGEISCHT_SHOOT3 = 403,
+    FETTGEISCHT_SHOOT4 = 404,
+    FETTGEISCHT_DIE1 = 405,
+    FETTGEISCHT_DIE2 = 406,
+    FETTGEISCHT_DIE3 = 407,
+    FETTGEISCHT_DEAD = 408,
+    SELF1_W1 = 409,
+    SELF_W2 = 410,
+    SELF_W3 = 411,
+    SELF_W4 = 412,
+    SELF_JUMP1 = 413,
+    SELF_JUMP2 = 414,
+    SELF_JUMP3 = 415,
+    SELF_JUMP4 = 416,
+    SPR_KNIFEREADY = 417,
+    SPR_KNIFEATK1 = 418,
+    SPR_KNIFEATK2 = 419,
+    SPR_KNIFEATK3 = 420,
+    SPR_KNIFEATK4 = 421,
+    SPR_PISTOLREADY = 422,
+    SPR_PISTOLATK1 = 423,
+    SPR_PISTOLATK2 = 424,
+    SPR_PISTOLATK3 = 425,
+    SPR_PISTOLATK4 = 426,
+    SPR_MACHINEGUNREADY = 427,
+    SPR_MACHINEGUNATK1 = 428,
+    SPR_MACHINEGUNATK2 = 429,
+    SPR_MACHINEGUNATK3 = 430,
+    SPR_MACHINEGUNATK4 = 431,
+    SPR_CHAINREADY = 432,
+    SPR_CHAINATK1 = 433,
+    SPR_CHAINATK2 = 434,
+    SPR_CHAINATK3 = 435,
+    SPR_CHAINATK4 = 436,
+}
+
+impl Sprite {
+    pub const ALL: [Sprite; 437] = [
+        Sprite::SPR_NONE,
+        Sprite::DEMO,
+        Sprite::DEATHCAM,
+        Sprite::STAT_0,
+        Sprite::STAT_1,
+        Sprite::STAT_2,
+        Sprite::STAT_3,
+        Sprite::STAT_4,
+        Sprite::STAT_5,
+        Sprite::STAT_6,
+        Sprite::STAT_7,
+        Sprite::STAT_8,
+        Sprite::STAT_9,
+        Sprite::STAT_10,
+        Sprite::STAT_11,
+        Sprite::STAT_12,
+        Sprite::STAT_13,
+        Sprite::STAT_14,
+        Sprite::STAT_15,
+        Sprite::STAT_16,
+        Sprite::STAT_17,
+        Sprite::STAT_18,
+        Sprite::STAT_19,
+        Sprite::STAT_20,
+        Sprite::STAT_21,
+        Sprite::STAT_22,
+        Sprite::STAT_23,
+        Sprite::STAT_24,
+        Sprite::STAT_25,
+        Sprite::STAT_26,
+        Sprite::STAT_27,
+        Sprite::STAT_28,
+        Sprite::STAT_29,
+        Sprite::STAT_30,
+        Sprite::STAT_31,
+        Sprite::STAT_32,
+        Sprite::STAT_33,
+        Sprite::STAT_34,
+        Sprite::STAT_35,
+        Sprite::STAT_36,
+        Sprite::STAT_37,
+        Sprite::STAT_38,
+        Sprite::STAT_39,
+        Sprite::STAT_40,
+        Sprite::STAT_41,
+        Sprite::STAT_42,
+        Sprite::STAT_43,
+        Sprite::STAT_44,
+        Sprite::STAT_45,
+        Sprite::STAT_46,
+        Sprite::STAT_47,
+        Sprite::BROWN_S_1,
+        Sprite::BROWN_S_2,
+        Sprite::BROWN_S_3,
+        Sprite::BROWN_S_4,
+        Sprite::BROWN_S_5,
+        Sprite::BROWN_S_6,
+        Sprite::BROWN_S_7,
+        Sprite::BROWN_S_8,
+        Sprite::BROWN_W1_1,
+        Sprite::BROWN_W1_2,
+        Sprite::BROWN_W1_3,
+        Sprite::BROWN_W1_4,
+        Sprite::BROWN_W1_5,
+        Sprite::BROWN_W1_6,
+        Sprite::BROWN_W1_7,
+        Sprite::BROWN_W1_8,
+        Sprite::BROWN_W2_1,
+        Sprite::BROWN_W2_2,
+        Sprite::BROWN_W2_3,
+        Sprite::BROWN_W2_4,
+        Sprite::BROWN_W2_5,
+        Sprite::BROWN_W2_6,
+        Sprite::BROWN_W2_7,
+        Sprite::BROWN_W2_8,
+        Sprite::BROWN_W3_1,
+        Sprite::BROWN_W3_2,
+        Sprite::BROWN_W3_3,
+        Sprite::BROWN_W3_4,
+        Sprite::BROWN_W3_5,
+        Sprite::BROWN_W3_6,
+        Sprite::BROWN_W3_7,
+        Sprite::BROWN_W3_8,
+        Sprite::BROWN_W4_1,
+        Sprite::BROWN_W4_2,
+        Sprite::BROWN_W4_3,
+        Sprite::BROWN_W4_4,
+        Sprite::BROWN_W4_5,
+        Sprite::BROWN_W4_6,
+        Sprite::BROWN_W4_7,
+        Sprite::BROWN_W4_8,
+        Sprite::BROWN_PAIN_1,
+        Sprite::BROWN_DIE_1,
+        Sprite::BROWN_DIE_2,
+        Sprite::BROWN_DIE_3,
+        Sprite::BROWN_PAIN_2,
+        Sprite::BROWN_DEAD,
+        Sprite::BROWN_SHOOT1,
+        Sprite::BROWN_SHOOT2,
+        Sprite::BROWN_SHOOT3,
+        Sprite::FURRY_W1_1,
+        Sprite::FURRY_W1_2,
+        Sprite::FURRY_W1_3,
+        Sprite::FURRY_W1_4,
+        Sprite::FURRY_W1_5,
+        Sprite::FURRY_W1_6,
+        Sprite::FURRY_W1_7,
+        Sprite::FURRY_W1_8,
+        Sprite::FURRY_W2_1,
+        Sprite::FURRY_W2_2,
+        Sprite::FURRY_W2_3,
+        Sprite::FURRY_W2_4,
+        Sprite::FURRY_W2_5,
+        Sprite::FURRY_W2_6,
+        Sprite::FURRY_W2_7,
+        Sprite::FURRY_W2_8,
+        Sprite::FURRY_W3_1,
+        Sprite::FURRY_W3_2,
+        Sprite::FURRY_W3_3,
+        Sprite::FURRY_W3_4,
+        Sprite::FURRY_W3_5,
+        Sprite::FURRY_W3_6,
+        Sprite::FURRY_W3_7,
+        Sprite::FURRY_W3_8,
+        Sprite::FURRY_W4_1,
+        Sprite::FURRY_W4_2,
+        Sprite::FURRY_W4_3,
+        Sprite::FURRY_W4_4,
+        Sprite::FURRY_W4_5,
+        Sprite::FURRY_W4_6,
+        Sprite::FURRY_W4_7,
+        Sprite::FURRY_W4_8,
+        Sprite::FURRY_DIE_1,
+        Sprite::FURRY_DIE_2,
+        Sprite::FURRY_DIE_3,
+        Sprite::FURRY_DEAD,
+        Sprite::FURRY_JUMP1,
+        Sprite::FURRY_JUMP2,
+        Sprite::FURRY_JUMP3,
+        Sprite::BLUE_S_1,
+        Sprite::BLUE_S_2,
+        Sprite::BLUE_S_3,
+        Sprite::BLUE_S_4,
+        Sprite::BLUE_S_5,
+        Sprite::BLUE_S_6,
+        Sprite::BLUE_S_7,
+        Sprite::BLUE_S_8,
+        Sprite::BLUE_W1_1,
+        Sprite::BLUE_W1_2,
+        Sprite::BLUE_W1_3,
+        Sprite::BLUE_W1_4,
+        Sprite::BLUE_W1_5,
+        Sprite::BLUE_W1_6,
+        Sprite::BLUE_W1_7,
+        Sprite::BLUE_W1_8,
+        Sprite::BLUE_W2_1,
+        Sprite::BLUE_W2_2,
+        Sprite::BLUE_W2_3,
+        Sprite::BLUE_W2_4,
+        Sprite::BLUE_W2_5,
+        Sprite::BLUE_W2_6,
+        Sprite::BLUE_W2_7,
+        Sprite::BLUE_W2_8,
+        Sprite::BLUE_W3_1,
+        Sprite::BLUE_W3_2,
+        Sprite::BLUE_W3_3,
+        Sprite::BLUE_W3_4,
+        Sprite::BLUE_W3_5,
+        Sprite::BLUE_W3_6,
+        Sprite::BLUE_W3_7,
+        Sprite::BLUE_W3_8,
+        Sprite::BLUE_W4_1,
+        Sprite::BLUE_W4_2,
+        Sprite::BLUE_W4_3,
+        Sprite::BLUE_W4_4,
+        Sprite::BLUE_W4_5,
+        Sprite::BLUE_W4_6,
+        Sprite::BLUE_W4_7,
+        Sprite::BLUE_W4_8,
+        Sprite::BLUE_PAIN_1,
+        Sprite::BLUE_DIE_1,
+        Sprite::BLUE_DIE_2,
+        Sprite::BLUE_DIE_3,
+        Sprite::BLUE_PAIN_2,
+        Sprite::BLUE_DEAD,
+        Sprite::BLUE_SHOOT1,
+        Sprite::BLUE_SHOOT2,
+        Sprite::BLUE_SHOOT3,
+        Sprite::ROTTEN_S_1,
+        Sprite::ROTTEN_S_2,
+        Sprite::ROTTEN_S_3,
+        Sprite::ROTTEN_S_4,
+        Sprite::ROTTEN_S_5,
+        Sprite::ROTTEN_S_6,
+        Sprite::ROTTEN_S_7,
+        Sprite::ROTTEN_S_8,
+        Sprite::ROTTEN_W1_1,
+        Sprite::ROTTEN_W1_2,
+        Sprite::ROTTEN_W1_3,
+        Sprite::ROTTEN_W1_4,
+        Sprite::ROTTEN_W1_5,
+        Sprite::ROTTEN_W1_6,
+        Sprite::ROTTEN_W1_7,
+        Sprite::ROTTEN_W1_8,
+        Sprite::ROTTEN_W2_1,
+        Sprite::ROTTEN_W2_2,
+        Sprite::ROTTEN_W2_3,
+        Sprite::ROTTEN_W2_4,
+        Sprite::ROTTEN_W2_5,
+        Sprite::ROTTEN_W2_6,
+        Sprite::ROTTEN_W2_7,
+        Sprite::ROTTEN_W2_8,
+        Sprite::ROTTEN_W3_1,
+        Sprite::ROTTEN_W3_2,
+        Sprite::ROTTEN_W3_3,
+        Sprite::ROTTEN_W3_4,
+        Sprite::ROTTEN_W3_5,
+        Sprite::ROTTEN_W3_6,
+        Sprite::ROTTEN_W3_7,
+        Sprite::ROTTEN_W3_8,
+        Sprite::ROTTEN_W4_1,
+        Sprite::ROTTEN_W4_2,
+        Sprite::ROTTEN_W4_3,
+        Sprite::ROTTEN_W4_4,
+        Sprite::ROTTEN_W4_5,
+        Sprite::ROTTEN_W4_6,
+        Sprite::ROTTEN_W4_7,
+        Sprite::ROTTEN_W4_8,
+        Sprite::ROTTEN_PAIN_1,
+        Sprite::ROTTEN_DIE_1,
+        Sprite::ROTTEN_DIE_2,
+        Sprite::ROTTEN_DIE_3,
+        Sprite::ROTTEN_PAIN_2,
+        Sprite::ROTTEN_DIE_4,
+        Sprite::ROTTEN_DEAD,
+        Sprite::ROTTEN_SHOOT1,
+        Sprite::ROTTEN_SHOOT2,
+        Sprite::ROTTEN_SHOOT3,
+        Sprite::ROTTEN_SHOOT4,
+        Sprite::WHITE_S_1,
+        Sprite::WHITE_S_2,
+        Sprite::WHITE_S_3,
+        Sprite::WHITE_S_4,
+        Sprite::WHITE_S_5,
+        Sprite::WHITE_S_6,
+        Sprite::WHITE_S_7,
+        Sprite::WHITE_S_8,
+        Sprite::WHITE_W1_1,
+        Sprite::WHITE_W1_2,
+        Sprite::WHITE_W1_3,
+        Sprite::WHITE_W1_4,
+        Sprite::WHITE_W1_5,
+        Sprite::WHITE_W1_6,
+        Sprite::WHITE_W1_7,
+        Sprite::WHITE_W1_8,
+        Sprite::WHITE_W2_1,
+        Sprite::WHITE_W2_2,
+        Sprite::WHITE_W2_3,
+        Sprite::WHITE_W2_4,
+        Sprite::WHITE_W2_5,
+        Sprite::WHITE_W2_6,
+        Sprite::WHITE_W2_7,
+        Sprite::WHITE_W2_8,
+        Sprite::WHITE_W3_1,
+        Sprite::WHITE_W3_2,
+        Sprite::WHITE_W3_3,
+        Sprite::WHITE_W3_4,
+        Sprite::WHITE_W3_5,
+        Sprite::WHITE_W3_6,
+        Sprite::WHITE_W3_7,
+        Sprite::WHITE_W3_8,
+        Sprite::WHITE_W4_1,
+        Sprite::WHITE_W4_2,
+        Sprite::WHITE_W4_3,
+        Sprite::WHITE_W4_4,
+        Sprite::WHITE_W4_5,
+        Sprite::WHITE_W4_6,
+        Sprite::WHITE_W4_7,
+        Sprite::WHITE_W4_8,
+        Sprite::WHITE_PAIN_1,
+        Sprite::WHITE_DIE_1,
+        Sprite::WHITE_DIE_2,
+        Sprite::WHITE_DIE_3,
+        Sprite::WHITE_PAIN_2,
+        Sprite::WHITE_DIE_4,
+        Sprite::WHITE_DEAD,
+        Sprite::WHITE_SHOOT1,
+        Sprite::WHITE_SHOOT2,
+        Sprite::WHITE_SHOOT3,
+        Sprite::BLINKY_W1,
+        Sprite::BLINKY_W2,
+        Sprite::PINKY_W1,
+        Sprite::PINKY_W2,
+        Sprite::CLYDE_W1,
+        Sprite::CLYDE_W2,
+        Sprite::INKY_W1,
+        Sprite::INKY_W2,
+        Sprite::BOSS_W1,
+        Sprite::BOSS_W2,
+        Sprite::BOSS_W3,
+        Sprite::BOSS_W4,
+        Sprite::BOSS_SHOOT1,
+        Sprite::BOSS_SHOOT2,
+        Sprite::BOSS_SHOOT3,
+        Sprite::BOSS_DEAD,
+        Sprite::BOSS_DIE1,
+        Sprite::BOSS_DIE2,
+        Sprite::BOSS_DIE3,
+        Sprite::GOOD_DR_W1,
+        Sprite::GOOD_DR_W2,
+        Sprite::GOOD_DR_W3,
+        Sprite::GOOD_DR_W4,
+        Sprite::GOOD_DR_SHOOT1,
+        Sprite::GOOD_DR_SHOOT2,
+        Sprite::GOOD_DR_DIE1,
+        Sprite::GOOD_DR_DIE2,
+        Sprite::GOOD_DR_DIE3,
+        Sprite::GOOD_DR_DEAD,
+        Sprite::PARTY_O1,
+        Sprite::PARTY_O2,
+        Sprite::PARTY_O3,
+        Sprite::PARTY_O4,
+        Sprite::ALP_W1,
+        Sprite::ALP_W2,
+        Sprite::ALP_W3,
+        Sprite::ALP_W4,
+        Sprite::ALP_SHOOT,
+        Sprite::ALP_FIRE1,
+        Sprite::ALP_FIRE2,
+        Sprite::ALP_DIE1,
+        Sprite::ALP_DIE2,
+        Sprite::ALP_DIE3,
+        Sprite::ALP_DIE4,
+        Sprite::ALP_DIE5,
+        Sprite::ALP_DEAD,
+        Sprite::MECHA_W1,
+        Sprite::MECHA_W2,
+        Sprite::MECHA_W3,
+        Sprite::MECHA_W4,
+        Sprite::MECHA_SHOOT1,
+        Sprite::MECHA_SHOOT2,
+        Sprite::MECHA_SHOOT3,
+        Sprite::MECHA_DEAD,
+        Sprite::MECHA_DIE1,
+        Sprite::MECHA_DIE2,
+        Sprite::MECHA_DIE3,
+        Sprite::GROFAZ_W1,
+        Sprite::GROFAZ_W2,
+        Sprite::GROFAZ_W3,
+        Sprite::GROFAZ_W4,
+        Sprite::GROFAZ_SHOOT1,
+        Sprite::GROFAZ_SHOOT2,
+        Sprite::GROFAZ_SHOOT3,
+        Sprite::GROFAZ_DEAD,
+        Sprite::GROFAZ_DIE1,
+        Sprite::GROFAZ_DIE2,
+        Sprite::GROFAZ_DIE3,
+        Sprite::GROFAZ_DIE4,
+        Sprite::GROFAZ_DIE5,
+        Sprite::GROFAZ_DIE6,
+        Sprite::GROFAZ_DIE7,
+        Sprite::HERMANN_W1,
+        Sprite::HERMANN_W2,
+        Sprite::HERMANN_W3,
+        Sprite::HERMANN_W4,
+        Sprite::HERMANN_SHOOT1,
+        Sprite::HERMANN_SHOOT2,
+        Sprite::HERMANN_DIE1,
+        Sprite::HERMANN_DIE2,
+        Sprite::HERMANN_DIE3,
+        Sprite::HERMANN_DEAD,
+        Sprite::ROCKET_1,
+        Sprite::ROCKET_2,
+        Sprite::ROCKET_3,
+        Sprite::ROCKET_4,
+        Sprite::ROCKET_5,
+        Sprite::ROCKET_6,
+        Sprite::ROCKET_7,
+        Sprite::ROCKET_8,
+        Sprite::SMOKE_1,
+        Sprite::SMOKE_2,
+        Sprite::SMOKE_3,
+        Sprite::SMOKE_4,
+        Sprite::BOOM_1,
+        Sprite::BOOM_2,
+        Sprite::BOOM_3,
+        Sprite::GRETEL_W1,
+        Sprite::GRETEL_W2,
+        Sprite::GRETEL_W3,
+        Sprite::GRETEL_W4,
+        Sprite::GRETEL_SHOOT1,
+        Sprite::GRETEL_SHOOT2,
+        Sprite::GRETEL_SHOOT3,
+        Sprite::GRETEL_DEAD,
+        Sprite::GRETEL_DIE1,
+        Sprite::GRETEL_DIE2,
+        Sprite::GRETEL_DIE3,
+        Sprite::FETTGEISCHT_W1,
+        Sprite::FETTGEISCHT_W2,
+        Sprite::FETTGEISCHT_W3,
+        Sprite::FETTGEISCHT_W4,
+        Sprite::FETTGEISCHT_SHOOT1,
+        Sprite::FETTGEISCHT_SHOOT2,
+        Sprite::FETTGEISCHT_SHOOT3,
+        Sprite::FETTGEISCHT_SHOOT4,
+        Sprite::FETTGEISCHT_DIE1,
+        Sprite::FETTGEISCHT_DIE2,
+        Sprite::FETTGEISCHT_DIE3,
+        Sprite::FETTGEISCHT_DEAD,
+        Sprite::SELF1_W1,
+        Sprite::SELF_W2,
+        Sprite::SELF_W3,
+        Sprite::SELF_W4,
+        Sprite::SELF_JUMP1,
+        Sprite::SELF_JUMP2,
+        Sprite::SELF_JUMP3,
+        Sprite::SELF_JUMP4,
+        Sprite::SPR_KNIFEREADY,
+        Sprite::SPR_KNIFEATK1,
+        Sprite::SPR_KNIFEATK2,
+        Sprite::SPR_KNIFEATK3,
+        Sprite::SPR_KNIFEATK4,
+        Sprite::SPR_PISTOLREADY,
+        Sprite::SPR_PISTOLATK1,
+        Sprite::SPR_PISTOLATK2,
+        Sprite::SPR_PISTOLATK3,
+        Sprite::SPR_PISTOLATK4,
+        Sprite::SPR_MACHINEGUNREADY,
+        Sprite::SPR_MACHINEGUNATK1,
+        Sprite::SPR_MACHINEGUNATK2,
+        Sprite::SPR_MACHINEGUNATK3,
+        Sprite::SPR_MACHINEGUNATK4,
+        Sprite::SPR_CHAINREADY,
+        Sprite::SPR_CHAINATK1,
+        Sprite::SPR_CHAINATK2,
+        Sprite::SPR_CHAINATK3,
+        Sprite::SPR_CHAINATK4,
+    ];
+    pub const NAMES: [&'static str; 437] = [
+        "SPR_NONE",
+        "DEMO",
+        "DEATHCAM",
+        "STAT_0",
+        "STAT_1",
+        "STAT_2",
+        "STAT_3",
+        "STAT_4",
+        "STAT_5",
+        "STAT_6",
+        "STAT_7",
+        "STAT_8",
+        "STAT_9",
+        "STAT_10",
+        "STAT_11",
+        "STAT_12",
+        "STAT_13",
+        "STAT_14",
+        "STAT_15",
+        "STAT_16",
+        "STAT_17",
+        "STAT_18",
+        "STAT_19",
+        "STAT_20",
+        "STAT_21",
+        "STAT_22",
+        "STAT_23",
+        "STAT_24",
+        "STAT_25",
+        "STAT_26",
+        "STAT_27",
+        "STAT_28",
+        "STAT_29",
+        "STAT_30",
+        "STAT_31",
+        "STAT_32",
+        "STAT_33",
+        "STAT_34",
+        "STAT_35",
+        "STAT_36",
+        "STAT_37",
+        "STAT_38",
+        "STAT_39",
+        "STAT_40",
+        "STAT_41",
+        "STAT_42",
+        "STAT_43",
+        "STAT_44",
+        "STAT_45",
+        "STAT_46",
+        "STAT_47",
+        "BROWN_S_1",
+        "BROWN_S_2",
+        "BROWN_S_3",
+        "BROWN_S_4",
+        "BROWN_S_5",
+        "BROWN_S_6",
+        "BROWN_S_7",
+        "BROWN_S_8",
+        "BROWN_W1_1",
+        "BROWN_W1_2",
+        "BROWN_W1_3",
+        "BROWN_W1_4",
+        "BROWN_W1_5",
+        "BROWN_W1_6",
+        "BROWN_W1_7",
+        "BROWN_W1_8",
+        "BROWN_W2_1",
+        "BROWN_W2_2",
+        "BROWN_W2_3",
+        "BROWN_W2_4",
+        "BROWN_W2_5",
+        "BROWN_W2_6",
+        "BROWN_W2_7",
+        "BROWN_W2_8",
+        "BROWN_W3_1",
+        "BROWN_W3_2",
+        "BROWN_W3_3",
+        "BROWN_W3_4",
+        "BROWN_W3_5",
+        "BROWN_W3_6",
+        "BROWN_W3_7",
+        "BROWN_W3_8",
+        "BROWN_W4_1",
+        "BROWN_W4_2",
+        "BROWN_W4_3",
+        "BROWN_W4_4",
+        "BROWN_W4_5",
+        "BROWN_W4_6",
+        "BROWN_W4_7",
+        "BROWN_W4_8",
+        "BROWN_PAIN_1",
+        "BROWN_DIE_1",
+        "BROWN_DIE_2",
+        "BROWN_DIE_3",
+        "BROWN_PAIN_2",
+        "BROWN_DEAD",
+        "BROWN_SHOOT1",
+        "BROWN_SHOOT2",
+        "BROWN_SHOOT3",
+        "FURRY_W1_1",
+        "FURRY_W1_2",
+        "FURRY_W1_3",
+        "FURRY_W1_4",
+        "FURRY_W1_5",
+        "FURRY_W1_6",
+        "FURRY_W1_7",
+        "FURRY_W1_8",
+        "FURRY_W2_1",
+        "FURRY_W2_2",
+        "FURRY_W2_3",
+        "FURRY_W2_4",
+        "FURRY_W2_5",
+        "FURRY_W2_6",
+        "FURRY_W2_7",
+        "FURRY_W2_8",
+        "FURRY_W3_1",
+        "FURRY_W3_2",
+        "FURRY_W3_3",
+        "FURRY_W3_4",
+        "FURRY_W3_5",
+        "FURRY_W3_6",
+        "FURRY_W3_7",
+        "FURRY_W3_8",
+        "FURRY_W4_1",
+        "FURRY_W4_2",
+        "FURRY_W4_3",
+        "FURRY_W4_4",
+        "FURRY_W4_5",
+        "FURRY_W4_6",
+        "FURRY_W4_7",
+        "FURRY_W4_8",
+        "FURRY_DIE_1",
+        "FURRY_DIE_2",
+        "FURRY_DIE_3",
+        "FURRY_DEAD",
+        "FURRY_JUMP1",
+        "FURRY_JUMP2",
+        "FURRY_JUMP3",
+        "BLUE_S_1",
+        "BLUE_S_2",
+        "BLUE_S_3",
+        "BLUE_S_4",
+        "BLUE_S_5",
+        "BLUE_S_6",
+        "BLUE_S_7",
+        "BLUE_S_8",
+        "BLUE_W1_1",
+        "BLUE_W1_2",
+        "BLUE_W1_3",
+        "BLUE_W1_4",
+        "BLUE_W1_5",
+        "BLUE_W1_6",
+        "BLUE_W1_7",
+        "BLUE_W1_8",
+        "BLUE_W2_1",
+        "BLUE_W2_2",
+        "BLUE_W2_3",
+        "BLUE_W2_4",
+        "BLUE_W2_5",
+        "BLUE_W2_6",
+        "BLUE_W2_7",
+        "BLUE_W2_8",
+        "BLUE_W3_1",
+        "BLUE_W3_2",
+        "BLUE_W3_3",
+        "BLUE_W3_4",
+        "BLUE_W3_5",
+        "BLUE_W3_6",
+        "BLUE_W3_7",
+        "BLUE_W3_8",
+        "BLUE_W4_1",
+        "BLUE_W4_2",
+        "BLUE_W4_3",
+        "BLUE_W4_4",
+        "BLUE_W4_5",
+        "BLUE_W4_6",
+        "BLUE_W4_7",
+        "BLUE_W4_8",
+        "BLUE_PAIN_1",
+        "BLUE_DIE_1",
+        "BLUE_DIE_2",
+        "BLUE_DIE_3",
+        "BLUE_PAIN_2",
+        "BLUE_DEAD",
+        "BLUE_SHOOT1",
+        "BLUE_SHOOT2",
+        "BLUE_SHOOT3",
+        "ROTTEN_S_1",
+        "ROTTEN_S_2",
+        "ROTTEN_S_3",
+        "ROTTEN_S_4",
+        "ROTTEN_S_5",
+        "ROTTEN_S_6",
+        "ROTTEN_S_7",
+        "ROTTEN_S_8",
+        "ROTTEN_W1_1",
+        "ROTTEN_W1_2",
+        "ROTTEN_W1_3",
+        "ROTTEN_W1_4",
+        "ROTTEN_W1_5",
+        "ROTTEN_W1_6",
+        "ROTTEN_W1_7",
+        "ROTTEN_W1_8",
+        "ROTTEN_W2_1",
+        "ROTTEN_W2_2",
+        "ROTTEN_W2_3",
+        "ROTTEN_W2_4",
+        "ROTTEN_W2_5",
+        "ROTTEN_W2_6",
+        "ROTTEN_W2_7",
+        "ROTTEN_W2_8",
+        "ROTTEN_W3_1",
+        "ROTTEN_W3_2",
+        "ROTTEN_W3_3",
+        "ROTTEN_W3_4",
+        "ROTTEN_W3_5",
+        "ROTTEN_W3_6",
+        "ROTTEN_W3_7",
+        "ROTTEN_W3_8",
+        "ROTTEN_W4_1",
+        "ROTTEN_W4_2",
+        "ROTTEN_W4_3",
+        "ROTTEN_W4_4",
+        "ROTTEN_W4_5",
+        "ROTTEN_W4_6",
+        "ROTTEN_W4_7",
+        "ROTTEN_W4_8",
+        "ROTTEN_PAIN_1",
+        "ROTTEN_DIE_1",
+        "ROTTEN_DIE_2",
+        "ROTTEN_DIE_3",
+        "ROTTEN_PAIN_2",
+        "ROTTEN_DIE_4",
+        "ROTTEN_DEAD",
+        "ROTTEN_SHOOT1",
+        "ROTTEN_SHOOT2",
+        "ROTTEN_SHOOT3",
+        "ROTTEN_SHOOT4",
+        "WHITE_S_1",
+        "WHITE_S_2",
+        "WHITE_S_3",
+        "WHITE_S_4",
+        "WHITE_S_5",
+        "WHITE_S_6",
+        "WHITE_S_7",
+        "WHITE_S_8",
+        "WHITE_W1_1",
+        "WHITE_W1_2",
+        "WHITE_W1_3",
+        "WHITE_W1_4",
+        "WHITE_W1_5",
+        "WHITE_W1_6",
+        "WHITE_W1_7",
+        "WHITE_W1_8",
+        "WHITE_W2_1",
+        "WHITE_W2_2",
+        "WHITE_W2_3",
+        "WHITE_W2_4",
+        "WHITE_W2_5",
+        "WHITE_W2_6",
+        "WHITE_W2_7",
+        "WHITE_W2_8",
+        "WHITE_W3_1",
+        "WHITE_W3_2",
+        "WHITE_W3_3",
+        "WHITE_W3_4",
+        "WHITE_W3_5",
+        "WHITE_W3_6",
+        "WHITE_W3_7",
+        "WHITE_W3_8",
+        "WHITE_W4_1",
+        "WHITE_W4_2",
+        "WHITE_W4_3",
+        "WHITE_W4_4",
+        "WHITE_W4_5",
+        "WHITE_W4_6",
+        "WHITE_W4_7",
+        "WHITE_W4_8",
+        "WHITE_PAIN_1",
+        "WHITE_DIE_1",
+        "WHITE_DIE_2",
+        "WHITE_DIE_3",
+        "WHITE_PAIN_2",
+        "WHITE_DIE_4",
+        "WHITE_DEAD",
+        "WHITE_SHOOT1",
+        "WHITE_SHOOT2",
+        "WHITE_SHOOT3",
+        "BLINKY_W1",
+        "BLINKY_W2",
+        "PINKY_W1",
+        "PINKY_W2",
+        "CLYDE_W1",
+        "CLYDE_W2",
+        "INKY_W1",
+        "INKY_W2",
+        "BOSS_W1",
+        "BOSS_W2",
+        "BOSS_W3",
+        "BOSS_W4",
+        "BOSS_SHOOT1",
+        "BOSS_SHOOT2",
+        "BOSS_SHOOT3",
+        "BOSS_DEAD",
+        "BOSS_DIE1",
+        "BOSS_DIE2",
+        "BOSS_DIE3",
+        "GOOD_DR_W1",
+        "GOOD_DR_W2",
+        "GOOD_DR_W3",
+        "GOOD_DR_W4",
+        "GOOD_DR_SHOOT1",
+        "GOOD_DR_SHOOT2",
+        "GOOD_DR_DIE1",
+        "GOOD_DR_DIE2",
+        "GOOD_DR_DIE3",
+        "GOOD_DR_DEAD",
+        "PARTY_O1",
+        "PARTY_O2",
+        "PARTY_O3",
+        "PARTY_O4",
+        "ALP_W1",
+        "ALP_W2",
+        "ALP_W3",
+        "ALP_W4",
+        "ALP_SHOOT",
+        "ALP_FIRE1",
+        "ALP_FIRE2",
+        "ALP_DIE1",
+        "ALP_DIE2",
+        "ALP_DIE3",
+        "ALP_DIE4",
+        "ALP_DIE5",
+        "ALP_DEAD",
+        "MECHA_W1",
+        "MECHA_W2",
+        "MECHA_W3",
+        "MECHA_W4",
+        "MECHA_SHOOT1",
+        "MECHA_SHOOT2",
+        "MECHA_SHOOT3",
+        "MECHA_DEAD",
+        "MECHA_DIE1",
+        "MECHA_DIE2",
+        "MECHA_DIE3",
+        "GROFAZ_W1",
+        "GROFAZ_W2",
+        "GROFAZ_W3",
+        "GROFAZ_W4",
+        "GROFAZ_SHOOT1",
+        "GROFAZ_SHOOT2",
+        "GROFAZ_SHOOT3",
+        "GROFAZ_DEAD",
+        "GROFAZ_DIE1",
+        "GROFAZ_DIE2",
+        "GROFAZ_DIE3",
+        "GROFAZ_DIE4",
+        "GROFAZ_DIE5",
+        "GROFAZ_DIE6",
+        "GROFAZ_DIE7",
+        "HERMANN_W1",
+        "HERMANN_W2",
+        "HERMANN_W3",
+        "HERMANN_W4",
+        "HERMANN_SHOOT1",
+        "HERMANN_SHOOT2",
+        "HERMANN_DIE1",
+        "HERMANN_DIE2",
+        "HERMANN_DIE3",
+        "HERMANN_DEAD",
+        "ROCKET_1",
+        "ROCKET_2",
+        "ROCKET_3",
+        "ROCKET_4",
+        "ROCKET_5",
+        "ROCKET_6",
+        "ROCKET_7",
+        "ROCKET_8",
+        "SMOKE_1",
+        "SMOKE_2",
+        "SMOKE_3",
+        "SMOKE_4",
+        "BOOM_1",
+        "BOOM_2",
+        "BOOM_3",
+        "GRETEL_W1",
+        "GRETEL_W2",
+        "GRETEL_W3",
+        "GRETEL_W4",
+        "GRETEL_SHOOT1",
+        "GRETEL_SHOOT2",
+        "GRETEL_SHOOT3",
+        "GRETEL_DEAD",
+        "GRETEL_DIE1",
+        "GRETEL_DIE2",
+        "GRETEL_DIE3",
+        "FETTGEISCHT_W1",
+        "FETTGEISCHT_W2",
+        "FETTGEISCHT_W3",
+        "FETTGEISCHT_W4",
+        "FETTGEISCHT_SHOOT1",
+        "FETTGEISCHT_SHOOT2",
+        "FETTGEISCHT_SHOOT3",
+        "FETTGEISCHT_SHOOT4",
+        "FETTGEISCHT_DIE1",
+        "FETTGEISCHT_DIE2",
+        "FETTGEISCHT_DIE3",
+        "FETTGEISCHT_DEAD",
+        "SELF1_W1",
+        "SELF_W2",
+        "SELF_W3",
+        "SELF_W4",
+        "SELF_JUMP1",
+        "SELF_JUMP2",
+        "SELF_JUMP3",
+        "SELF_JUMP4",
+        "SPR_KNIFEREADY",
+        "SPR_KNIFEATK1",
+        "SPR_KNIFEATK2",
+        "SPR_KNIFEATK3",
+        "SPR_KNIFEATK4",
+        "SPR_PISTOLREADY",
+        "SPR_PISTOLATK1",
+        "SPR_PISTOLATK2",
+        "SPR_PISTOLATK3",
+        "SPR_PISTOLATK4",
+        "SPR_MACHINEGUNREADY",
+        "SPR_MACHINEGUNATK1",
+        "SPR_MACHINEGUNATK2",
+        "SPR_MACHINEGUNATK3",
+        "SPR_MACHINEGUNATK4",
+        "SPR_CHAINREADY",
+        "SPR_CHAINATK1",
+        "SPR_CHAINATK2",
+        "SPR_CHAINATK3",
+        "SPR_CHAINATK4",
+    ];
+
+    pub const fn id(self) -> i32 {
+        self as i32
+    }
+    pub fn from_id(id: i32) -> Option<Sprite> {
+        Self::ALL.get(usize::try_from(id).ok()?).copied()
+    }
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+    pub fn from_name(name: &str) -> Option<Sprite> {
+        let index = Self::NAMES.iter().position(|n| *n == name)?;
+        Some(Self::ALL[index])
+    }
+}
+
+/// Traps declared with `trap door.<name> = <number>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DoorTrap {
+    GetOpen = 0,
+    SetOpen = 1,
+    Trigger = 2,
+    Blocked = 3,
+    PlaySound = 4,
+}
+
+impl TryFrom<u8> for DoorTrap {
+    type Error = u8;
+
+    fn try_from(value: u8) -> ::std::result::Result<Self, u8> {
+        match value {
+            0 => Ok(DoorTrap::GetOpen),
+            1 => Ok(DoorTrap::SetOpen),
+            2 => Ok(DoorTrap::Trigger),
+            3 => Ok(DoorTrap::Blocked),
+            4 => Ok(DoorTrap::PlaySound),
+            x => Err(x),
+        }
+    }
+}
+
+/// Traps declared with `trap enemy.<name> = <number>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EnemyTrap {
+    PlayRandomSound = 0,
+}
+
+impl TryFrom<u8> for EnemyTrap {
+    type Error = u8;
+
+    fn try_from(value: u8) -> ::std::result::Result<Self, u8> {
+        match value {
+            0 => Ok(EnemyTrap::PlayRandomSound),
+            x => Err(x),
+        }
+    }
+}
+
+/// Traps declared with `trap weapon.<name> = <number>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WeaponTrap {
+    Fire = 0,
+    AmmoDepleted = 1,
+    ReadyState = 2,
+    PlaySound = 4,
+}
+
+impl TryFrom<u8> for WeaponTrap {
+    type Error = u8;
+
+    fn try_from(value: u8) -> ::std::result::Result<Self, u8> {
+        match value {
+            0 => Ok(WeaponTrap::Fire),
+            1 => Ok(WeaponTrap::AmmoDepleted),
+            2 => Ok(WeaponTrap::ReadyState),
+            4 => Ok(WeaponTrap::PlaySound),
+            x => Err(x),
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub struct Player {
     pub shoot_timeout: i32,
     pub weapon: Weapon,
     pub health: i32,
+    pub score: i32,
 }
 
 #[derive(Debug)]
@@ -36,6 +37,7 @@ impl Default for Player {
             shoot_timeout: 0,
             weapon: Default::default(),
             health: 100,
+            score: 0,
         }
     }
 }
@@ -50,6 +52,7 @@ impl Writable for Player {
         w.writei32(self.shoot_timeout)?;
         self.weapon.write(w)?;
         w.writei32(self.health)?;
+        w.writei32(self.score)?;
         Ok(())
     }
 }
@@ -64,6 +67,7 @@ impl Loadable for Player {
         let shoot_timeout = r.readi32()?;
         let weapon = Weapon::read_from(r)?;
         let health = r.readi32()?;
+        let score = r.readi32()?;
         Ok(Self {
             x,
             y,
@@ -73,6 +77,7 @@ impl Loadable for Player {
             shoot_timeout,
             weapon,
             health,
+            score,
         })
     }
 }
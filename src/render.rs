@@ -385,6 +385,7 @@ fn raycast_test() {
         shoot_timeout: 0,
         weapon: Default::default(),
         health: 100,
+        score: 0,
     };
     let col = 10;
     sweep_raycast(
@@ -27,9 +27,11 @@ impl Actor {
         }
     }
 
-    pub fn shoot(&mut self, hitpoints: i32) {
-        if let Actor::Enemy { enemy } = self {
-            enemy.hit(hitpoints)
+    /// Returns the points scored if the hit was fatal
+    pub fn shoot(&mut self, hitpoints: i32) -> i32 {
+        match self {
+            Actor::Enemy { enemy } => enemy.hit(hitpoints),
+            _ => 0,
         }
     }
 }
@@ -84,7 +86,10 @@ impl ms::Writable for Thing {
 impl ms::Loadable for Thing {
     fn read_from(r: &mut dyn std::io::Read) -> Result<Self> {
         let unique_id = r.readi32()? as usize;
-        let actor = Actor::read_from(r)?;
+        let mut actor = Actor::read_from(r)?;
+        if let Actor::Enemy { enemy } = &mut actor {
+            enemy.exec_ctx.actor = Some(unique_id);
+        }
         Ok(Self { actor, unique_id })
     }
 }
@@ -110,12 +115,14 @@ impl ms::Writable for Things {
 impl Things {
     fn spawn_from_thing_def(
         thing_def: &ThingDef,
+        difficulty: Difficulty,
         blockmap: &mut BlockMap,
         unique_id: usize,
     ) -> Option<Thing> {
         let thing = match &thing_def.thing_type {
             ThingType::Enemy(enemy_spawn_info) => {
-                let enemy = Enemy::spawn(enemy_spawn_info, thing_def);
+                let mut enemy = Enemy::spawn(enemy_spawn_info, thing_def, difficulty);
+                enemy.exec_ctx.actor = Some(unique_id);
 
                 blockmap.insert(unique_id, enemy.x, enemy.y);
                 Thing {
@@ -160,7 +167,12 @@ impl Things {
         let mut things = Vec::new();
         let mut blockmap = BlockMap::default();
         for (i, thing_def) in thing_defs.thing_defs.iter().enumerate() {
-            let thing = match Self::spawn_from_thing_def(thing_def, &mut blockmap, i) {
+            let thing = match Self::spawn_from_thing_def(
+                thing_def,
+                thing_defs.difficulty,
+                &mut blockmap,
+                i,
+            ) {
                 Some(value) => value,
                 None => continue,
             };
@@ -178,11 +190,13 @@ impl Things {
         }
     }
 
+    /// Update the things for which `select(unique_id)` is true
     pub fn update(
         &mut self,
         player: &mut Player,
         map_dynamic: &mut Map,
         audio_service: &mut dyn AudioService,
+        select: impl Fn(usize) -> bool,
     ) {
         // temporarily take out things during mutation
         let mut things = std::mem::take(&mut self.things);
@@ -190,6 +204,9 @@ impl Things {
         let mut spawn_thing_defs = Vec::new();
 
         for thing in &mut things {
+            if !select(thing.unique_id) {
+                continue;
+            }
             // let thing_def = &self.thing_defs.thing_defs[thing.static_index];
             #[allow(clippy::single_match)]
             match &mut thing.actor {
@@ -250,23 +267,10 @@ impl Things {
                     } else {
                         self.blockmap.remove(thing.unique_id, old_x, old_y);
 
-                        let thing_def = self.thing_defs.thing_defs.get(thing.unique_id);
-                        match thing_def {
-                            Some(ThingDef {
-                                thing_type:
-                                    ThingType::Enemy(EnemySpawnInfo {
-                                        spawn_on_death: Some(id),
-                                        ..
-                                    }),
-                                ..
-                            }) => {
-                                if let Some(thing_def) =
-                                    ThingDef::from_map_id(*id, enemy.x, enemy.y)
-                                {
-                                    spawn_thing_defs.push(thing_def);
-                                }
-                            }
-                            _ => (),
+                        if let Some(thing_def) = enemy.info().drop.and_then(|id| {
+                            ThingDef::from_map_id(id, enemy.x, enemy.y, self.thing_defs.difficulty)
+                        }) {
+                            spawn_thing_defs.push(thing_def);
                         }
                     }
 
@@ -295,9 +299,12 @@ impl Things {
         // }
         for thing_def in spawn_thing_defs {
             // TODO: rethink: as long as nothing is ever deleted from things this is probably good enough
-            if let Some(thing) =
-                Self::spawn_from_thing_def(&thing_def, &mut self.blockmap, things.len())
-            {
+            if let Some(thing) = Self::spawn_from_thing_def(
+                &thing_def,
+                self.thing_defs.difficulty,
+                &mut self.blockmap,
+                things.len(),
+            ) {
                 things.push(thing);
             }
         }
@@ -318,7 +325,7 @@ impl Things {
                         collected: false,
                         item,
                     } => Some(SpriteDef {
-                        id: sprite::SpriteIndex::Undirectional(item.id - 22 + 2),
+                        id: sprite::SpriteIndex::Undirectional(prop_sprite(item.id)),
                         x: item.x,
                         y: item.y,
                         owner: i,
@@ -360,18 +367,26 @@ impl Things {
     }
 }
 
-fn try_to_collectible(sprite_index: i32) -> Option<Collectible> {
-    Some(match sprite_index {
-        29 => Collectible::DogFood,
-        43 => Collectible::Key(0),
-        44 => Collectible::Key(1),
-        47 => Collectible::Food,
-        48 => Collectible::Medkit,
-        49 => Collectible::Ammo,
-        50 => Collectible::Machinegun,
-        51 => Collectible::Chaingun,
-        52..=55 => Collectible::Treasure(sprite_index - 52),
-        56 => Collectible::LifeUp,
+/// Sprite of the static object with the map object code `map_code` (23..=71)
+fn prop_sprite(map_code: i32) -> i32 {
+    Sprite::STAT_0.id() + map_code - 23
+}
+
+fn try_to_collectible(map_code: i32) -> Option<Collectible> {
+    let sprite = Sprite::from_id(prop_sprite(map_code))?;
+    Some(match sprite {
+        Sprite::STAT_6 => Collectible::DogFood,
+        Sprite::STAT_20 => Collectible::Key(0),
+        Sprite::STAT_21 => Collectible::Key(1),
+        Sprite::STAT_24 => Collectible::Food,
+        Sprite::STAT_25 => Collectible::Medkit,
+        Sprite::STAT_26 => Collectible::Ammo,
+        Sprite::STAT_27 => Collectible::Machinegun,
+        Sprite::STAT_28 => Collectible::Chaingun,
+        Sprite::STAT_29 | Sprite::STAT_30 | Sprite::STAT_31 | Sprite::STAT_32 => {
+            Collectible::Treasure(sprite.id() - Sprite::STAT_29.id())
+        }
+        Sprite::STAT_33 => Collectible::LifeUp,
         _ => return None,
     })
 }
@@ -1,19 +1,52 @@
 use crate::{fa::FA_FRAC_PI_4, prelude::*};
+use anyhow::anyhow;
 
-#[derive(Clone, Copy, Debug)]
+/// Skill level. Easy stands in for the two lowest levels of the original: it spawns the easy
+/// enemies and the player only takes a quarter of the damage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Difficulty {
     Easy,
+    #[default]
     Medium,
     Hard,
 }
 
-pub struct EnemyCapabilities {
-    pub can_open_doors: bool,
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+    /// Index into per skill tables like `EnemyInfo::health`, see also `EnemySpawnInfo::skill`
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    pub fn scale_damage(self, hitpoints: i32) -> i32 {
+        match self {
+            Difficulty::Easy => hitpoints >> 2,
+            Difficulty::Medium | Difficulty::Hard => hitpoints,
+        }
+    }
 }
 
-pub fn get_capabilities_by_name(name: &str) -> EnemyCapabilities {
-    EnemyCapabilities {
-        can_open_doors: name != "furry",
+impl ms::Loadable for Difficulty {
+    fn read_from(r: &mut dyn std::io::Read) -> Result<Self> {
+        Ok(match r.readu8()? {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Medium,
+            2 => Difficulty::Hard,
+            x => return Err(anyhow!("unrecognized Difficulty discriminator {x}")),
+        })
+    }
+}
+
+impl ms::Writable for Difficulty {
+    fn write(&self, w: &mut dyn std::io::Write) -> Result<()> {
+        w.writeu8(self.index() as u8)?;
+        Ok(())
     }
 }
 
@@ -45,9 +78,12 @@ pub struct ThingDef {
 }
 
 impl ThingDef {
-    pub fn from_map_id(c: i32, x: Fp16, y: Fp16) -> Option<ThingDef> {
-        let thing_type = if let Some(spawn_info) = IMG_WL6.spawn_infos.find_spawn_info(c) {
+    pub fn from_map_id(c: i32, x: Fp16, y: Fp16, difficulty: Difficulty) -> Option<ThingDef> {
+        let thing_type = if let Some(spawn_info) = img_wl6().spawn_infos.find_spawn_info(c) {
             // println!("spawn info: {spawn_info:?}");
+            if spawn_info.skill as usize > difficulty.index() {
+                return None;
+            }
             ThingType::Enemy(spawn_info.clone())
         } else {
             match c {
@@ -66,10 +102,11 @@ impl ThingDef {
 
 pub struct ThingDefs {
     pub thing_defs: Vec<ThingDef>,
+    pub difficulty: Difficulty,
 }
 
 impl ThingDefs {
-    pub fn from_map_plane(plane: &[u16]) -> Self {
+    pub fn from_map_plane(plane: &[u16], difficulty: Difficulty) -> Self {
         let mut plane_iter = plane.iter();
         let mut thing_defs = Vec::new();
 
@@ -79,14 +116,17 @@ impl ThingDefs {
                 let x = FP16_HALF + x.into();
                 let y = FP16_HALF + y.into();
 
-                let thing_def = match ThingDef::from_map_id(c, x, y) {
+                let thing_def = match ThingDef::from_map_id(c, x, y, difficulty) {
                     Some(value) => value,
                     None => continue,
                 };
                 thing_defs.push(thing_def);
             }
         }
-        ThingDefs { thing_defs }
+        ThingDefs {
+            thing_defs,
+            difficulty,
+        }
     }
 
     // keep for reference:
@@ -189,7 +189,7 @@ pub struct Voxel {
 impl Voxel {
     pub fn spawn(spawn_info: SpawnInfo, res: &res::VoxelRes) -> Voxel {
         match spawn_info {
-            SpawnInfo::StartLevel(index, _) => {
+            SpawnInfo::StartLevel(index, _, _) => {
                 let map = res.get_map(index as usize).unwrap();
                 let camera = Camera::spawn_at(&map, 0.0, 0.0);
 
@@ -290,9 +290,9 @@ impl Voxel {
 
     pub fn deconstruct(&self, input_state: &InputState) -> SpawnInfo {
         if input_state.next_level {
-            return SpawnInfo::StartLevel(self.level.wrapping_add(1), None);
+            return SpawnInfo::StartLevel(self.level.wrapping_add(1), Default::default(), None);
         } else if input_state.prev_level {
-            return SpawnInfo::StartLevel(self.level.saturating_sub(1), None);
+            return SpawnInfo::StartLevel(self.level.saturating_sub(1), Default::default(), None);
         }
         todo!()
     }
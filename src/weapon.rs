@@ -1,9 +1,10 @@
-use std::io::Cursor;
-
-use crate::prelude::*;
 use crate::sprite::SpriteSceenSetup;
+use crate::{enums::WeaponTrap, prelude::*};
 use anyhow::anyhow;
-use state_bc::opcode::Value;
+use state_bc::{
+    host::{run_block, Host, TrapHandler},
+    opcode::Value,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum WeaponType {
@@ -77,12 +78,61 @@ impl Default for Weapon {
             // selected_weapon: WeaponType::Gun,
             selected_weapon: WeaponType::Machinegun,
             ammo: 99,
-            exec_ctx: ExecCtx::new("weapon_gun::ready", &IMG_WL6).unwrap(),
+            exec_ctx: ExecCtx::new("weapon_gun::ready", img_wl6()).unwrap(),
             shoot: false,
         }
     }
 }
 
+/// Weapon plus the per-update inputs its bytecode needs
+struct WeaponHost<'a> {
+    weapon: &'a mut Weapon,
+    fire: bool,
+    audio_service: &'a mut dyn AudioService,
+}
+
+impl TrapHandler for WeaponHost<'_> {
+    type Trap = WeaponTrap;
+
+    fn trap(&mut self, trap: WeaponTrap, env: &mut opcode::Env) -> Result<()> {
+        let weapon = &mut self.weapon;
+        match trap {
+            WeaponTrap::Fire => env.stack.push(opcode::Value::Bool(self.fire)),
+            WeaponTrap::AmmoDepleted => env.stack.push(opcode::Value::Bool(
+                self.fire && weapon.selected_weapon != WeaponType::Knife && weapon.ammo <= 0,
+            )),
+            WeaponTrap::ReadyState => {
+                let label = weapon.selected_weapon.map_state_label("ready");
+                match weapon.exec_ctx.image.get_state_offs_by_label(&label) {
+                    Some(offs) => env.stack.push(Value::I32(offs)),
+                    None => return Err(anyhow!("could not find ready state {label}")),
+                }
+            }
+            WeaponTrap::PlaySound => {
+                if let Some(Value::U8(snd_id)) = env.stack.pop() {
+                    self.audio_service.play_sound(snd_id as i32);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Host for WeaponHost<'_> {
+    fn exec_ctx(&mut self) -> &mut ExecCtx {
+        &mut self.weapon.exec_ctx
+    }
+
+    fn dispatch_call(&mut self, function: Function) -> Result<()> {
+        self.weapon.dispatch_call(function, self.fire);
+        Ok(())
+    }
+
+    fn safe_label(&mut self) -> Option<String> {
+        Some(self.weapon.selected_weapon.map_state_label("ready"))
+    }
+}
+
 impl Weapon {
     fn exec_code(
         &mut self,
@@ -90,53 +140,16 @@ impl Weapon {
         fire: bool,
         audio_service: &mut dyn AudioService,
     ) -> Result<()> {
-        let mut env = opcode::Env::default();
-        let mut cursor = Cursor::new(&self.exec_ctx.image.code[code_offs as usize..]);
-        loop {
-            let state = opcode::exec(&mut cursor, &mut env).expect("error on bytecode exec");
-            match state {
-                opcode::Event::Stop => break,
-                opcode::Event::Call(function) => {
-                    self.dispatch_call(function, fire);
-                }
-                opcode::Event::Trap => {
-                    match env.stack.pop() {
-                        Some(Value::U8(0)) => env.stack.push(opcode::Value::Bool(fire)),
-                        Some(Value::U8(1)) => env.stack.push(opcode::Value::Bool(
-                            fire && self.selected_weapon != WeaponType::Knife && self.ammo <= 0,
-                        )),
-                        Some(Value::U8(2)) => {
-                            if let Some(offs) = self.exec_ctx.image.get_state_offs_by_label(
-                                &self.selected_weapon.map_state_label("ready"),
-                            ) {
-                                env.stack.push(Value::I32(offs));
-                            } else {
-                                panic!("could not find ready state for current weapon");
-                            }
-                        }
-                        Some(Value::U8(4)) => {
-                            if let Some(Value::U8(snd_id)) = env.stack.pop() {
-                                audio_service.play_sound(snd_id as i32);
-                            }
-                        }
-                        x => panic!("unexpected trap code {x:?}"),
-                    }
-                }
-                opcode::Event::GoState => {
-                    match env.stack.pop() {
-                        Some(Value::I32(offs)) => {
-                            self.exec_ctx
-                                .jump(offs)
-                                .unwrap_or_else(|_| panic!("jump to {offs} failed."));
-                        }
-                        Some(x) => return Err(anyhow!("unhandled opcode::Value {x:?}")),
-                        None => return Err(anyhow!("stack underflow")),
-                    };
-                    break;
-                }
-            }
-        }
-        Ok(())
+        let image = self.exec_ctx.image.clone();
+        run_block(
+            &image,
+            code_offs,
+            &mut WeaponHost {
+                weapon: self,
+                fire,
+                audio_service,
+            },
+        )
     }
     fn dispatch_call(&mut self, function: Function, _fire: bool) {
         match function {
@@ -172,14 +185,21 @@ impl Weapon {
             }
         }
 
-        if self.exec_ctx.state.ticks <= 0 {
-            self.exec_code(self.exec_ctx.state.action_offs, fire, audio_service)
+        // a block waiting in WAIT holds the state until it finishes
+        let block = match self.exec_ctx.suspended_block() {
+            Some(block) => Some(block),
+            None => (self.exec_ctx.state.ticks <= 0).then_some(self.exec_ctx.state.action_offs),
+        };
+        if let Some(block) = block {
+            self.exec_code(block, fire, audio_service)
                 .expect("exec_code failed.");
+        }
+        if self.exec_ctx.suspended.is_none() {
             if self.exec_ctx.state.ticks <= 0 {
                 self.exec_ctx.jump(self.exec_ctx.state.next).unwrap();
             }
+            self.exec_ctx.state.ticks -= 1;
         }
-        self.exec_ctx.state.ticks -= 1;
 
         if self.shoot && self.selected_weapon != WeaponType::Knife {
             self.ammo -= 1;
@@ -205,7 +225,7 @@ impl ms::Loadable for Weapon {
         Ok(Self {
             ammo: r.readi32()?,
             selected_weapon: WeaponType::read_from(r)?,
-            exec_ctx: ExecCtx::read_from(r, &IMG_WL6)?,
+            exec_ctx: ExecCtx::read_from(r, img_wl6())?,
             shoot: false,
         })
     }
@@ -303,6 +303,9 @@ impl MapsFile {
 
         (to_plane(&d0), to_plane(&d1))
     }
+    pub fn num_maps(&self) -> i32 {
+        self.map_headers.len() as i32
+    }
     pub fn get_map_name(&self, id: i32) -> &str {
         self.map_headers[id as usize].name.as_str()
     }